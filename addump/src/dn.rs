@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+
+use esedb::data::Data;
+use esedb::table::{Table, Value};
+
+use crate::schema::{
+    Attribute, extract_dnt, find_column_id, get_first_value, RDN_TYPE_COLUMN_NAME, SchemaConfig, SchemaError,
+};
+
+
+/// The maximum number of RDNs in a distinguished name; protects against cycles in the parent links.
+const MAX_DN_DEPTH: usize = 256;
+
+
+/// Escapes the value of a relative distinguished name according to RFC 4514.
+pub fn escape_rdn_value(value: &str) -> String {
+    let last_index = value.chars().count().saturating_sub(1);
+    let mut ret = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\0' => {
+                ret.push_str("\\00");
+                continue;
+            },
+            '"'|'+'|','|';'|'<'|'='|'>'|'\\' => ret.push('\\'),
+            '#'|' ' if i == 0 => ret.push('\\'),
+            ' ' if i == last_index => ret.push('\\'),
+            _ => {},
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+
+/// Builds the distinguished names of all objects in the datatable, keyed by DNT.
+///
+/// Each object's RDN is assembled from its RDN type and naming attribute, then prefixed to the DN of
+/// its parent. If a parent is missing or nameless, or the parent links form a cycle, the DN stops at
+/// the last object that could be resolved. Objects without a name receive no DN. The root object
+/// (as given by the configuration) has the empty DN.
+pub fn build_dn_map<'a, A: IntoIterator<Item = &'a Attribute>>(data_table: &Table, data_rows: &[BTreeMap<i32, Value>], attributes: A, config: &SchemaConfig) -> Result<BTreeMap<i32, String>, SchemaError> {
+    let dnt_column_index = find_column_id(data_table, &config.dnt_column_name, "key (DNT)")?;
+    let parent_dnt_column_index = find_column_id(data_table, &config.parent_dnt_column_name, "parent reference")?;
+    let rdn_type_column_index = find_column_id(data_table, RDN_TYPE_COLUMN_NAME, "RDN type")?;
+    let name_column_index = find_column_id(data_table, &config.top_object_name_column_name, "naming")?;
+
+    let attribute_id_to_name: BTreeMap<i32, String> = attributes.into_iter()
+        .map(|a| (a.id, a.ldap_name.to_uppercase()))
+        .collect();
+
+    // collect the RDN and the parent of each named object
+    let mut dnt_to_rdn_and_parent: BTreeMap<i32, (String, Option<i32>)> = BTreeMap::new();
+    for row in data_rows {
+        let Some(dnt_value) = row.get(&dnt_column_index) else { continue };
+        let dnt = extract_dnt(dnt_value);
+        let Some(name) = get_first_value(row, name_column_index).and_then(Data::as_str) else { continue };
+        let rdn_type = match get_first_value(row, rdn_type_column_index) {
+            Some(Data::Long(rdn_type_id)) => attribute_id_to_name.get(rdn_type_id)
+                .map(|n| n.as_str())
+                .unwrap_or("CN"),
+            _ => "CN",
+        };
+        let rdn = format!("{}={}", rdn_type, escape_rdn_value(name));
+        let parent_dnt = row.get(&parent_dnt_column_index)
+            .map(extract_dnt);
+        dnt_to_rdn_and_parent.insert(dnt, (rdn, parent_dnt));
+    }
+
+    let mut dnt_to_dn = BTreeMap::new();
+    dnt_to_dn.insert(config.root_object_dnt, String::new());
+    for &start_dnt in dnt_to_rdn_and_parent.keys() {
+        // walk upward until we reach an object whose DN we already know or we cannot continue
+        let mut chain = Vec::new();
+        let mut current_dnt = start_dnt;
+        let mut base_dn = "";
+        loop {
+            if let Some(known_dn) = dnt_to_dn.get(&current_dnt) {
+                base_dn = known_dn.as_str();
+                break;
+            }
+            if chain.contains(&current_dnt) || chain.len() >= MAX_DN_DEPTH {
+                // cycle
+                break;
+            }
+            let Some((_rdn, parent_dnt_opt)) = dnt_to_rdn_and_parent.get(&current_dnt) else {
+                // missing parent
+                break
+            };
+            chain.push(current_dnt);
+            let Some(parent_dnt) = parent_dnt_opt else { break };
+            current_dnt = *parent_dnt;
+        }
+
+        // assemble the DNs top-down
+        let mut dn = base_dn.to_owned();
+        for chain_dnt in chain.into_iter().rev() {
+            let (rdn, _parent_dnt) = &dnt_to_rdn_and_parent[&chain_dnt];
+            dn = if dn.is_empty() {
+                rdn.clone()
+            } else {
+                format!("{},{}", rdn, dn)
+            };
+            dnt_to_dn.insert(chain_dnt, dn.clone());
+        }
+    }
+    Ok(dnt_to_dn)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use esedb::data::DataType;
+    use esedb::table::{Column, ColumnFlags, DEFAULT_CODEPAGE, ObjectFlags, TableHeader};
+
+    use crate::schema::{DNT_COLUMN_NAME, PARENT_DNT_COLUMN_NAME, ROOT_OBJECT_DNT, TOP_OBJECT_NAME_COLUMN_NAME};
+
+    const DNT: i32 = 1;
+    const PARENT_DNT: i32 = 2;
+    const RDN_TYPE: i32 = 3;
+    const NAME: i32 = 256;
+
+    const CN_ID: i32 = 3;
+    const DC_ID: i32 = 1376281;
+
+    fn column(column_id: i32, column_type: DataType, name: &str) -> Column {
+        Column {
+            table_object_id: 8,
+            column_id,
+            column_type,
+            length: 0,
+            flags: ColumnFlags::empty(),
+            codepage: DEFAULT_CODEPAGE,
+            root_flag: None,
+            record_offset: None,
+            name: name.to_owned(),
+            default_value: None,
+        }
+    }
+
+    fn data_table() -> Table {
+        Table {
+            header: TableHeader {
+                table_object_id: 8,
+                fdp_object_id: 8,
+                fdp_page_number: 0,
+                density: 0,
+                flags: ObjectFlags::empty(),
+                page_count: 0,
+                root_flag: None,
+                name: "datatable".to_owned(),
+                template_name: None,
+            },
+            columns: vec![
+                column(DNT, DataType::Long, DNT_COLUMN_NAME),
+                column(PARENT_DNT, DataType::Long, PARENT_DNT_COLUMN_NAME),
+                column(RDN_TYPE, DataType::Long, RDN_TYPE_COLUMN_NAME),
+                column(NAME, DataType::LongText, TOP_OBJECT_NAME_COLUMN_NAME),
+            ],
+            indexes: Vec::new(),
+            long_value: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    fn attributes() -> Vec<Attribute> {
+        [(CN_ID, "cn"), (DC_ID, "dc")].into_iter()
+            .map(|(id, ldap_name)| Attribute { id, syntax: 0, ldap_name: ldap_name.to_owned(), link_id: None })
+            .collect()
+    }
+
+    fn object_row(dnt: i32, parent_dnt: i32, rdn_type: i32, name: &str) -> BTreeMap<i32, Value> {
+        [
+            (DNT, Data::Long(dnt)),
+            (PARENT_DNT, Data::Long(parent_dnt)),
+            (RDN_TYPE, Data::Long(rdn_type)),
+            (NAME, Data::LongText(name.to_owned())),
+        ].into_iter()
+            .map(|(column_id, data)| (column_id, Value::Simple(data)))
+            .collect()
+    }
+
+    fn dn_map(rows: &[BTreeMap<i32, Value>]) -> BTreeMap<i32, String> {
+        build_dn_map(&data_table(), rows, &attributes(), &SchemaConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn dns_are_built_from_parents() {
+        let rows = [
+            object_row(3, ROOT_OBJECT_DNT, DC_ID, "com"),
+            object_row(4, 3, DC_ID, "example"),
+            object_row(5, 4, CN_ID, "Users"),
+            // an unknown RDN type falls back to CN
+            object_row(6, 5, 12345, "Administrator"),
+        ];
+        let dnt_to_dn = dn_map(&rows);
+        assert_eq!(dnt_to_dn[&ROOT_OBJECT_DNT], "");
+        assert_eq!(dnt_to_dn[&4], "DC=example,DC=com");
+        assert_eq!(dnt_to_dn[&6], "CN=Administrator,CN=Users,DC=example,DC=com");
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        let rows = [
+            object_row(3, ROOT_OBJECT_DNT, DC_ID, "com"),
+            object_row(4, 3, CN_ID, "Smith, John"),
+            object_row(5, 3, CN_ID, "#1 <admin>+\"root\"; a=b\\c "),
+            object_row(6, 3, CN_ID, " leading space\0"),
+        ];
+        let dnt_to_dn = dn_map(&rows);
+        assert_eq!(dnt_to_dn[&4], r"CN=Smith\, John,DC=com");
+        assert_eq!(dnt_to_dn[&5], r#"CN=\#1 \<admin\>\+\"root\"\; a\=b\\c\ ,DC=com"#);
+        assert_eq!(dnt_to_dn[&6], r"CN=\ leading space\00,DC=com");
+
+        // only the first character is escaped if it is a hash sign or a space
+        assert_eq!(escape_rdn_value("a#b c"), "a#b c");
+    }
+
+    #[test]
+    fn parent_cycle_ends_the_dn() {
+        let rows = [
+            object_row(3, ROOT_OBJECT_DNT, DC_ID, "com"),
+            object_row(10, 11, CN_ID, "a"),
+            object_row(11, 10, CN_ID, "b"),
+            object_row(12, 12, CN_ID, "self"),
+            object_row(13, 10, CN_ID, "child"),
+        ];
+        let dnt_to_dn = dn_map(&rows);
+        assert_eq!(dnt_to_dn[&3], "DC=com");
+        assert_eq!(dnt_to_dn[&10], "CN=a,CN=b");
+        assert_eq!(dnt_to_dn[&11], "CN=b");
+        assert_eq!(dnt_to_dn[&12], "CN=self");
+        assert_eq!(dnt_to_dn[&13], "CN=child,CN=a,CN=b");
+    }
+}
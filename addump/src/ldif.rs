@@ -0,0 +1,56 @@
+use std::io::{self, Write};
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use esedb::data::Data;
+
+
+/// Returns whether the value can be written as-is according to the SAFE-STRING rule of RFC 2849.
+///
+/// Trailing spaces are also treated as unsafe, since they tend to be mangled by other tools.
+fn is_safe_string(value: &[u8]) -> bool {
+    match value.first() {
+        None => return true,
+        Some(b' '|b':'|b'<') => return false,
+        Some(_) => {},
+    }
+    if value.last() == Some(&b' ') {
+        return false;
+    }
+    value.iter()
+        .all(|b| *b != b'\0' && *b != b'\n' && *b != b'\r' && *b < 0x80)
+}
+
+/// Writes an attribute-value line, switching to base64 (`::`) if the value is not a safe string.
+pub fn write_ldif_line<W: Write>(writer: &mut W, attribute_name: &str, value: &[u8]) -> io::Result<()> {
+    if is_safe_string(value) {
+        // safe strings are pure ASCII
+        let value_str = std::str::from_utf8(value).unwrap();
+        writeln!(writer, "{}: {}", attribute_name, value_str)
+    } else {
+        writeln!(writer, "{}:: {}", attribute_name, BASE64.encode(value))
+    }
+}
+
+/// Converts a value to the bytes to be written to LDIF; returns `None` for null values.
+pub fn data_to_ldif_bytes(data: &Data) -> Option<Vec<u8>> {
+    let bytes = match data {
+        Data::Nil => return None,
+        Data::Bit(b) => if bool::from(*b) { b"TRUE".to_vec() } else { b"FALSE".to_vec() },
+        Data::UnsignedByte(v) => v.to_string().into_bytes(),
+        Data::Short(v) => v.to_string().into_bytes(),
+        Data::Long(v) => v.to_string().into_bytes(),
+        Data::Currency(v) => v.to_string().into_bytes(),
+        Data::IeeeSingle(v) => v.to_string().into_bytes(),
+        Data::IeeeDouble(v) => v.to_string().into_bytes(),
+        Data::DateTime(v) => v.to_string().into_bytes(),
+        Data::UnsignedLong(v) => v.to_string().into_bytes(),
+        Data::LongLong(v) => v.to_string().into_bytes(),
+        Data::UnsignedShort(v) => v.to_string().into_bytes(),
+        Data::Text(s)|Data::LongText(s) => s.as_bytes().to_vec(),
+        // GUIDs are binary in LDAP, in the mixed-endian order used by Windows
+        Data::Guid(g) => g.to_bytes_le().to_vec(),
+        Data::Binary(b)|Data::LongBinary(b)|Data::SuperLongValue(b)|Data::Other(_, b) => b.clone(),
+    };
+    Some(bytes)
+}
@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use esedb::data::Data;
+use esedb::table::{Table, Value};
+
+use crate::schema::{Attribute, get_first_value};
+
+
+pub const LINK_TABLE_NAME: &str = "link_table";
+pub const LINK_DNT_COLUMN_NAME: &str = "link_DNT";
+pub const BACKLINK_DNT_COLUMN_NAME: &str = "backlink_DNT";
+pub const LINK_BASE_COLUMN_NAME: &str = "link_base";
+pub const LINK_DELETION_TIME_COLUMN_NAME: &str = "link_deltime";
+
+
+/// Collects the links stored in the link table, grouped by link base.
+///
+/// The returned map is: link base → DNT of the linking object (e.g. the group) → DNTs of the linked
+/// objects (e.g. the members). Links that have been deleted are skipped.
+///
+/// A flat map from linking to linked DNTs is not enough: the link table holds the links of all
+/// linked attributes at once (`member`, `managedBy`, `msDS-AuthenticatedAtDC`...) and only the link
+/// base tells them apart. The inner map for the link base of an attribute is that flat map; see
+/// [`collect_linked_attributes`] for assigning it to the attributes on both ends.
+pub fn collect_links(link_table: &Table, link_rows: &[BTreeMap<i32, Value>]) -> BTreeMap<i32, BTreeMap<i32, Vec<i32>>> {
+    let link_dnt_column_index = link_table.columns.iter()
+        .find(|c| c.name == LINK_DNT_COLUMN_NAME)
+        .expect("failed to find link DNT column")
+        .column_id;
+    let backlink_dnt_column_index = link_table.columns.iter()
+        .find(|c| c.name == BACKLINK_DNT_COLUMN_NAME)
+        .expect("failed to find backlink DNT column")
+        .column_id;
+    let link_base_column_index = link_table.columns.iter()
+        .find(|c| c.name == LINK_BASE_COLUMN_NAME)
+        .expect("failed to find link base column")
+        .column_id;
+    let deletion_time_column_index_opt = link_table.columns.iter()
+        .find(|c| c.name == LINK_DELETION_TIME_COLUMN_NAME)
+        .map(|c| c.column_id);
+
+    let mut base_to_links: BTreeMap<i32, BTreeMap<i32, Vec<i32>>> = BTreeMap::new();
+    for link_row in link_rows {
+        let Some(Data::Long(link_dnt)) = get_first_value(link_row, link_dnt_column_index) else { continue };
+        let Some(Data::Long(backlink_dnt)) = get_first_value(link_row, backlink_dnt_column_index) else { continue };
+        let Some(Data::Long(link_base)) = get_first_value(link_row, link_base_column_index) else { continue };
+        if let Some(deletion_time_column_index) = deletion_time_column_index_opt {
+            match get_first_value(link_row, deletion_time_column_index) {
+                None|Some(Data::Nil) => {},
+                Some(_) => continue,
+            }
+        }
+
+        base_to_links
+            .entry(*link_base)
+            .or_default()
+            .entry(*link_dnt)
+            .or_default()
+            .push(*backlink_dnt);
+    }
+    base_to_links
+}
+
+/// Distributes links to the linked attributes of the objects on both ends.
+///
+/// The returned map is: DNT of an object → LDAP name of a linked attribute (e.g. `member` or
+/// `memberOf`) → DNTs of the objects referenced by this attribute.
+pub fn collect_linked_attributes<'a, A: IntoIterator<Item = &'a Attribute>>(base_to_links: &BTreeMap<i32, BTreeMap<i32, Vec<i32>>>, attributes: A) -> BTreeMap<i32, BTreeMap<String, Vec<i32>>> {
+    let mut dnt_to_attributes: BTreeMap<i32, BTreeMap<String, Vec<i32>>> = BTreeMap::new();
+    for attribute in attributes {
+        let Some(link_id) = attribute.link_id else { continue };
+        let Some(links) = base_to_links.get(&(link_id / 2)) else { continue };
+
+        let is_forward_link = link_id % 2 == 0;
+        for (link_dnt, backlink_dnts) in links {
+            if is_forward_link {
+                dnt_to_attributes
+                    .entry(*link_dnt)
+                    .or_default()
+                    .entry(attribute.ldap_name.clone())
+                    .or_default()
+                    .extend(backlink_dnts);
+            } else {
+                for backlink_dnt in backlink_dnts {
+                    dnt_to_attributes
+                        .entry(*backlink_dnt)
+                        .or_default()
+                        .entry(attribute.ldap_name.clone())
+                        .or_default()
+                        .push(*link_dnt);
+                }
+            }
+        }
+    }
+    dnt_to_attributes
+}
@@ -1,20 +1,154 @@
+mod dn;
+mod ldif;
+mod links;
+mod metadata;
 mod schema;
+mod security;
+mod values;
 
 
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{Args, Parser};
 use esedb::header::read_header;
+use esedb::data::Data;
 use esedb::page::CATALOG_PAGE_NUMBER;
-use esedb::table::{collect_tables, read_table_from_pages};
+use esedb::table::{collect_tables, read_table_from_pages, Table, Value};
 
-use crate::schema::{collect_schema_attributes, find_schema_root};
+use crate::dn::build_dn_map;
+use crate::ldif::{data_to_ldif_bytes, write_ldif_line};
+use crate::links::{collect_linked_attributes, collect_links, LINK_TABLE_NAME};
+use crate::metadata::{format_repl_property_meta_data, parse_repl_property_meta_data};
+use crate::schema::{
+    Attribute, collect_schema_attributes, collect_schema_classes, extract_dnt, find_column_id, find_schema_root,
+    ObjectClass, SchemaConfig, SchemaError, SYNTAX_DISTINGUISHED_NAME, SYNTAX_OBJECT_IDENTIFIER,
+};
+use crate::security::{
+    collect_security_descriptors, format_sddl, parse_security_descriptor, resolve_security_descriptor,
+    SD_TABLE_NAME, SECURITY_DESCRIPTOR_ATTRIBUTE_NAME,
+};
+use crate::values::{
+    format_filetime, format_ntds_sid, format_user_account_control, render_value, TIME_ATTRIBUTE_NAMES,
+};
 
 
 #[derive(Parser)]
-struct Opts {
+enum Opts {
+    /// Output all objects of the datatable.
+    Dump(DumpOpts),
+
+    /// Output the attributes of the object with the given distinguished name.
+    Get(GetOpts),
+
+    /// Output the distinguished name and classes of each object, one object per line.
+    List(ListOpts),
+}
+impl Opts {
+    pub fn db_path(&self) -> &Path {
+        match self {
+            Self::Dump(dpo) => dpo.db_path.as_path(),
+            Self::Get(go) => go.db_path.as_path(),
+            Self::List(lo) => lo.db_path.as_path(),
+        }
+    }
+
+    pub fn schema_opts(&self) -> &SchemaOpts {
+        match self {
+            Self::Dump(dpo) => &dpo.schema,
+            Self::Get(go) => &go.schema,
+            Self::List(lo) => &lo.schema,
+        }
+    }
+}
+
+#[derive(Parser)]
+struct DumpOpts {
+    pub db_path: PathBuf,
+
+    /// Output the objects in LDIF (RFC 2849) instead of the raw dump format.
+    #[arg(long)]
+    pub ldif: bool,
+
+    /// Output 64-bit integer values in the given columns or attributes as timestamps, in addition
+    /// to the attributes known to contain them.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMN,...")]
+    pub time_columns: Vec<String>,
+
+    #[command(flatten)]
+    pub schema: SchemaOpts,
+}
+
+#[derive(Parser)]
+struct GetOpts {
+    pub db_path: PathBuf,
+    pub dn: String,
+
+    /// Output 64-bit integer values in the given columns or attributes as timestamps, in addition
+    /// to the attributes known to contain them.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMN,...")]
+    pub time_columns: Vec<String>,
+
+    #[command(flatten)]
+    pub schema: SchemaOpts,
+}
+
+#[derive(Parser)]
+struct ListOpts {
     pub db_path: PathBuf,
+
+    /// Only output objects of the given class (by LDAP name), including objects of its subclasses.
+    /// If specified multiple times, objects of any of the classes are output.
+    #[arg(long = "class", value_name = "CLASS")]
+    pub classes: Vec<String>,
+
+    #[command(flatten)]
+    pub schema: SchemaOpts,
+}
+
+#[derive(Args)]
+struct SchemaOpts {
+    /// The DNT of the root object ($ROOT_OBJECT$).
+    #[arg(long)]
+    pub root_dnt: Option<i32>,
+
+    /// The object class number of the schema root (dMD).
+    #[arg(long)]
+    pub schema_root_class: Option<i32>,
+
+    /// The object class number of classes in the schema (classSchema).
+    #[arg(long)]
+    pub class_schema_class: Option<i32>,
+
+    /// The object class number of attributes in the schema (attributeSchema).
+    #[arg(long)]
+    pub attribute_schema_class: Option<i32>,
+
+    /// The DNT of the schema root, if it cannot be determined automatically.
+    #[arg(long)]
+    pub schema_root_dnt: Option<i32>,
+}
+impl SchemaOpts {
+    /// Returns the schema assumptions, with the defaults replaced by any that have been specified.
+    pub fn schema_config(&self) -> SchemaConfig {
+        let mut config = SchemaConfig::default();
+        if let Some(root_dnt) = self.root_dnt {
+            config.root_object_dnt = root_dnt;
+        }
+        if let Some(schema_root_class) = self.schema_root_class {
+            config.schema_root_object_class = schema_root_class;
+        }
+        if let Some(class_schema_class) = self.class_schema_class {
+            config.schema_class_object_class = class_schema_class;
+        }
+        if let Some(attribute_schema_class) = self.attribute_schema_class {
+            config.schema_attribute_object_class = attribute_schema_class;
+        }
+        config.schema_root_dnt = self.schema_root_dnt;
+        config
+    }
 }
 
 
@@ -27,7 +161,8 @@ fn main() {
         .init();
 
     let opts = Opts::parse();
-    let mut file = File::open(&opts.db_path)
+    let schema_config = opts.schema_opts().schema_config();
+    let mut file = File::open(opts.db_path())
         .expect("failed to open database file");
     let header = read_header(&mut file)
         .expect("failed to read database header");
@@ -36,9 +171,12 @@ fn main() {
     if header != shadow_header {
         println!("warning: shadow header mismatch");
     }
+    if header.needs_recovery() {
+        println!("warning: database was not shut down cleanly and may be inconsistent");
+    }
 
     // read the catalog of objects
-    let naive_rows = read_table_from_pages(&mut file, &header, CATALOG_PAGE_NUMBER, &*esedb::table::METADATA_COLUMN_DEFS, None)
+    let naive_rows = read_table_from_pages(&file, &header, CATALOG_PAGE_NUMBER, &*esedb::table::METADATA_COLUMN_DEFS, None)
         .expect("failed to read metadata table from pages");
     let naive_tables = collect_tables(&naive_rows, &*esedb::table::METADATA_COLUMN_DEFS)
         .expect("failed to collect tables");
@@ -49,7 +187,7 @@ fn main() {
         .expect("MSysObjects table not found");
 
     // re-read the metadata given this definition
-    let meta_rows = read_table_from_pages(&mut file, &header, mso.header.fdp_page_number.try_into().unwrap(), &mso.columns, mso.long_value_page_number())
+    let meta_rows = read_table_from_pages(&file, &header, mso.fdp_page_number().expect("invalid FDP page number"), &mso.columns, mso.long_value_page_number().expect("invalid long value page number"))
         .expect("failed to read metadata table from pages");
     let tables = collect_tables(&meta_rows, &mso.columns)
         .expect("failed to collect tables");
@@ -58,24 +196,479 @@ fn main() {
     let d8a = tables.iter()
         .find(|t| t.header.name == "datatable")
         .expect("datatable not found");
-    let d8a_rows = read_table_from_pages(&mut file, &header, d8a.header.fdp_page_number.try_into().unwrap(), &d8a.columns, d8a.long_value_page_number())
+    let d8a_rows = read_table_from_pages(&file, &header, d8a.fdp_page_number().expect("invalid FDP page number"), &d8a.columns, d8a.long_value_page_number().expect("invalid long value page number"))
         .expect("failed to read data rows");
 
-    let schema_root = find_schema_root(d8a, &d8a_rows);
-    //let id_to_class = collect_schema_classes(d8a, &d8a_rows, schema_root);
-    let name_to_attribute = collect_schema_attributes(d8a, &d8a_rows, schema_root);
-
-    // run through the datatable
-    for d8a_row in &d8a_rows {
-        println!("---");
-        for (col_id, value) in d8a_row {
-            let column = d8a.columns.iter().find(|c| c.column_id == *col_id).unwrap();
-            if let Some(attribute) = name_to_attribute.get(&column.name) {
-                print!("{}: ", attribute.ldap_name);
-            } else {
-                print!("{}: ", column.name);
+    let schema_root = find_schema_root(d8a, &d8a_rows, &schema_config)
+        .unwrap_or_else(|e| exit_with_error("failed to find schema root", e));
+    let id_to_class = collect_schema_classes(d8a, &d8a_rows, schema_root, &schema_config)
+        .unwrap_or_else(|e| exit_with_error("failed to collect schema classes", e));
+    let name_to_attribute = collect_schema_attributes(d8a, &d8a_rows, schema_root, &schema_config)
+        .unwrap_or_else(|e| exit_with_error("failed to collect schema attributes", e));
+    let id_to_attribute_name: BTreeMap<i32, String> = name_to_attribute.values()
+        .map(|a| (a.id, a.ldap_name.clone()))
+        .collect();
+    let dnt_to_dn = build_dn_map(d8a, &d8a_rows, name_to_attribute.values(), &schema_config)
+        .unwrap_or_else(|e| exit_with_error("failed to build distinguished names", e));
+
+    // security descriptors are stored once in the SD table and referenced by ID
+    let sd_id_to_descriptor = if let Some(sd_table) = tables.iter().find(|t| t.header.name == SD_TABLE_NAME) {
+        let sd_rows = read_table_from_pages(&file, &header, sd_table.fdp_page_number().expect("invalid FDP page number"), &sd_table.columns, sd_table.long_value_page_number().expect("invalid long value page number"))
+            .expect("failed to read security descriptor rows");
+        collect_security_descriptors(sd_table, &sd_rows)
+    } else {
+        BTreeMap::new()
+    };
+
+    let lookup = NameLookup {
+        id_to_class: &id_to_class,
+        id_to_attribute_name: &id_to_attribute_name,
+        dnt_to_dn: &dnt_to_dn,
+        sd_id_to_descriptor: &sd_id_to_descriptor,
+    };
+
+    // group memberships and other linked attributes are stored in the link table
+    let dnt_to_linked_attributes = if let Some(link_table) = tables.iter().find(|t| t.header.name == LINK_TABLE_NAME) {
+        let link_rows = read_table_from_pages(&file, &header, link_table.fdp_page_number().expect("invalid FDP page number"), &link_table.columns, link_table.long_value_page_number().expect("invalid long value page number"))
+            .expect("failed to read link rows");
+        let base_to_links = collect_links(link_table, &link_rows);
+        collect_linked_attributes(&base_to_links, name_to_attribute.values())
+    } else {
+        BTreeMap::new()
+    };
+
+    let dnt_column_index = find_column_id(d8a, &schema_config.dnt_column_name, "key (DNT)")
+        .unwrap_or_else(|e| exit_with_error("failed to find DNT column", e));
+
+    match opts {
+        Opts::Dump(dump_opts) => {
+            if dump_opts.ldif {
+                output_ldif(d8a, &d8a_rows, dnt_column_index, &name_to_attribute, &lookup, &dnt_to_linked_attributes)
+                    .expect("failed to write LDIF");
+                return;
+            }
+
+            // run through the datatable
+            for d8a_row in &d8a_rows {
+                println!("---");
+                let linked_attributes = d8a_row.get(&dnt_column_index)
+                    .and_then(|dnt_value| dnt_to_linked_attributes.get(&extract_dnt(dnt_value)));
+                print_object(d8a, d8a_row, &name_to_attribute, &lookup, linked_attributes, true, &dump_opts.time_columns);
+            }
+        },
+        Opts::Get(get_opts) => {
+            // attribute types and values in DNs are compared case-insensitively
+            let wanted_dn = get_opts.dn.to_lowercase();
+            let found_dnt_opt = dnt_to_dn.iter()
+                .find(|(_dnt, dn)| dn.to_lowercase() == wanted_dn)
+                .map(|(dnt, _dn)| *dnt);
+            let found_row_opt = found_dnt_opt.and_then(|found_dnt| d8a_rows.iter()
+                .find(|row| row.get(&dnt_column_index).map(|v| extract_dnt(v) == found_dnt).unwrap_or(false))
+            );
+            let (Some(found_dnt), Some(found_row)) = (found_dnt_opt, found_row_opt) else {
+                eprintln!("no object with the distinguished name {:?} found", get_opts.dn);
+                std::process::exit(1);
+            };
+
+            println!("dn: {}", dnt_to_dn[&found_dnt]);
+            print_object(
+                d8a, found_row, &name_to_attribute, &lookup, dnt_to_linked_attributes.get(&found_dnt), false,
+                &get_opts.time_columns,
+            );
+        },
+        Opts::List(list_opts) => {
+            let object_class_column_index = find_column_id(d8a, &schema_config.object_class_column_name, "objectClass")
+                .unwrap_or_else(|e| exit_with_error("failed to find objectClass column", e));
+            let wanted_classes: Vec<String> = list_opts.classes.iter()
+                .map(|c| c.to_lowercase())
+                .collect();
+            for (wanted_class, given_class) in wanted_classes.iter().zip(list_opts.classes.iter()) {
+                if !id_to_class.values().any(|c| c.ldap_name.to_lowercase() == *wanted_class) {
+                    println!("warning: no class named {:?} in the schema", given_class);
+                }
+            }
+
+            for d8a_row in &d8a_rows {
+                // the objectClass attribute contains the whole chain of superclasses
+                let class_names: Vec<String> = d8a_row.get(&object_class_column_index)
+                    .map(|v| v.to_data_vec())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|data| match data {
+                        Data::Long(class_id) => id_to_class.get(class_id)
+                            .map(|c| c.ldap_name.clone())
+                            .unwrap_or_else(|| class_id.to_string()),
+                        other => other.to_string(),
+                    })
+                    .collect();
+                if class_names.is_empty() {
+                    // phantom object
+                    continue;
+                }
+                let matches = wanted_classes.is_empty()
+                    || class_names.iter().any(|n| wanted_classes.contains(&n.to_lowercase()));
+                if !matches {
+                    continue;
+                }
+
+                let dnt_opt = d8a_row.get(&dnt_column_index).map(extract_dnt);
+                let dn = match dnt_opt {
+                    Some(dnt) => dnt_to_dn.get(&dnt).cloned().unwrap_or_else(|| format!("<DNT {}>", dnt)),
+                    None => "<no DNT>".to_owned(),
+                };
+                println!("{}\t{}", dn, class_names.join(", "));
             }
-            println!("{:?}", value);
+        },
+    }
+}
+
+/// Prints the attributes of an object, one per line.
+///
+/// Columns that do not correspond to an attribute in the schema are only printed if
+/// `include_unknown_columns` is set.
+fn print_object(
+    data_table: &Table,
+    row: &BTreeMap<i32, Value>,
+    name_to_attribute: &BTreeMap<String, Attribute>,
+    lookup: &NameLookup,
+    linked_attributes: Option<&BTreeMap<String, Vec<i32>>>,
+    include_unknown_columns: bool,
+    time_columns: &[String],
+) {
+    for (col_id, value) in row {
+        let column = data_table.columns.iter().find(|c| c.column_id == *col_id).unwrap();
+        let attribute_opt = name_to_attribute.get(&column.name);
+        if let Some(attribute) = attribute_opt {
+            print!("{}: ", attribute.ldap_name);
+        } else if include_unknown_columns {
+            print!("{}: ", column.name);
+        } else {
+            continue;
         }
+
+        println!("{}", format_attribute_value(lookup, &column.name, attribute_opt, value, time_columns));
+    }
+
+    let Some(linked_attributes) = linked_attributes else { return };
+    for (ldap_name, linked_dnts) in linked_attributes {
+        let linked_names: Vec<String> = linked_dnts.iter()
+            .map(|dnt| lookup.dnt_to_dn.get(dnt).cloned().unwrap_or_else(|| format!("<DNT {}>", dnt)))
+            .collect();
+        println!("{}: {}", ldap_name, linked_names.join(", "));
+    }
+}
+
+/// Reports an error that prevents the database from being dumped and exits.
+fn exit_with_error(message: &str, error: SchemaError) -> ! {
+    eprintln!("{}: {}", message, error);
+    std::process::exit(1);
+}
+
+/// Lookup tables for translating references to classes and objects into names.
+struct NameLookup<'a> {
+    id_to_class: &'a BTreeMap<i32, ObjectClass>,
+    id_to_attribute_name: &'a BTreeMap<i32, String>,
+    dnt_to_dn: &'a BTreeMap<i32, String>,
+    sd_id_to_descriptor: &'a BTreeMap<i64, Vec<u8>>,
+}
+impl<'a> NameLookup<'a> {
+    /// Translates the value of an attribute into a name if it refers to a class or attribute
+    /// (objectClass and other attributes with OID syntax) or to another object (objectCategory and
+    /// other attributes with DN syntax).
+    pub fn resolve(&self, attribute: &Attribute, data: &Data) -> Option<String> {
+        let Data::Long(reference) = data else { return None };
+        if attribute.syntax == SYNTAX_OBJECT_IDENTIFIER {
+            self.id_to_class.get(reference)
+                .map(|c| c.ldap_name.clone())
+                .or_else(|| self.id_to_attribute_name.get(reference).cloned())
+        } else if attribute.syntax == SYNTAX_DISTINGUISHED_NAME {
+            self.dnt_to_dn.get(reference).cloned()
+        } else {
+            None
+        }
+    }
+}
+
+fn output_ldif(
+    data_table: &Table,
+    data_rows: &[BTreeMap<i32, Value>],
+    dnt_column_index: i32,
+    name_to_attribute: &BTreeMap<String, Attribute>,
+    lookup: &NameLookup,
+    dnt_to_linked_attributes: &BTreeMap<i32, BTreeMap<String, Vec<i32>>>,
+) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "version: 1")?;
+    for row in data_rows {
+        // skip phantoms and the root object
+        let Some(dnt_value) = row.get(&dnt_column_index) else { continue };
+        let dnt = extract_dnt(dnt_value);
+        let Some(dn) = lookup.dnt_to_dn.get(&dnt) else { continue };
+        if dn.is_empty() {
+            continue;
+        }
+
+        writeln!(out)?;
+        write_ldif_line(&mut out, "dn", dn.as_bytes())?;
+        for (col_id, value) in row {
+            let column = data_table.columns.iter().find(|c| c.column_id == *col_id).unwrap();
+            let Some(attribute) = name_to_attribute.get(&column.name) else { continue };
+            // multi-valued attributes get one line per value
+            for data in value.to_data_vec() {
+                if let Some(name) = lookup.resolve(attribute, data) {
+                    write_ldif_line(&mut out, &attribute.ldap_name, name.as_bytes())?;
+                    continue;
+                }
+                if attribute.ldap_name == SECURITY_DESCRIPTOR_ATTRIBUTE_NAME {
+                    // output the descriptor instead of its ID
+                    let descriptor_opt = data.as_bytes()
+                        .and_then(|bytes| resolve_security_descriptor(bytes, lookup.sd_id_to_descriptor));
+                    if let Some(descriptor) = descriptor_opt {
+                        write_ldif_line(&mut out, &attribute.ldap_name, descriptor)?;
+                        continue;
+                    }
+                }
+                let Some(bytes) = data_to_ldif_bytes(data) else { continue };
+                write_ldif_line(&mut out, &attribute.ldap_name, &bytes)?;
+            }
+        }
+
+        // linked attributes refer to other objects by DN; skip any we cannot resolve
+        let Some(linked_attributes) = dnt_to_linked_attributes.get(&dnt) else { continue };
+        for (ldap_name, linked_dnts) in linked_attributes {
+            for linked_dnt in linked_dnts {
+                let Some(linked_dn) = lookup.dnt_to_dn.get(linked_dnt) else { continue };
+                write_ldif_line(&mut out, ldap_name, linked_dn.as_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_attribute_value(lookup: &NameLookup, column_name: &str, attribute_opt: Option<&Attribute>, value: &Value, time_columns: &[String]) -> String {
+    let ldap_name = attribute_opt.map(|a| a.ldap_name.as_str()).unwrap_or("");
+    let is_time = TIME_ATTRIBUTE_NAMES.contains(&ldap_name)
+        || time_columns.iter().any(|c| c == column_name || c == ldap_name);
+    if attribute_opt.is_none() && !is_time {
+        return value.to_string();
+    }
+
+    let is_sid = ldap_name.ends_with("Sid");
+    let is_uac = ldap_name == "userAccountControl";
+    let is_repl_meta = ldap_name == "replPropertyMetaData";
+    let is_guid = attribute_opt.map(|a| a.is_guid()).unwrap_or(false);
+    let is_security_descriptor = ldap_name == SECURITY_DESCRIPTOR_ATTRIBUTE_NAME;
+
+    let mut pieces = Vec::new();
+    for data in value.to_data_vec() {
+        let formatted_opt = match data {
+            Data::LongLong(filetime)|Data::Currency(filetime) if is_time => format_filetime(*filetime),
+            Data::Binary(bytes)|Data::LongBinary(bytes) if is_sid => format_ntds_sid(bytes),
+            Data::Long(uac) if is_uac => Some(format_user_account_control(*uac)),
+            other if is_guid => other.to_guid_lenient().map(|g| g.to_string()),
+            Data::Binary(bytes)|Data::LongBinary(bytes) if is_security_descriptor
+                => resolve_security_descriptor(bytes, lookup.sd_id_to_descriptor)
+                    .and_then(parse_security_descriptor)
+                    .map(|sd| format_sddl(&sd)),
+            Data::Binary(bytes)|Data::LongBinary(bytes) if is_repl_meta => parse_repl_property_meta_data(bytes)
+                .map(|entries| format_repl_property_meta_data(
+                    &entries,
+                    |id| i32::try_from(id).ok()
+                        .and_then(|id| lookup.id_to_attribute_name.get(&id))
+                        .cloned(),
+                )),
+            other => attribute_opt.and_then(|attribute| lookup.resolve(attribute, other)),
+        };
+        let formatted = formatted_opt.unwrap_or_else(|| match attribute_opt {
+            Some(attribute) => render_value(attribute.syntax, data),
+            None => data.to_string(),
+        });
+        pieces.push(formatted);
+    }
+    pieces.join(", ")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use esedb::data::DataType;
+    use esedb::table::{Column, ColumnFlags, DEFAULT_CODEPAGE, ObjectFlags, TableHeader, TagFlags};
+
+    use crate::schema::{SCHEMA_CLASS_OBJECT_CLASS, SCHEMA_ROOT_OBJECT_CLASS, SYNTAX_OCTET_STRING};
+
+    const DNT: i32 = 1;
+    const PARENT_DNT: i32 = 2;
+    const OBJECT_CLASS: i32 = 256;
+    const GOVERNS_ID: i32 = 257;
+    const LDAP_NAME: i32 = 258;
+    const OBJECT_CATEGORY: i32 = 259;
+
+    const USER_CLASS_ID: i32 = 655369;
+    const PERSON_CLASS_ID: i32 = 196615;
+
+    fn column(column_id: i32, column_type: DataType, name: &str) -> Column {
+        Column {
+            table_object_id: 8,
+            column_id,
+            column_type,
+            length: 0,
+            flags: ColumnFlags::empty(),
+            codepage: DEFAULT_CODEPAGE,
+            root_flag: None,
+            record_offset: None,
+            name: name.to_owned(),
+            default_value: None,
+        }
+    }
+
+    fn data_table() -> Table {
+        Table {
+            header: TableHeader {
+                table_object_id: 8,
+                fdp_object_id: 8,
+                fdp_page_number: 0,
+                density: 0,
+                flags: ObjectFlags::empty(),
+                page_count: 0,
+                root_flag: None,
+                name: "datatable".to_owned(),
+                template_name: None,
+            },
+            columns: vec![
+                column(DNT, DataType::Long, "DNT_col"),
+                column(PARENT_DNT, DataType::Long, "PDNT_col"),
+                column(OBJECT_CLASS, DataType::Long, "ATTc0"),
+                column(GOVERNS_ID, DataType::Long, "ATTc131094"),
+                column(LDAP_NAME, DataType::LongText, "ATTm131532"),
+                column(OBJECT_CATEGORY, DataType::Long, "ATTb590606"),
+            ],
+            indexes: Vec::new(),
+            long_value: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    fn row(values: &[(i32, Data)]) -> BTreeMap<i32, Value> {
+        values.iter()
+            .map(|(column_id, data)| (*column_id, Value::Simple(data.clone())))
+            .collect()
+    }
+
+    fn class_row(dnt: i32, parent_dnt: i32, object_class: i32, governs_id: i32, ldap_name: &str) -> BTreeMap<i32, Value> {
+        row(&[
+            (DNT, Data::Long(dnt)),
+            (PARENT_DNT, Data::Long(parent_dnt)),
+            (OBJECT_CLASS, Data::Long(object_class)),
+            (GOVERNS_ID, Data::Long(governs_id)),
+            (LDAP_NAME, Data::LongText(ldap_name.to_owned())),
+        ])
+    }
+
+    #[test]
+    fn schema_classes_are_children_of_schema_root() {
+        let rows = vec![
+            row(&[(DNT, Data::Long(3)), (PARENT_DNT, Data::Long(2)), (OBJECT_CLASS, Data::Long(SCHEMA_ROOT_OBJECT_CLASS))]),
+            class_row(4, 3, SCHEMA_CLASS_OBJECT_CLASS, USER_CLASS_ID, "user"),
+            class_row(5, 3, SCHEMA_CLASS_OBJECT_CLASS, PERSON_CLASS_ID, "person"),
+            // not a class
+            class_row(6, 3, 196622, 1, "cn"),
+            // a class, but not below the schema root
+            class_row(7, 2, SCHEMA_CLASS_OBJECT_CLASS, 2, "elsewhere"),
+        ];
+        let id_to_class = collect_schema_classes(&data_table(), &rows, &rows[0], &SchemaConfig::default())
+            .unwrap();
+
+        let names: Vec<(i32, &str)> = id_to_class.values()
+            .map(|c| (c.id, c.ldap_name.as_str()))
+            .collect();
+        assert_eq!(names, [(PERSON_CLASS_ID, "person"), (USER_CLASS_ID, "user")]);
+    }
+
+    #[test]
+    fn object_class_and_category_are_translated() {
+        let id_to_class: BTreeMap<i32, ObjectClass> = [
+            ObjectClass { id: USER_CLASS_ID, ldap_name: "user".to_owned() },
+            ObjectClass { id: PERSON_CLASS_ID, ldap_name: "person".to_owned() },
+        ].into_iter().map(|c| (c.id, c)).collect();
+        let id_to_attribute_name = BTreeMap::new();
+        let mut dnt_to_dn = BTreeMap::new();
+        dnt_to_dn.insert(1234, "CN=Person,CN=Schema,CN=Configuration,DC=example,DC=com".to_owned());
+        let sd_id_to_descriptor = BTreeMap::new();
+        let lookup = NameLookup {
+            id_to_class: &id_to_class,
+            id_to_attribute_name: &id_to_attribute_name,
+            dnt_to_dn: &dnt_to_dn,
+            sd_id_to_descriptor: &sd_id_to_descriptor,
+        };
+
+        let object_class = Attribute {
+            id: 0,
+            syntax: SYNTAX_OBJECT_IDENTIFIER,
+            ldap_name: "objectClass".to_owned(),
+            link_id: None,
+        };
+        let object_category = Attribute {
+            id: 590606,
+            syntax: SYNTAX_DISTINGUISHED_NAME,
+            ldap_name: "objectCategory".to_owned(),
+            link_id: None,
+        };
+
+        let classes = Value::Multiple {
+            values: vec![Data::Long(PERSON_CLASS_ID), Data::Long(USER_CLASS_ID)].into(),
+            flags: TagFlags::empty(),
+            compression: None,
+        };
+        assert_eq!(
+            format_attribute_value(&lookup, "ATTc0", Some(&object_class), &classes, &[]),
+            "person, user",
+        );
+
+        let category = Value::Simple(Data::Long(1234));
+        assert_eq!(
+            format_attribute_value(&lookup, "ATTb590606", Some(&object_category), &category, &[]),
+            "CN=Person,CN=Schema,CN=Configuration,DC=example,DC=com",
+        );
+
+        // unknown references fall back to the number
+        assert_eq!(lookup.resolve(&object_class, &Data::Long(1)), None);
+        assert_eq!(lookup.resolve(&object_category, &Data::Long(1)), None);
+    }
+
+    #[test]
+    fn replication_metadata_attribute_ids_are_translated() {
+        let id_to_class = BTreeMap::new();
+        let mut id_to_attribute_name = BTreeMap::new();
+        id_to_attribute_name.insert(0, "objectClass".to_owned());
+        let dnt_to_dn = BTreeMap::new();
+        let sd_id_to_descriptor = BTreeMap::new();
+        let lookup = NameLookup {
+            id_to_class: &id_to_class,
+            id_to_attribute_name: &id_to_attribute_name,
+            dnt_to_dn: &dnt_to_dn,
+            sd_id_to_descriptor: &sd_id_to_descriptor,
+        };
+        let repl_meta = Attribute {
+            id: 589827,
+            syntax: SYNTAX_OCTET_STRING,
+            ldap_name: "replPropertyMetaData".to_owned(),
+            link_id: None,
+        };
+
+        // version 1, two entries: objectClass and an ID that does not fit into an i32
+        let mut blob = [1u32, 0, 2, 0].iter()
+            .flat_map(|n| n.to_le_bytes())
+            .collect::<Vec<u8>>();
+        for attribute_id in [0, 0x8000_0000u32] {
+            blob.extend_from_slice(&attribute_id.to_le_bytes());
+            blob.extend_from_slice(&[0; 44]);
+        }
+
+        let formatted = format_attribute_value(&lookup, "ATTk589827", Some(&repl_meta), &Value::Simple(Data::Binary(blob)), &[]);
+        assert!(formatted.contains("\n  objectClass version=0 "));
+        assert!(formatted.contains("\n  0x80000000 version=0 "));
     }
 }
@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use uuid::Uuid;
+
+use crate::values::{FILETIME_TICKS_PER_SECOND, format_filetime};
+
+
+/// The size of the header of a replPropertyMetaData blob.
+const HEADER_SIZE: usize = 16;
+
+/// The size of each entry in a version 1 replPropertyMetaData blob.
+const ENTRY_V1_SIZE: usize = 48;
+
+
+/// Replication metadata of a single attribute of an object.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PropertyMetaData {
+    pub attribute_id: u32,
+    pub version: u32,
+    /// Time of the originating change, in seconds since 1601-01-01 00:00:00 UTC.
+    pub time_changed: i64,
+    pub originating_dsa: Uuid,
+    pub originating_usn: i64,
+    pub local_usn: i64,
+}
+impl PropertyMetaData {
+    /// The time of the originating change as a FILETIME.
+    pub fn time_changed_filetime(&self) -> i64 {
+        self.time_changed.saturating_mul(FILETIME_TICKS_PER_SECOND)
+    }
+}
+
+
+/// Parses the value of the replPropertyMetaData attribute.
+///
+/// Returns `None` if the blob is truncated or has a version other than 1.
+pub fn parse_repl_property_meta_data(bytes: &[u8]) -> Option<Vec<PropertyMetaData>> {
+    // header: version (u32), reserved (u32), entry count (u32), padding (u32)
+    let header = bytes.get(0..HEADER_SIZE)?;
+    let version = u32::from_le_bytes(header[0..4].try_into().unwrap());
+    if version != 1 {
+        return None;
+    }
+    let entry_count: usize = u32::from_le_bytes(header[8..12].try_into().unwrap()).try_into().unwrap();
+
+    // entry: attribute ID (u32), version (u32), time changed (i64), originating DSA (GUID),
+    // originating USN (i64), local USN (i64)
+    let entries_length = entry_count.checked_mul(ENTRY_V1_SIZE)?;
+    let entry_bytes = bytes.get(HEADER_SIZE..HEADER_SIZE.checked_add(entries_length)?)?;
+    let mut entries = Vec::with_capacity(entry_count);
+    for entry in entry_bytes.chunks_exact(ENTRY_V1_SIZE) {
+        entries.push(PropertyMetaData {
+            attribute_id: u32::from_le_bytes(entry[0..4].try_into().unwrap()),
+            version: u32::from_le_bytes(entry[4..8].try_into().unwrap()),
+            time_changed: i64::from_le_bytes(entry[8..16].try_into().unwrap()),
+            originating_dsa: Uuid::from_bytes_le(entry[16..32].try_into().unwrap()),
+            originating_usn: i64::from_le_bytes(entry[32..40].try_into().unwrap()),
+            local_usn: i64::from_le_bytes(entry[40..48].try_into().unwrap()),
+        });
+    }
+    Some(entries)
+}
+
+/// Formats replication metadata, one entry per line, using the given function to obtain the names
+/// of attributes from their IDs.
+pub fn format_repl_property_meta_data<N: Fn(u32) -> Option<String>>(entries: &[PropertyMetaData], attribute_name: N) -> String {
+    let mut ret = String::new();
+    for entry in entries {
+        ret.push_str("\n  ");
+        match attribute_name(entry.attribute_id) {
+            Some(name) => write!(ret, "{}", name).unwrap(),
+            None => write!(ret, "0x{:08X}", entry.attribute_id).unwrap(),
+        }
+        let time_changed = format_filetime(entry.time_changed_filetime())
+            .unwrap_or_else(|| entry.time_changed.to_string());
+        write!(
+            ret,
+            " version={} changed={} dsa={} originating_usn={} local_usn={}",
+            entry.version, time_changed, entry.originating_dsa,
+            entry.originating_usn, entry.local_usn,
+        ).unwrap();
+    }
+    ret
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DSA_GUID: &str = "12345678-9abc-def0-1234-56789abcdef0";
+
+    fn entry_bytes(entry: &PropertyMetaData) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ENTRY_V1_SIZE);
+        bytes.extend_from_slice(&entry.attribute_id.to_le_bytes());
+        bytes.extend_from_slice(&entry.version.to_le_bytes());
+        bytes.extend_from_slice(&entry.time_changed.to_le_bytes());
+        bytes.extend_from_slice(&entry.originating_dsa.to_bytes_le());
+        bytes.extend_from_slice(&entry.originating_usn.to_le_bytes());
+        bytes.extend_from_slice(&entry.local_usn.to_le_bytes());
+        bytes
+    }
+
+    fn blob(version: u32, entry_count: u32, entries: &[PropertyMetaData]) -> Vec<u8> {
+        let mut bytes = version.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&entry_count.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for entry in entries {
+            bytes.extend_from_slice(&entry_bytes(entry));
+        }
+        bytes
+    }
+
+    fn entries() -> [PropertyMetaData; 2] {
+        let originating_dsa = Uuid::parse_str(DSA_GUID).unwrap();
+        [
+            PropertyMetaData {
+                attribute_id: 0,
+                version: 1,
+                // 2024-02-29T13:37:42Z
+                time_changed: 13_353_687_462,
+                originating_dsa,
+                originating_usn: 4100,
+                local_usn: 4100,
+            },
+            PropertyMetaData {
+                attribute_id: 0x0009_0092,
+                version: 3,
+                time_changed: 0,
+                originating_dsa,
+                originating_usn: 12_345_678_901,
+                local_usn: 20_000,
+            },
+        ]
+    }
+
+    #[test]
+    fn metadata_is_parsed() {
+        let entries = entries();
+        assert_eq!(parse_repl_property_meta_data(&blob(1, 2, &entries)), Some(entries.to_vec()));
+        assert_eq!(parse_repl_property_meta_data(&blob(1, 0, &[])), Some(Vec::new()));
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        assert_eq!(parse_repl_property_meta_data(&blob(2, 2, &entries())), None);
+    }
+
+    #[test]
+    fn truncated_metadata_is_rejected() {
+        let bytes = blob(1, 2, &entries());
+        for len in 0..bytes.len() {
+            assert_eq!(parse_repl_property_meta_data(&bytes[..len]), None);
+        }
+        // more entries than there are bytes for, up to an overflowing total size
+        assert_eq!(parse_repl_property_meta_data(&blob(1, 3, &entries())), None);
+        assert_eq!(parse_repl_property_meta_data(&blob(1, u32::MAX, &entries())), None);
+    }
+
+    #[test]
+    fn metadata_is_formatted_with_attribute_names() {
+        let formatted = format_repl_property_meta_data(
+            &entries(),
+            |id| (id == 0).then(|| "objectClass".to_owned()),
+        );
+        assert_eq!(
+            formatted,
+            format!(
+                concat!(
+                    "\n  objectClass version=1 changed=2024-02-29T13:37:42.0000000Z dsa={0} originating_usn=4100 local_usn=4100",
+                    "\n  0x00090092 version=3 changed=never dsa={0} originating_usn=12345678901 local_usn=20000",
+                ),
+                DSA_GUID,
+            ),
+        );
+    }
+}
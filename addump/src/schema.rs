@@ -1,5 +1,5 @@
 use std::collections::BTreeMap;
-use std::fmt::Write;
+use std::fmt::{self, Write};
 
 use esedb::data::Data;
 use esedb::table::{Table, Value};
@@ -15,9 +15,9 @@ use esedb::table::{Table, Value};
 // * the object class of the schema root is 196617 (1.2.840.113556.1.3.9 dMD)
 // * the naming attribute for the top objects is ATTm589825
 //   (encoding=2.5.5.12=String(Unicode) attribute=1.2.840.113556.1.4.1 name)
-// * there are up to two instances of this object
-// * if there are two, one of the instances is $ROOT_OBJECT$ -> Boot -> Schema,
-//   which we are not interested in
+// * there are usually two instances of this object, one of which is
+//   $ROOT_OBJECT$ -> Boot -> Schema, which we are not interested in
+// * if more than one instance remains, the one with the most attributes is the schema
 //
 // we make further assumptions when decoding the schema:
 // * attributes and classes are immediate children of the schema root
@@ -36,6 +36,13 @@ use esedb::table::{Table, Value};
 //   (encoding=2.5.5.2=Object-Identifier attribute=1.2.840.113556.1.2.32 attributeSyntax)
 // * taking the value of ATTc131104, subtracting 0x0008_0000 and adding 'a' returns the
 //   letter between "ATT" and the attribute ID in the database column names
+// * the attribute for the link ID of a linked attribute is ATTj131122
+//   (encoding=2.5.5.9=Integer attribute=1.2.840.113556.1.2.50 linkID); forward links have even
+//   link IDs, the matching backlink has the following odd ID, and both are stored in link_table
+//   under link_base = linkID / 2
+//
+// the DNT of $ROOT_OBJECT$, the object classes and the columns used to find the schema root can be
+// changed through SchemaConfig
 
 
 // finding the schema:
@@ -47,6 +54,9 @@ pub const BOOT_OBJECT_NAME: &str = "Boot";
 pub const ROOT_OBJECT_DNT: i32 = 2;
 pub const SCHEMA_ROOT_OBJECT_CLASS: i32 = 196617;
 
+// building distinguished names:
+pub const RDN_TYPE_COLUMN_NAME: &str = "RDNtyp_col";
+
 // taking the schema apart:
 pub const SCHEMA_OBJECT_LDAP_NAME_COLUMN_NAME: &str = "ATTm131532";
 pub const SCHEMA_CLASS_OBJECT_CLASS: i32 = 196621;
@@ -54,6 +64,19 @@ pub const SCHEMA_CLASS_OBJECT_CLASS_COLUMN_NAME: &str = "ATTc131094";
 pub const SCHEMA_ATTRIBUTE_OBJECT_CLASS: i32 = 196622;
 pub const SCHEMA_ATTRIBUTE_ID_COLUMN_NAME: &str = "ATTc131102";
 pub const SCHEMA_ATTRIBUTE_SYNTAX_COLUMN_NAME: &str = "ATTc131104";
+pub const SCHEMA_ATTRIBUTE_LINK_ID_COLUMN_NAME: &str = "ATTj131122";
+
+// interpreting values:
+pub const GUID_ATTRIBUTE_NAMES: [&str; 6] = [
+    "attributeSecurityGUID", "invocationId", "objectGUID", "schemaIDGUID", "mS-DS-ConsistencyGuid",
+    "msDS-OptionalFeatureGUID",
+];
+pub const SYNTAX_DISTINGUISHED_NAME: i32 = 0x0008_0001; // 2.5.5.1, stored as the DNT of the object
+pub const SYNTAX_OBJECT_IDENTIFIER: i32 = 0x0008_0002; // 2.5.5.2, stored as an attribute or class ID
+pub const SYNTAX_BOOLEAN: i32 = 0x0008_0008; // 2.5.5.8
+pub const SYNTAX_OCTET_STRING: i32 = 0x0008_000A; // 2.5.5.10
+pub const SYNTAX_GENERALIZED_TIME: i32 = 0x0008_000B; // 2.5.5.11, stored as seconds since 1601
+pub const SYNTAX_SID: i32 = 0x0008_0011; // 2.5.5.17, with the RID stored big-endian
 
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -61,8 +84,20 @@ pub struct Attribute {
     pub id: i32,
     pub syntax: i32,
     pub ldap_name: String,
+    pub link_id: Option<i32>,
 }
 impl Attribute {
+    /// Whether the values of this attribute are GUIDs.
+    ///
+    /// There is no dedicated syntax for GUIDs; they are octet strings, recognized by their name.
+    pub fn is_guid(&self) -> bool {
+        if GUID_ATTRIBUTE_NAMES.contains(&self.ldap_name.as_str()) {
+            return true;
+        }
+        self.syntax == SYNTAX_OCTET_STRING
+            && self.ldap_name.to_lowercase().ends_with("guid")
+    }
+
     pub fn to_column_name(&self) -> String {
         let mut ret = String::with_capacity(3 + 1 + 6);
         ret.push_str("ATT");
@@ -85,149 +120,254 @@ pub struct ObjectClass {
     pub ldap_name: String,
 }
 
-pub fn find_schema_root<'t, 'r>(data_table: &'t Table, data_rows: &'r [BTreeMap<i32, Value>]) -> &'r BTreeMap<i32, Value> {
+/// The assumptions made when bootstrapping the schema.
+///
+/// The defaults match the databases written by Active Directory; see the comment at the top of this
+/// module.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SchemaConfig {
+    pub dnt_column_name: String,
+    pub parent_dnt_column_name: String,
+    pub object_class_column_name: String,
+    pub top_object_name_column_name: String,
+    pub boot_object_name: String,
+    pub root_object_dnt: i32,
+    pub schema_root_object_class: i32,
+    pub schema_class_object_class: i32,
+    pub schema_attribute_object_class: i32,
+
+    /// The DNT of the schema root; if `None`, it is searched for.
+    pub schema_root_dnt: Option<i32>,
+}
+impl Default for SchemaConfig {
+    fn default() -> Self {
+        Self {
+            dnt_column_name: DNT_COLUMN_NAME.to_owned(),
+            parent_dnt_column_name: PARENT_DNT_COLUMN_NAME.to_owned(),
+            object_class_column_name: OBJECT_CLASS_COLUMN_NAME.to_owned(),
+            top_object_name_column_name: TOP_OBJECT_NAME_COLUMN_NAME.to_owned(),
+            boot_object_name: BOOT_OBJECT_NAME.to_owned(),
+            root_object_dnt: ROOT_OBJECT_DNT,
+            schema_root_object_class: SCHEMA_ROOT_OBJECT_CLASS,
+            schema_class_object_class: SCHEMA_CLASS_OBJECT_CLASS,
+            schema_attribute_object_class: SCHEMA_ATTRIBUTE_OBJECT_CLASS,
+            schema_root_dnt: None,
+        }
+    }
+}
+
+
+/// An error encountered while bootstrapping the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SchemaError {
+    /// The datatable has no column with the given name.
+    MissingColumn { column_name: String, description: &'static str },
+
+    /// An object has no DNT or its DNT is not a long integer.
+    InvalidDnt { description: &'static str, value: Option<Value> },
+
+    /// No object has the object class of the schema root.
+    SchemaRootNotFound { object_class: i32 },
+
+    /// The object that has been chosen as the schema root does not exist or is not a schema root.
+    ChosenSchemaRootInvalid { dnt: i32 },
+
+    /// Multiple objects may be the schema root and none has more attributes than the others.
+    AmbiguousSchemaRoot { candidate_dnts: Vec<i32> },
+}
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingColumn { column_name, description }
+                => write!(f, "datatable has no {} column {:?}", description, column_name),
+            Self::InvalidDnt { description, value: Some(value) }
+                => write!(f, "{} has an invalid DNT {:?}", description, value),
+            Self::InvalidDnt { description, value: None }
+                => write!(f, "{} has no DNT", description),
+            Self::SchemaRootNotFound { object_class }
+                => write!(f, "no schema root (object of class {}) found", object_class),
+            Self::ChosenSchemaRootInvalid { dnt }
+                => write!(f, "object with DNT {} is not a schema root", dnt),
+            Self::AmbiguousSchemaRoot { candidate_dnts } => {
+                write!(f, "cannot choose between schema root candidates with DNTs")?;
+                for (i, dnt) in candidate_dnts.iter().enumerate() {
+                    let separator = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}", separator, dnt)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+impl std::error::Error for SchemaError {
+}
+
+
+/// Finds the root object of the schema.
+///
+/// If the configuration specifies the DNT of the schema root, that object is returned. Otherwise,
+/// instances of the schema root below Boot are ignored; if multiple candidates remain, the one with
+/// the most attributes as its children is chosen.
+pub fn find_schema_root<'r>(data_table: &Table, data_rows: &'r [BTreeMap<i32, Value>], config: &SchemaConfig) -> Result<&'r BTreeMap<i32, Value>, SchemaError> {
     // obtain some important column indexes
-    let dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == DNT_COLUMN_NAME)
-        .expect("failed to find key (DNT) column while bootstrapping schema")
-        .column_id;
-    let parent_dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == PARENT_DNT_COLUMN_NAME)
-        .expect("failed to find parent reference column while bootstrapping schema")
-        .column_id;
-    let object_class_column_index = data_table.columns.iter()
-        .find(|c| c.name == OBJECT_CLASS_COLUMN_NAME)
-        .expect("failed to find objectClass column while bootstrapping schema")
-        .column_id;
-    let top_name_column_index = data_table.columns.iter()
-        .find(|c| c.name == TOP_OBJECT_NAME_COLUMN_NAME)
-        .expect("failed to find top naming column while bootstrapping schema")
-        .column_id;
+    let dnt_column_index = find_column_id(data_table, &config.dnt_column_name, "key (DNT)")?;
+    let parent_dnt_column_index = find_column_id(data_table, &config.parent_dnt_column_name, "parent reference")?;
+    let object_class_column_index = find_column_id(data_table, &config.object_class_column_name, "objectClass")?;
+    let top_name_column_index = find_column_id(data_table, &config.top_object_name_column_name, "top naming")?;
+
+    let is_schema_root = |row: &BTreeMap<i32, Value>|
+        column_contains_value(row, object_class_column_index, &Data::Long(config.schema_root_object_class));
+
+    if let Some(chosen_dnt) = config.schema_root_dnt {
+        return data_rows.iter()
+            .find(|row| column_contains_value(row, dnt_column_index, &Data::Long(chosen_dnt)))
+            .filter(|row| is_schema_root(row))
+            .ok_or(SchemaError::ChosenSchemaRootInvalid { dnt: chosen_dnt });
+    }
 
     // find Boot
     let boot_entry_opt = data_rows.iter()
         .find(|row|
-            column_contains_value(row, parent_dnt_column_index, &Data::Long(ROOT_OBJECT_DNT))
-            && column_contains_value(row, top_name_column_index, &Data::LongText(BOOT_OBJECT_NAME.to_owned()))
+            column_contains_value(row, parent_dnt_column_index, &Data::Long(config.root_object_dnt))
+            && column_contains_value(row, top_name_column_index, &Data::LongText(config.boot_object_name.clone()))
         );
     let boot_dnt_opt = if let Some(boot_entry) = boot_entry_opt {
-        let boot_dnt_value = boot_entry.get(&dnt_column_index)
-            .expect("Boot entry has no DNT?!");
-        Some(extract_dnt(boot_dnt_value))
+        Some(row_dnt(boot_entry, dnt_column_index, "Boot object")?)
     } else {
         None
     };
 
-    // find schema root that is not a child of Boot
-    let schema_root = data_rows.iter()
-        .find(|row|
+    // find schema roots that are not children of Boot
+    let candidates: Vec<&BTreeMap<i32, Value>> = data_rows.iter()
+        .filter(|row|
             boot_dnt_opt.map(|boot_dnt| !column_contains_value(row, parent_dnt_column_index, &Data::Long(boot_dnt)))
                 .unwrap_or(true)
-            && column_contains_value(row, object_class_column_index, &Data::Long(SCHEMA_ROOT_OBJECT_CLASS))
+            && is_schema_root(row)
         )
-        .expect("schema root not found");
-    schema_root
+        .collect();
+    match candidates.as_slice() {
+        [] => return Err(SchemaError::SchemaRootNotFound { object_class: config.schema_root_object_class }),
+        [single] => return Ok(single),
+        _ => {},
+    }
+
+    // count the attributes below each candidate
+    let mut candidate_dnt_to_attribute_count = BTreeMap::new();
+    for candidate in &candidates {
+        let candidate_dnt = row_dnt(candidate, dnt_column_index, "schema root candidate")?;
+        candidate_dnt_to_attribute_count.insert(candidate_dnt, 0usize);
+    }
+    for row in data_rows {
+        if !column_contains_value(row, object_class_column_index, &Data::Long(config.schema_attribute_object_class)) {
+            continue;
+        }
+        let Some(Data::Long(parent_dnt)) = get_first_value(row, parent_dnt_column_index) else { continue };
+        if let Some(count) = candidate_dnt_to_attribute_count.get_mut(parent_dnt) {
+            *count += 1;
+        }
+    }
+
+    let max_count = candidate_dnt_to_attribute_count.values().copied().max().unwrap_or(0);
+    let best_dnts: Vec<i32> = candidate_dnt_to_attribute_count.iter()
+        .filter(|(_dnt, count)| **count == max_count)
+        .map(|(dnt, _count)| *dnt)
+        .collect();
+    let [best_dnt] = best_dnts.as_slice() else {
+        return Err(SchemaError::AmbiguousSchemaRoot { candidate_dnts: best_dnts });
+    };
+    let best = candidates.into_iter()
+        .find(|row| column_contains_value(row, dnt_column_index, &Data::Long(*best_dnt)))
+        .unwrap();
+    Ok(best)
 }
 
-pub fn collect_schema_classes(data_table: &Table, data_rows: &[BTreeMap<i32, Value>], schema_root: &BTreeMap<i32, Value>) -> BTreeMap<i32, ObjectClass> {
+pub fn collect_schema_classes(data_table: &Table, data_rows: &[BTreeMap<i32, Value>], schema_root: &BTreeMap<i32, Value>, config: &SchemaConfig) -> Result<BTreeMap<i32, ObjectClass>, SchemaError> {
     // obtain some important column indexes
-    let dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == DNT_COLUMN_NAME)
-        .expect("failed to find key (DNT) column while bootstrapping schema")
-        .column_id;
-    let parent_dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == PARENT_DNT_COLUMN_NAME)
-        .expect("failed to find parent reference column while bootstrapping schema")
-        .column_id;
-    let object_class_column_index = data_table.columns.iter()
-        .find(|c| c.name == OBJECT_CLASS_COLUMN_NAME)
-        .expect("failed to find objectClass column while bootstrapping schema")
-        .column_id;
-
-    let schema_object_class_column_index = data_table.columns.iter()
-        .find(|c| c.name == SCHEMA_CLASS_OBJECT_CLASS_COLUMN_NAME)
-        .expect("failed to find governsID column while bootstrapping schema")
-        .column_id;
-    let ldap_name_column_index = data_table.columns.iter()
-        .find(|c| c.name == SCHEMA_OBJECT_LDAP_NAME_COLUMN_NAME)
-        .expect("failed to find lDAPDisplayName column while bootstrapping schema")
-        .column_id;
-
-    let schema_root_dnt_value = schema_root.get(&dnt_column_index)
-        .expect("schema root has no DNT?!");
-    let schema_root_dnt = extract_dnt(schema_root_dnt_value);
+    let dnt_column_index = find_column_id(data_table, &config.dnt_column_name, "key (DNT)")?;
+    let parent_dnt_column_index = find_column_id(data_table, &config.parent_dnt_column_name, "parent reference")?;
+    let object_class_column_index = find_column_id(data_table, &config.object_class_column_name, "objectClass")?;
+
+    let schema_object_class_column_index = find_column_id(data_table, SCHEMA_CLASS_OBJECT_CLASS_COLUMN_NAME, "governsID")?;
+    let ldap_name_column_index = find_column_id(data_table, SCHEMA_OBJECT_LDAP_NAME_COLUMN_NAME, "lDAPDisplayName")?;
+
+    let schema_root_dnt = row_dnt(schema_root, dnt_column_index, "schema root")?;
 
     // find the class children
     let mut id_to_object_class = BTreeMap::new();
     let class_rows = data_rows.iter()
         .filter(|row|
             column_contains_value(row, parent_dnt_column_index, &Data::Long(schema_root_dnt))
-            && column_contains_value(row, object_class_column_index, &Data::Long(SCHEMA_CLASS_OBJECT_CLASS))
+            && column_contains_value(row, object_class_column_index, &Data::Long(config.schema_class_object_class))
         );
     for class_row in class_rows {
         let Some(Data::Long(schema_object_class)) = get_first_value(class_row, schema_object_class_column_index) else { continue };
-        let Some(Data::LongText(ldap_name)) = get_first_value(class_row, ldap_name_column_index) else { continue };
+        let Some(ldap_name) = get_first_value(class_row, ldap_name_column_index).and_then(Data::as_str) else { continue };
         let object_class = ObjectClass {
             id: *schema_object_class,
-            ldap_name: ldap_name.clone(),
+            ldap_name: ldap_name.to_owned(),
         };
         id_to_object_class.insert(object_class.id, object_class);
     }
-    id_to_object_class
+    Ok(id_to_object_class)
 }
 
-pub fn collect_schema_attributes(data_table: &Table, data_rows: &[BTreeMap<i32, Value>], schema_root: &BTreeMap<i32, Value>) -> BTreeMap<String, Attribute> {
+pub fn collect_schema_attributes(data_table: &Table, data_rows: &[BTreeMap<i32, Value>], schema_root: &BTreeMap<i32, Value>, config: &SchemaConfig) -> Result<BTreeMap<String, Attribute>, SchemaError> {
     // obtain some important column indexes
-    let dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == DNT_COLUMN_NAME)
-        .expect("failed to find key (DNT) column while bootstrapping schema")
-        .column_id;
-    let parent_dnt_column_index = data_table.columns.iter()
-        .find(|c| c.name == PARENT_DNT_COLUMN_NAME)
-        .expect("failed to find parent reference column while bootstrapping schema")
-        .column_id;
-    let object_class_column_index = data_table.columns.iter()
-        .find(|c| c.name == OBJECT_CLASS_COLUMN_NAME)
-        .expect("failed to find objectClass column while bootstrapping schema")
-        .column_id;
-
-    let attribute_id_column_index = data_table.columns.iter()
-        .find(|c| c.name == SCHEMA_ATTRIBUTE_ID_COLUMN_NAME)
-        .expect("failed to find attributeID column while bootstrapping schema")
-        .column_id;
-    let attribute_syntax_column_index = data_table.columns.iter()
-        .find(|c| c.name == SCHEMA_ATTRIBUTE_SYNTAX_COLUMN_NAME)
-        .expect("failed to find attributeSyntax column while bootstrapping schema")
-        .column_id;
-    let ldap_name_column_index = data_table.columns.iter()
-        .find(|c| c.name == SCHEMA_OBJECT_LDAP_NAME_COLUMN_NAME)
-        .expect("failed to find lDAPDisplayName column while bootstrapping schema")
-        .column_id;
-
-    let schema_root_dnt_value = schema_root.get(&dnt_column_index)
-        .expect("schema root has no DNT?!");
-    let schema_root_dnt = extract_dnt(schema_root_dnt_value);
+    let dnt_column_index = find_column_id(data_table, &config.dnt_column_name, "key (DNT)")?;
+    let parent_dnt_column_index = find_column_id(data_table, &config.parent_dnt_column_name, "parent reference")?;
+    let object_class_column_index = find_column_id(data_table, &config.object_class_column_name, "objectClass")?;
+
+    let attribute_id_column_index = find_column_id(data_table, SCHEMA_ATTRIBUTE_ID_COLUMN_NAME, "attributeID")?;
+    let attribute_syntax_column_index = find_column_id(data_table, SCHEMA_ATTRIBUTE_SYNTAX_COLUMN_NAME, "attributeSyntax")?;
+    let link_id_column_index_opt = data_table.columns.iter()
+        .find(|c| c.name == SCHEMA_ATTRIBUTE_LINK_ID_COLUMN_NAME)
+        .map(|c| c.column_id);
+    let ldap_name_column_index = find_column_id(data_table, SCHEMA_OBJECT_LDAP_NAME_COLUMN_NAME, "lDAPDisplayName")?;
+
+    let schema_root_dnt = row_dnt(schema_root, dnt_column_index, "schema root")?;
 
     // find the class children
     let mut database_column_to_attribute = BTreeMap::new();
     let attribute_rows = data_rows.iter()
         .filter(|row|
             column_contains_value(row, parent_dnt_column_index, &Data::Long(schema_root_dnt))
-            && column_contains_value(row, object_class_column_index, &Data::Long(SCHEMA_ATTRIBUTE_OBJECT_CLASS))
+            && column_contains_value(row, object_class_column_index, &Data::Long(config.schema_attribute_object_class))
         );
     for attribute_row in attribute_rows {
         let Some(Data::Long(attribute_id)) = get_first_value(attribute_row, attribute_id_column_index) else { continue };
         let Some(Data::Long(syntax)) = get_first_value(attribute_row, attribute_syntax_column_index) else { continue };
-        let Some(Data::LongText(ldap_name)) = get_first_value(attribute_row, ldap_name_column_index) else { continue };
+        let Some(ldap_name) = get_first_value(attribute_row, ldap_name_column_index).and_then(Data::as_str) else { continue };
+        let link_id = match link_id_column_index_opt.and_then(|i| get_first_value(attribute_row, i)) {
+            Some(Data::Long(link_id)) => Some(*link_id),
+            _ => None,
+        };
         let attribute = Attribute {
             id: *attribute_id,
             syntax: *syntax,
-            ldap_name: ldap_name.clone(),
+            ldap_name: ldap_name.to_owned(),
+            link_id,
         };
         let column_name = attribute.to_column_name();
         database_column_to_attribute.insert(column_name, attribute);
     }
-    database_column_to_attribute
+    Ok(database_column_to_attribute)
+}
+
+/// Returns the ID of the datatable column with the given name.
+pub fn find_column_id(data_table: &Table, column_name: &str, description: &'static str) -> Result<i32, SchemaError> {
+    data_table.columns.iter()
+        .find(|c| c.name == column_name)
+        .map(|c| c.column_id)
+        .ok_or_else(|| SchemaError::MissingColumn { column_name: column_name.to_owned(), description })
+}
+
+/// Returns the DNT of the given object.
+fn row_dnt(row: &BTreeMap<i32, Value>, dnt_column_index: i32, description: &'static str) -> Result<i32, SchemaError> {
+    let dnt_value = row.get(&dnt_column_index);
+    match dnt_value.and_then(Value::single) {
+        Some(Data::Long(dnt)) => Ok(*dnt),
+        _ => Err(SchemaError::InvalidDnt { description, value: dnt_value.cloned() }),
+    }
 }
 
 fn column_contains_value(row: &BTreeMap<i32, Value>, column_index: i32, expected_value: &Data) -> bool {
@@ -236,18 +376,13 @@ fn column_contains_value(row: &BTreeMap<i32, Value>, column_index: i32, expected
         .any(|v| v == expected_value)
 }
 
-fn get_first_value(row: &BTreeMap<i32, Value>, column_index: i32) -> Option<&Data> {
-    let value = row.get(&column_index)?;
-    match value {
-        Value::Simple(data) => Some(data),
-        Value::Complex { data, .. } => Some(data),
-        Value::Multiple { values, .. } => values.get(0),
-    }
+pub fn get_first_value(row: &BTreeMap<i32, Value>, column_index: i32) -> Option<&Data> {
+    row.get(&column_index)?.first()
 }
 
-fn extract_dnt(dnt_value: &Value) -> i32 {
-    match dnt_value {
-        Value::Simple(Data::Long(dnt)) => *dnt,
+pub fn extract_dnt(dnt_value: &Value) -> i32 {
+    match dnt_value.single() {
+        Some(Data::Long(dnt)) => *dnt,
         _ => panic!("unexpected DNT value {:?}", dnt_value),
     }
 }
@@ -0,0 +1,451 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use esedb::data::Data;
+use esedb::table::{Table, Value};
+use uuid::Uuid;
+
+use crate::schema::get_first_value;
+use crate::values::format_sid;
+
+
+pub const SECURITY_DESCRIPTOR_ATTRIBUTE_NAME: &str = "nTSecurityDescriptor";
+pub const SD_TABLE_NAME: &str = "sd_table";
+pub const SD_ID_COLUMN_NAME: &str = "sd_id";
+pub const SD_VALUE_COLUMN_NAME: &str = "sd_value";
+
+/// The size of the header of a self-relative security descriptor.
+const DESCRIPTOR_HEADER_SIZE: usize = 20;
+
+/// The size of the header of an access control list.
+const ACL_HEADER_SIZE: usize = 8;
+
+/// The size of the header of an access control entry.
+const ACE_HEADER_SIZE: usize = 4;
+
+// security descriptor control flags
+const SE_DACL_PRESENT: u16 = 0x0004;
+const SE_SACL_PRESENT: u16 = 0x0010;
+const SE_DACL_AUTO_INHERIT_REQ: u16 = 0x0100;
+const SE_SACL_AUTO_INHERIT_REQ: u16 = 0x0200;
+const SE_DACL_AUTO_INHERITED: u16 = 0x0400;
+const SE_SACL_AUTO_INHERITED: u16 = 0x0800;
+const SE_DACL_PROTECTED: u16 = 0x1000;
+const SE_SACL_PROTECTED: u16 = 0x2000;
+
+// object ACE flags
+const ACE_OBJECT_TYPE_PRESENT: u32 = 0x0000_0001;
+const ACE_INHERITED_OBJECT_TYPE_PRESENT: u32 = 0x0000_0002;
+
+/// The SDDL abbreviations of the ACE types, indexed by type.
+const ACE_TYPE_ABBREVIATIONS: [&str; 0x14] = [
+    "A", "D", "AU", "AL", "", "OA", "OD", "OU", "OL", "XA", "XD", "ZA", "", "XU", "", "", "", "ML", "RA", "SP",
+];
+
+/// The SDDL abbreviations of the ACE flags.
+const ACE_FLAG_ABBREVIATIONS: [(u8, &str); 7] = [
+    (0x01, "OI"), (0x02, "CI"), (0x04, "NP"), (0x08, "IO"), (0x10, "ID"), (0x40, "SA"), (0x80, "FA"),
+];
+
+
+/// A security descriptor in self-relative format.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct SecurityDescriptor {
+    pub revision: u8,
+    pub control: u16,
+    pub owner: Option<Vec<u8>>,
+    pub group: Option<Vec<u8>>,
+    pub sacl: Option<Acl>,
+    pub dacl: Option<Acl>,
+}
+
+/// An access control list.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Acl {
+    pub revision: u8,
+    pub entries: Vec<Ace>,
+}
+
+/// An access control entry.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Ace {
+    pub ace_type: u8,
+    pub flags: u8,
+    pub access_mask: u32,
+    pub object_type: Option<Uuid>,
+    pub inherited_object_type: Option<Uuid>,
+    pub sid: Vec<u8>,
+}
+impl Ace {
+    /// Whether entries of this type contain object types (GUIDs) before the SID.
+    pub fn is_object_type(ace_type: u8) -> bool {
+        matches!(ace_type, 0x05..=0x08 | 0x0B | 0x0C | 0x0F | 0x10)
+    }
+}
+
+
+/// Collects the security descriptors stored in the SD table, keyed by their ID.
+pub fn collect_security_descriptors(sd_table: &Table, sd_rows: &[BTreeMap<i32, Value>]) -> BTreeMap<i64, Vec<u8>> {
+    let id_column_index = sd_table.columns.iter()
+        .find(|c| c.name == SD_ID_COLUMN_NAME)
+        .expect("failed to find security descriptor ID column")
+        .column_id;
+    let value_column_index = sd_table.columns.iter()
+        .find(|c| c.name == SD_VALUE_COLUMN_NAME)
+        .expect("failed to find security descriptor value column")
+        .column_id;
+
+    let mut id_to_descriptor = BTreeMap::new();
+    for sd_row in sd_rows {
+        // older databases predate the LongLong type and store the ID as Currency
+        let Some(Data::LongLong(sd_id)|Data::Currency(sd_id)) = get_first_value(sd_row, id_column_index) else { continue };
+        let Some(descriptor) = get_first_value(sd_row, value_column_index).and_then(Data::as_bytes) else { continue };
+        id_to_descriptor.insert(*sd_id, descriptor.to_vec());
+    }
+    id_to_descriptor
+}
+
+/// Obtains the security descriptor referenced by a value of the nTSecurityDescriptor attribute.
+///
+/// Current databases store the 8-byte ID of the descriptor in the SD table; older ones store the
+/// descriptor itself.
+pub fn resolve_security_descriptor<'a>(value: &'a [u8], id_to_descriptor: &'a BTreeMap<i64, Vec<u8>>) -> Option<&'a [u8]> {
+    if value.len() == 8 {
+        let sd_id = i64::from_le_bytes(value.try_into().unwrap());
+        id_to_descriptor.get(&sd_id)
+            .map(|d| d.as_slice())
+    } else {
+        Some(value)
+    }
+}
+
+/// Parses a self-relative security descriptor.
+///
+/// Returns `None` if the descriptor is truncated or refers to data outside of itself.
+pub fn parse_security_descriptor(bytes: &[u8]) -> Option<SecurityDescriptor> {
+    // header: revision (u8), padding (u8), control (u16), offsets of owner, group, SACL and DACL (u32)
+    let header = bytes.get(0..DESCRIPTOR_HEADER_SIZE)?;
+    let revision = header[0];
+    let control = u16::from_le_bytes(header[2..4].try_into().unwrap());
+    let owner_offset = read_offset(&header[4..8]);
+    let group_offset = read_offset(&header[8..12]);
+    let sacl_offset = read_offset(&header[12..16]);
+    let dacl_offset = read_offset(&header[16..20]);
+
+    let owner = match owner_offset {
+        0 => None,
+        offset => Some(parse_sid(bytes.get(offset..)?)?.to_vec()),
+    };
+    let group = match group_offset {
+        0 => None,
+        offset => Some(parse_sid(bytes.get(offset..)?)?.to_vec()),
+    };
+    let sacl = match sacl_offset {
+        0 => None,
+        offset => Some(parse_acl(bytes.get(offset..)?)?),
+    };
+    let dacl = match dacl_offset {
+        0 => None,
+        offset => Some(parse_acl(bytes.get(offset..)?)?),
+    };
+
+    Some(SecurityDescriptor {
+        revision,
+        control,
+        owner,
+        group,
+        sacl,
+        dacl,
+    })
+}
+
+fn read_offset(bytes: &[u8]) -> usize {
+    u32::from_le_bytes(bytes.try_into().unwrap()).try_into().unwrap()
+}
+
+/// Returns the bytes of the SID at the start of the buffer.
+fn parse_sid(bytes: &[u8]) -> Option<&[u8]> {
+    // revision (u8), sub-authority count (u8), identifier authority (6 bytes), sub-authorities (u32 each)
+    let sub_authority_count = usize::from(*bytes.get(1)?);
+    bytes.get(0..8 + 4*sub_authority_count)
+}
+
+fn parse_acl(bytes: &[u8]) -> Option<Acl> {
+    // header: revision (u8), padding (u8), size (u16), entry count (u16), padding (u16)
+    let header = bytes.get(0..ACL_HEADER_SIZE)?;
+    let revision = header[0];
+    let size = usize::from(u16::from_le_bytes(header[2..4].try_into().unwrap()));
+    let entry_count = usize::from(u16::from_le_bytes(header[4..6].try_into().unwrap()));
+    let acl_bytes = bytes.get(0..size)?;
+
+    let mut entries = Vec::with_capacity(entry_count);
+    let mut offset = ACL_HEADER_SIZE;
+    for _ in 0..entry_count {
+        let ace_bytes = acl_bytes.get(offset..)?;
+        let ace_size = usize::from(u16::from_le_bytes(ace_bytes.get(2..4)?.try_into().unwrap()));
+        if ace_size < ACE_HEADER_SIZE {
+            return None;
+        }
+        entries.push(parse_ace(ace_bytes.get(0..ace_size)?)?);
+        offset += ace_size;
+    }
+    Some(Acl {
+        revision,
+        entries,
+    })
+}
+
+fn parse_ace(bytes: &[u8]) -> Option<Ace> {
+    // header: type (u8), flags (u8), size (u16); then the access mask (u32)
+    let ace_type = bytes[0];
+    let flags = bytes[1];
+    let access_mask = u32::from_le_bytes(bytes.get(4..8)?.try_into().unwrap());
+
+    // object entries: object flags (u32), object type (GUID), inherited object type (GUID)
+    // all entries (that we care about) then contain the SID
+    let mut offset = 8;
+    let mut object_type = None;
+    let mut inherited_object_type = None;
+    if Ace::is_object_type(ace_type) {
+        let object_flags = u32::from_le_bytes(bytes.get(8..12)?.try_into().unwrap());
+        offset = 12;
+        if object_flags & ACE_OBJECT_TYPE_PRESENT != 0 {
+            object_type = Some(Uuid::from_bytes_le(bytes.get(offset..offset+16)?.try_into().unwrap()));
+            offset += 16;
+        }
+        if object_flags & ACE_INHERITED_OBJECT_TYPE_PRESENT != 0 {
+            inherited_object_type = Some(Uuid::from_bytes_le(bytes.get(offset..offset+16)?.try_into().unwrap()));
+            offset += 16;
+        }
+    }
+    let sid = parse_sid(bytes.get(offset..)?)?.to_vec();
+
+    Some(Ace {
+        ace_type,
+        flags,
+        access_mask,
+        object_type,
+        inherited_object_type,
+        sid,
+    })
+}
+
+/// Formats a security descriptor in the Security Descriptor Definition Language (SDDL).
+///
+/// SIDs are always output in their numeric form and access masks in hexadecimal.
+pub fn format_sddl(descriptor: &SecurityDescriptor) -> String {
+    let mut ret = String::new();
+    if let Some(owner) = &descriptor.owner {
+        write!(ret, "O:{}", format_sddl_sid(owner)).unwrap();
+    }
+    if let Some(group) = &descriptor.group {
+        write!(ret, "G:{}", format_sddl_sid(group)).unwrap();
+    }
+    if descriptor.control & SE_DACL_PRESENT != 0 {
+        ret.push_str("D:");
+        push_acl_flags(&mut ret, descriptor.control, SE_DACL_PROTECTED, SE_DACL_AUTO_INHERIT_REQ, SE_DACL_AUTO_INHERITED);
+        push_acl(&mut ret, descriptor.dacl.as_ref());
+    }
+    if descriptor.control & SE_SACL_PRESENT != 0 {
+        ret.push_str("S:");
+        push_acl_flags(&mut ret, descriptor.control, SE_SACL_PROTECTED, SE_SACL_AUTO_INHERIT_REQ, SE_SACL_AUTO_INHERITED);
+        push_acl(&mut ret, descriptor.sacl.as_ref());
+    }
+    ret
+}
+
+fn format_sddl_sid(sid: &[u8]) -> String {
+    format_sid(sid)
+        .unwrap_or_else(|| "<invalid SID>".to_owned())
+}
+
+fn push_acl_flags(ret: &mut String, control: u16, protected: u16, auto_inherit_req: u16, auto_inherited: u16) {
+    if control & protected != 0 {
+        ret.push('P');
+    }
+    if control & auto_inherit_req != 0 {
+        ret.push_str("AR");
+    }
+    if control & auto_inherited != 0 {
+        ret.push_str("AI");
+    }
+}
+
+fn push_acl(ret: &mut String, acl: Option<&Acl>) {
+    let Some(acl) = acl else {
+        // present but null
+        ret.push_str("NO_ACCESS_CONTROL");
+        return;
+    };
+    for entry in &acl.entries {
+        let type_abbreviation = ACE_TYPE_ABBREVIATIONS.get(usize::from(entry.ace_type))
+            .copied()
+            .filter(|a| !a.is_empty());
+        ret.push('(');
+        match type_abbreviation {
+            Some(abbreviation) => ret.push_str(abbreviation),
+            None => write!(ret, "0x{:02X}", entry.ace_type).unwrap(),
+        }
+        ret.push(';');
+        for (flag, abbreviation) in ACE_FLAG_ABBREVIATIONS {
+            if entry.flags & flag != 0 {
+                ret.push_str(abbreviation);
+            }
+        }
+        write!(ret, ";0x{:08X};", entry.access_mask).unwrap();
+        if let Some(object_type) = entry.object_type {
+            write!(ret, "{}", object_type).unwrap();
+        }
+        ret.push(';');
+        if let Some(inherited_object_type) = entry.inherited_object_type {
+            write!(ret, "{}", inherited_object_type).unwrap();
+        }
+        write!(ret, ";{})", format_sddl_sid(&entry.sid)).unwrap();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_CLASS_GUID: &str = "bf967aba-0de6-11d0-a285-00aa003049e2";
+
+    /// A binary SID with the NT authority (5) and the given sub-authorities.
+    fn nt_sid(sub_authorities: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![1, sub_authorities.len().try_into().unwrap(), 0, 0, 0, 0, 0, 5];
+        for sub_authority in sub_authorities {
+            bytes.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn ace(ace_type: u8, flags: u8, access_mask: u32, object_type: Option<Uuid>, sid: &[u8]) -> Vec<u8> {
+        let mut body = access_mask.to_le_bytes().to_vec();
+        if Ace::is_object_type(ace_type) {
+            let object_flags = if object_type.is_some() { ACE_OBJECT_TYPE_PRESENT } else { 0 };
+            body.extend_from_slice(&object_flags.to_le_bytes());
+            if let Some(object_type) = object_type {
+                body.extend_from_slice(&object_type.to_bytes_le());
+            }
+        }
+        body.extend_from_slice(sid);
+
+        let mut bytes = vec![ace_type, flags];
+        bytes.extend_from_slice(&u16::try_from(ACE_HEADER_SIZE + body.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    fn acl(entries: &[Vec<u8>]) -> Vec<u8> {
+        let size = ACL_HEADER_SIZE + entries.iter().map(|e| e.len()).sum::<usize>();
+        let mut bytes = vec![2, 0];
+        bytes.extend_from_slice(&u16::try_from(size).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&u16::try_from(entries.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        for entry in entries {
+            bytes.extend_from_slice(entry);
+        }
+        bytes
+    }
+
+    /// A self-relative security descriptor with the given parts, stored in this order after the header.
+    fn descriptor(control: u16, owner: Option<&[u8]>, group: Option<&[u8]>, dacl: Option<&[u8]>) -> Vec<u8> {
+        let mut header = vec![1, 0];
+        header.extend_from_slice(&control.to_le_bytes());
+        let mut parts = Vec::new();
+        // owner, group, SACL, DACL
+        for part in [owner, group, None, dacl] {
+            let offset = match part {
+                Some(part) => {
+                    let offset = DESCRIPTOR_HEADER_SIZE + parts.len();
+                    parts.extend_from_slice(part);
+                    u32::try_from(offset).unwrap()
+                },
+                None => 0,
+            };
+            header.extend_from_slice(&offset.to_le_bytes());
+        }
+        header.extend_from_slice(&parts);
+        header
+    }
+
+    fn full_descriptor() -> Vec<u8> {
+        let administrators = nt_sid(&[32, 544]);
+        let dacl = acl(&[
+            ace(0x00, 0x02, 0x000F_01FF, None, &administrators),
+            ace(0x05, 0x0A, 0x0000_0010, Some(Uuid::parse_str(USER_CLASS_GUID).unwrap()), &nt_sid(&[11])),
+        ]);
+        let control = SE_DACL_PRESENT | SE_DACL_PROTECTED | SE_DACL_AUTO_INHERITED;
+        descriptor(control, Some(&administrators), Some(&nt_sid(&[18])), Some(&dacl))
+    }
+
+    #[test]
+    fn minimal_descriptor() {
+        let bytes = descriptor(0, None, None, None);
+        let parsed = parse_security_descriptor(&bytes).unwrap();
+        assert_eq!(parsed, SecurityDescriptor {
+            revision: 1,
+            control: 0,
+            owner: None,
+            group: None,
+            sacl: None,
+            dacl: None,
+        });
+        assert_eq!(format_sddl(&parsed), "");
+
+        // a DACL that is present but null grants everyone full access
+        let bytes = descriptor(SE_DACL_PRESENT, Some(&nt_sid(&[18])), None, None);
+        let parsed = parse_security_descriptor(&bytes).unwrap();
+        assert_eq!(format_sddl(&parsed), "O:S-1-5-18D:NO_ACCESS_CONTROL");
+    }
+
+    #[test]
+    fn descriptor_with_access_control_entries() {
+        let parsed = parse_security_descriptor(&full_descriptor()).unwrap();
+        let dacl = parsed.dacl.as_ref().unwrap();
+        assert_eq!(dacl.entries.len(), 2);
+        assert_eq!(dacl.entries[1].object_type, Some(Uuid::parse_str(USER_CLASS_GUID).unwrap()));
+        assert_eq!(dacl.entries[1].inherited_object_type, None);
+        assert_eq!(
+            format_sddl(&parsed),
+            format!(
+                "O:S-1-5-32-544G:S-1-5-18D:PAI(A;CI;0x000F01FF;;;S-1-5-32-544)(OA;CIIO;0x00000010;{};;S-1-5-11)",
+                USER_CLASS_GUID,
+            ),
+        );
+    }
+
+    #[test]
+    fn truncated_descriptor_is_rejected() {
+        let bytes = full_descriptor();
+        for len in 0..bytes.len() {
+            assert_eq!(parse_security_descriptor(&bytes[..len]), None, "truncated to {} bytes", len);
+        }
+    }
+
+    #[test]
+    fn out_of_range_offsets_are_rejected() {
+        let bytes = full_descriptor();
+        // owner, group and DACL offsets
+        for offset_position in [4, 8, 16] {
+            for offset in [u32::try_from(bytes.len()).unwrap(), u32::MAX] {
+                let mut broken = bytes.clone();
+                broken[offset_position..offset_position+4].copy_from_slice(&offset.to_le_bytes());
+                assert_eq!(parse_security_descriptor(&broken), None);
+            }
+        }
+
+        // an ACL claiming to be larger than the descriptor
+        let mut broken = bytes.clone();
+        let dacl_offset = read_offset(&bytes[16..20]);
+        broken[dacl_offset+2..dacl_offset+4].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert_eq!(parse_security_descriptor(&broken), None);
+
+        // an entry claiming to be shorter than its header
+        let mut broken = bytes.clone();
+        let first_ace_offset = dacl_offset + ACL_HEADER_SIZE;
+        broken[first_ace_offset+2..first_ace_offset+4].copy_from_slice(&2u16.to_le_bytes());
+        assert_eq!(parse_security_descriptor(&broken), None);
+    }
+}
@@ -0,0 +1,249 @@
+use std::fmt::Write;
+
+use bitflags::bitflags;
+use esedb::data::Data;
+
+use crate::schema::{
+    SYNTAX_BOOLEAN, SYNTAX_DISTINGUISHED_NAME, SYNTAX_GENERALIZED_TIME, SYNTAX_OBJECT_IDENTIFIER, SYNTAX_SID,
+};
+
+
+/// The number of FILETIME intervals (100ns each) in a second.
+pub const FILETIME_TICKS_PER_SECOND: i64 = 10_000_000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const DAYS_FROM_1601_TO_1970: i64 = 134_774;
+
+/// The LDAP names of attributes known to contain FILETIMEs.
+pub const TIME_ATTRIBUTE_NAMES: [&str; 9] = [
+    "accountExpires", "badPasswordTime", "creationTime", "lastLogoff", "lastLogon", "lastLogonTimestamp",
+    "lockoutTime", "msDS-LastSuccessfulInteractiveLogonTime", "pwdLastSet",
+];
+
+
+bitflags! {
+    /// Flags stored in the `userAccountControl` attribute.
+    #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct UserAccountControl : u32 {
+        const SCRIPT = 0x0000_0001;
+        const ACCOUNTDISABLE = 0x0000_0002;
+        // no 0x0000_0004
+        const HOMEDIR_REQUIRED = 0x0000_0008;
+        const LOCKOUT = 0x0000_0010;
+        const PASSWD_NOTREQD = 0x0000_0020;
+        const PASSWD_CANT_CHANGE = 0x0000_0040;
+        const ENCRYPTED_TEXT_PWD_ALLOWED = 0x0000_0080;
+        const TEMP_DUPLICATE_ACCOUNT = 0x0000_0100;
+        const NORMAL_ACCOUNT = 0x0000_0200;
+        // no 0x0000_0400
+        const INTERDOMAIN_TRUST_ACCOUNT = 0x0000_0800;
+        const WORKSTATION_TRUST_ACCOUNT = 0x0000_1000;
+        const SERVER_TRUST_ACCOUNT = 0x0000_2000;
+        // no 0x0000_4000
+        // no 0x0000_8000
+        const DONT_EXPIRE_PASSWORD = 0x0001_0000;
+        const MNS_LOGON_ACCOUNT = 0x0002_0000;
+        const SMARTCARD_REQUIRED = 0x0004_0000;
+        const TRUSTED_FOR_DELEGATION = 0x0008_0000;
+        const NOT_DELEGATED = 0x0010_0000;
+        const USE_DES_KEY_ONLY = 0x0020_0000;
+        const DONT_REQ_PREAUTH = 0x0040_0000;
+        const PASSWORD_EXPIRED = 0x0080_0000;
+        const TRUSTED_TO_AUTH_FOR_DELEGATION = 0x0100_0000;
+        // no 0x0200_0000
+        const PARTIAL_SECRETS_ACCOUNT = 0x0400_0000;
+    }
+}
+
+
+/// Formats a `userAccountControl` value as the raw integer followed by the names of the flags it
+/// contains; unknown bits are appended in hexadecimal.
+pub fn format_user_account_control(value: i32) -> String {
+    let flags = UserAccountControl::from_bits_retain(value as u32);
+    let mut pieces: Vec<String> = flags.iter_names()
+        .map(|(name, _flag)| name.to_owned())
+        .collect();
+    let unknown_bits = flags.bits() & !UserAccountControl::all().bits();
+    if unknown_bits != 0 {
+        pieces.push(format!("0x{:08X}", unknown_bits));
+    }
+    format!("{} ({})", value, pieces.join(" | "))
+}
+
+/// Formats a binary security identifier (SID) in its canonical string form (`S-1-5-21-...`).
+///
+/// Returns `None` if the buffer is too short or has superfluous bytes.
+pub fn format_sid(bytes: &[u8]) -> Option<String> {
+    // revision (u8), sub-authority count (u8), identifier authority (u48 big-endian),
+    // sub-authorities (u32 little-endian each)
+    let revision = *bytes.first()?;
+    let sub_authority_count = usize::from(*bytes.get(1)?);
+    let authority_bytes = bytes.get(2..8)?;
+    let sub_authority_bytes = bytes.get(8..)?;
+    if sub_authority_bytes.len() != 4 * sub_authority_count {
+        return None;
+    }
+
+    let mut identifier_authority: u64 = 0;
+    for b in authority_bytes {
+        identifier_authority = (identifier_authority << 8) | u64::from(*b);
+    }
+
+    let mut ret = String::new();
+    write!(ret, "S-{}-", revision).unwrap();
+    if identifier_authority < (1 << 32) {
+        write!(ret, "{}", identifier_authority).unwrap();
+    } else {
+        write!(ret, "0x{:012X}", identifier_authority).unwrap();
+    }
+    for chunk in sub_authority_bytes.chunks_exact(4) {
+        let sub_authority = u32::from_le_bytes(chunk.try_into().unwrap());
+        write!(ret, "-{}", sub_authority).unwrap();
+    }
+    Some(ret)
+}
+
+/// Formats a SID as stored by Active Directory, which stores the final sub-authority (the RID)
+/// big-endian to make it sort nicely.
+pub fn format_ntds_sid(bytes: &[u8]) -> Option<String> {
+    if bytes.len() < 12 {
+        return format_sid(bytes);
+    }
+    let mut standard_bytes = bytes.to_vec();
+    let rid_start = standard_bytes.len() - 4;
+    standard_bytes[rid_start..].reverse();
+    format_sid(&standard_bytes)
+}
+
+/// Formats a FILETIME (the number of 100ns intervals since 1601-01-01 00:00:00 UTC) as an ISO 8601
+/// timestamp.
+///
+/// The values 0 and `i64::MAX`, which Active Directory uses to mean "not set", are formatted as
+/// `never`. Returns `None` for negative values, which are durations rather than timestamps.
+pub fn format_filetime(filetime: i64) -> Option<String> {
+    if filetime == 0 || filetime == i64::MAX {
+        return Some("never".to_owned());
+    }
+    if filetime < 0 {
+        return None;
+    }
+
+    let seconds = filetime.div_euclid(FILETIME_TICKS_PER_SECOND);
+    let sub_second_ticks = filetime.rem_euclid(FILETIME_TICKS_PER_SECOND);
+    let days_since_1601 = seconds.div_euclid(SECONDS_PER_DAY);
+    let second_of_day = seconds.rem_euclid(SECONDS_PER_DAY);
+
+    let (year, month, day) = civil_from_days(days_since_1601 - DAYS_FROM_1601_TO_1970);
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:07}Z",
+        year, month, day,
+        second_of_day / 3600, (second_of_day / 60) % 60, second_of_day % 60,
+        sub_second_ticks,
+    ))
+}
+
+/// Formats a value according to the syntax of its attribute.
+///
+/// References to other objects and to schema entries cannot be resolved here and are output as
+/// numbers; values of other syntaxes and values not matching their syntax are output as-is.
+pub fn render_value(syntax: i32, data: &Data) -> String {
+    // strings, integers, large integers and octet strings are output as-is
+    let rendered_opt = match (syntax, data) {
+        (SYNTAX_DISTINGUISHED_NAME, Data::Long(dnt)) => Some(format!("<DNT {}>", dnt)),
+        (SYNTAX_OBJECT_IDENTIFIER, Data::Long(id)) => Some(format!("<ID {}>", id)),
+        (SYNTAX_BOOLEAN, Data::Long(value)) => Some(if *value != 0 { "TRUE" } else { "FALSE" }.to_owned()),
+        (SYNTAX_GENERALIZED_TIME, Data::LongLong(seconds)|Data::Currency(seconds))
+            => format_filetime(seconds.saturating_mul(FILETIME_TICKS_PER_SECOND)),
+        (SYNTAX_SID, Data::Binary(bytes)|Data::LongBinary(bytes)) => format_ntds_sid(bytes),
+        _ => None,
+    };
+    rendered_opt
+        .unwrap_or_else(|| data.to_string())
+}
+
+/// Converts a number of days since 1970-01-01 into a proleptic Gregorian (year, month, day).
+fn civil_from_days(days_since_1970: i64) -> (i64, i64, i64) {
+    // shift the epoch to 0000-03-01, so that leap days are at the end of each year
+    let z = days_since_1970 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era/1_460 + day_of_era/36_524 - day_of_era/146_096) / 365;
+    let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+    let shifted_month = (5*day_of_year + 2) / 153;
+    let day = day_of_year - (153*shifted_month + 2)/5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era*400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary SID with the given identifier authority and sub-authorities.
+    fn sid_bytes(authority: u64, sub_authorities: &[u32]) -> Vec<u8> {
+        let mut bytes = vec![1, sub_authorities.len().try_into().unwrap()];
+        bytes.extend_from_slice(&authority.to_be_bytes()[2..]);
+        for sub_authority in sub_authorities {
+            bytes.extend_from_slice(&sub_authority.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn well_known_sid() {
+        assert_eq!(format_sid(&sid_bytes(5, &[32, 544])).as_deref(), Some("S-1-5-32-544"));
+        assert_eq!(format_sid(&sid_bytes(1, &[0])).as_deref(), Some("S-1-1-0"));
+    }
+
+    #[test]
+    fn sid_with_several_sub_authorities() {
+        let bytes = sid_bytes(5, &[21, 1004336348, 1177238915, 682003330, 512]);
+        assert_eq!(format_sid(&bytes).as_deref(), Some("S-1-5-21-1004336348-1177238915-682003330-512"));
+
+        // Active Directory stores the RID big-endian
+        let mut ntds_bytes = bytes.clone();
+        let rid_start = ntds_bytes.len() - 4;
+        ntds_bytes[rid_start..].copy_from_slice(&512u32.to_be_bytes());
+        assert_eq!(format_ntds_sid(&ntds_bytes), format_sid(&bytes));
+    }
+
+    #[test]
+    fn sid_with_large_authority_is_hexadecimal() {
+        assert_eq!(format_sid(&sid_bytes(0x1234_5678_9ABC, &[])).as_deref(), Some("S-1-0x123456789ABC"));
+    }
+
+    #[test]
+    fn truncated_sid_is_rejected() {
+        let bytes = sid_bytes(5, &[21, 1004336348, 1177238915]);
+        for len in 0..bytes.len() {
+            assert_eq!(format_sid(&bytes[..len]), None);
+            assert_eq!(format_ntds_sid(&bytes[..len]), None);
+        }
+
+        let mut superfluous = bytes.clone();
+        superfluous.push(0);
+        assert_eq!(format_sid(&superfluous), None);
+    }
+
+    #[test]
+    fn unset_filetimes_are_never() {
+        assert_eq!(format_filetime(0).as_deref(), Some("never"));
+        assert_eq!(format_filetime(i64::MAX).as_deref(), Some("never"));
+    }
+
+    #[test]
+    fn filetime_is_formatted_as_utc_timestamp() {
+        assert_eq!(format_filetime(116_444_736_000_000_000).as_deref(), Some("1970-01-01T00:00:00.0000000Z"));
+        assert_eq!(format_filetime(1).as_deref(), Some("1601-01-01T00:00:00.0000001Z"));
+        // a leap day, with sub-second ticks
+        assert_eq!(format_filetime(133_536_874_621_234_567).as_deref(), Some("2024-02-29T13:37:42.1234567Z"));
+    }
+
+    #[test]
+    fn negative_filetime_is_rejected() {
+        // durations such as maxPwdAge are stored as negative numbers
+        assert_eq!(format_filetime(-36_000_000_000), None);
+        assert_eq!(format_filetime(i64::MIN), None);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+    ListArray, NullArray, StringArray, UInt16Array, UInt32Array, UInt8Array,
+};
+use arrow::buffer::{NullBuffer, OffsetBuffer};
+use arrow::datatypes::{DataType as ArrowDataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::{RecordBatch, RecordBatchOptions};
+
+use crate::data::{Data, DataType};
+use crate::table::{Column, ColumnFlags, Table, Value};
+
+
+/// Returns the Arrow data type corresponding to the data type of a column.
+///
+/// Date-time values are stored as their raw 64-bit representation, GUIDs in their canonical
+/// string form, and values of unknown types as binary data.
+pub fn arrow_data_type(data_type: DataType) -> ArrowDataType {
+    match data_type {
+        DataType::Nil => ArrowDataType::Null,
+        DataType::Bit => ArrowDataType::Boolean,
+        DataType::UnsignedByte => ArrowDataType::UInt8,
+        DataType::Short => ArrowDataType::Int16,
+        DataType::Long => ArrowDataType::Int32,
+        DataType::Currency => ArrowDataType::Int64,
+        DataType::IeeeSingle => ArrowDataType::Float32,
+        DataType::IeeeDouble => ArrowDataType::Float64,
+        DataType::DateTime => ArrowDataType::Int64,
+        DataType::Text|DataType::LongText => ArrowDataType::Utf8,
+        DataType::UnsignedLong => ArrowDataType::UInt32,
+        DataType::LongLong => ArrowDataType::Int64,
+        DataType::Guid => ArrowDataType::Utf8,
+        DataType::UnsignedShort => ArrowDataType::UInt16,
+        DataType::Binary|DataType::LongBinary|DataType::SuperLongValue|DataType::Other(_)
+            => ArrowDataType::Binary,
+    }
+}
+
+/// Returns the Arrow field corresponding to a column. Multi-valued columns become lists.
+pub fn arrow_field(column: &Column) -> Field {
+    let item_type = arrow_data_type(column.column_type);
+    if column.flags.contains(ColumnFlags::MULTI_VALUED) {
+        let item_field = Field::new("item", item_type, true);
+        Field::new(&column.name, ArrowDataType::List(Arc::new(item_field)), true)
+    } else {
+        Field::new(&column.name, item_type, true)
+    }
+}
+
+/// Returns the Arrow schema corresponding to the columns of a table.
+///
+/// The schema only depends on the definition of the table, so that the record batches of
+/// multiple chunks of rows of the same table are compatible.
+pub fn arrow_schema(table: &Table) -> Schema {
+    let fields: Vec<Field> = table.columns.iter()
+        .map(arrow_field)
+        .collect();
+    Schema::new(fields)
+}
+
+/// Converts rows of a table into an Arrow record batch with the schema returned by
+/// [`arrow_schema`].
+///
+/// Missing values become nulls. Data that does not match the type of its column (e.g. the raw
+/// bytes of an undecodable value read in lenient mode) also becomes null. If a column that is
+/// not multi-valued contains multiple values, only the first one is kept.
+pub fn to_record_batch(table: &Table, rows: &[BTreeMap<i32, Value>]) -> Result<RecordBatch, ArrowError> {
+    let schema = Arc::new(arrow_schema(table));
+    let mut arrays = Vec::with_capacity(table.columns.len());
+    for (column, field) in table.columns.iter().zip(schema.fields().iter()) {
+        let array = match field.data_type() {
+            ArrowDataType::List(item_field) => {
+                let mut items = Vec::new();
+                let mut lengths = Vec::with_capacity(rows.len());
+                let mut validity = Vec::with_capacity(rows.len());
+                for row in rows {
+                    match row.get(&column.column_id) {
+                        Some(value) => {
+                            let data_vec = value.to_data_vec();
+                            lengths.push(data_vec.len());
+                            items.extend(data_vec.into_iter().map(Some));
+                            validity.push(true);
+                        },
+                        None => {
+                            lengths.push(0);
+                            validity.push(false);
+                        },
+                    }
+                }
+                let values = data_array(column.column_type, &items);
+                let list = ListArray::try_new(
+                    Arc::clone(item_field), OffsetBuffer::from_lengths(lengths), values,
+                    Some(NullBuffer::from(validity)),
+                )?;
+                Arc::new(list) as ArrayRef
+            },
+            _ => {
+                let items: Vec<Option<&Data>> = rows.iter()
+                    .map(|row| row.get(&column.column_id).and_then(|v| v.first()))
+                    .collect();
+                data_array(column.column_type, &items)
+            },
+        };
+        arrays.push(array);
+    }
+
+    // a table without columns still has rows
+    let options = RecordBatchOptions::new()
+        .with_row_count(Some(rows.len()));
+    RecordBatch::try_new_with_options(schema, arrays, &options)
+}
+
+macro_rules! typed_array {
+    ($array_type:ty, $items:expr, $($pattern:pat => $value:expr),+ $(,)?) => {
+        Arc::new(<$array_type>::from_iter($items.iter().map(|item| match item {
+            $(Some($pattern) => Some($value),)+
+            _ => None,
+        }))) as ArrayRef
+    };
+}
+
+/// Builds an array of data of the given column type; absent or mismatched data becomes null.
+fn data_array(column_type: DataType, items: &[Option<&Data>]) -> ArrayRef {
+    match column_type {
+        DataType::Nil => Arc::new(NullArray::new(items.len())),
+        DataType::Bit => typed_array!(BooleanArray, items, Data::Bit(b) => bool::from(*b)),
+        DataType::UnsignedByte => typed_array!(UInt8Array, items, Data::UnsignedByte(v) => *v),
+        DataType::Short => typed_array!(Int16Array, items, Data::Short(v) => *v),
+        DataType::Long => typed_array!(Int32Array, items, Data::Long(v) => *v),
+        DataType::Currency => typed_array!(Int64Array, items, Data::Currency(v) => *v),
+        DataType::IeeeSingle => typed_array!(Float32Array, items, Data::IeeeSingle(v) => *v),
+        DataType::IeeeDouble => typed_array!(Float64Array, items, Data::IeeeDouble(v) => *v),
+        DataType::DateTime => typed_array!(Int64Array, items, Data::DateTime(v) => *v),
+        DataType::Text|DataType::LongText => typed_array!(
+            StringArray, items,
+            Data::Text(s)|Data::LongText(s) => s.as_str(),
+        ),
+        DataType::UnsignedLong => typed_array!(UInt32Array, items, Data::UnsignedLong(v) => *v),
+        DataType::LongLong => typed_array!(Int64Array, items, Data::LongLong(v) => *v),
+        DataType::Guid => typed_array!(StringArray, items, Data::Guid(g) => g.to_string()),
+        DataType::UnsignedShort => typed_array!(UInt16Array, items, Data::UnsignedShort(v) => *v),
+        DataType::Binary|DataType::LongBinary|DataType::SuperLongValue|DataType::Other(_) => typed_array!(
+            BinaryArray, items,
+            Data::Binary(bs)|Data::LongBinary(bs)|Data::SuperLongValue(bs)|Data::Other(_, bs) => bs.as_slice(),
+        ),
+    }
+}
@@ -0,0 +1,173 @@
+//! Reading databases through asynchronous I/O.
+//!
+//! Only fetching pages is asynchronous: the pages that are needed are read into memory first and
+//! then decoded using the same code as the synchronous API.
+
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::{self, SeekFrom};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use crate::error::{IoError, ReadError};
+use crate::header::{Header, parse_header};
+use crate::page::{page_byte_offset, read_page_entry, read_page_header, read_page_tags, PageFlags};
+use crate::source::DbSource;
+use crate::table::{Column, read_table_from_pages, Value};
+
+
+/// The offset of the page size within the database header.
+const PAGE_SIZE_OFFSET: usize = 236;
+
+
+/// A source of bytes that can be read asynchronously at arbitrary offsets.
+pub trait AsyncByteRead {
+    /// Fills the buffer with the bytes starting at the given offset.
+    fn read_exact_at(&mut self, offset: u64, buf: &mut [u8]) -> impl Future<Output = io::Result<()>>;
+
+    /// Returns the total length of the source in bytes.
+    fn byte_len(&mut self) -> impl Future<Output = io::Result<u64>>;
+}
+impl<T: AsyncRead + AsyncSeek + Unpin> AsyncByteRead for T {
+    async fn read_exact_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.seek(SeekFrom::Start(offset)).await?;
+        self.read_exact(buf).await?;
+        Ok(())
+    }
+
+    async fn byte_len(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::End(0)).await
+    }
+}
+
+
+/// Pages of a database file that have been read into memory, readable as if they were the file.
+///
+/// Reading a page that has not been fetched fails with [`io::ErrorKind::NotFound`].
+struct PageCache {
+    page_size: u64,
+    file_len: u64,
+    pages: BTreeMap<u64, Vec<u8>>,
+}
+impl PageCache {
+    fn new(page_size: u32, file_len: u64) -> Self {
+        Self {
+            page_size: page_size.into(),
+            file_len,
+            pages: BTreeMap::new(),
+        }
+    }
+
+    /// Reads the page with the given number into the cache unless it is already there.
+    async fn fetch<R: AsyncByteRead>(&mut self, reader: &mut R, page_number: u64) -> Result<(), ReadError> {
+        if self.pages.contains_key(&page_number) {
+            return Ok(());
+        }
+        let page_size: u32 = self.page_size.try_into().unwrap();
+        let byte_offset = page_byte_offset(page_size, page_number)?;
+        if byte_offset + self.page_size > self.file_len {
+            return Err(ReadError::PageBeyondEof { page_number, file_len: self.file_len });
+        }
+        let mut page = vec![0u8; page_size.try_into().unwrap()];
+        reader.read_exact_at(byte_offset, &mut page).await?;
+        self.pages.insert(page_number, page);
+        Ok(())
+    }
+
+    /// Reads all pages of the tree starting at the given page into the cache.
+    async fn fetch_tree<R: AsyncByteRead>(&mut self, reader: &mut R, header: &Header, root_page_number: u64) -> Result<(), ReadError> {
+        let mut pending = vec![root_page_number];
+        while let Some(page_number) = pending.pop() {
+            self.fetch(reader, page_number).await
+                .map_err(|e| e.at_page(page_number))?;
+
+            // the entries of the page are decoded from the cache
+            let page_header = read_page_header(&*self, header, page_number)
+                .map_err(|e| e.at_page(page_number))?;
+            if !page_header.is_usable() || page_header.flags.contains(PageFlags::LEAF_PAGE) {
+                continue;
+            }
+            let page_tags = read_page_tags(&*self, header.page_size, &page_header)
+                .map_err(|e| e.at_page(page_number))?;
+            for page_tag in page_tags.iter().skip(1) {
+                let entry = read_page_entry(&*self, header.page_size, &page_header, page_tag)
+                    .map_err(|e| e.at_page(page_number))?;
+                if let Some(branch) = entry.as_branch() {
+                    pending.push(branch.child_page_number.into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+impl DbSource for PageCache {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        let mut done = 0;
+        while done < buf.len() {
+            let position = offset + u64::try_from(done).unwrap();
+            if position >= self.file_len {
+                return Err(IoError::UnexpectedEof);
+            }
+
+            // the header and shadow header occupy the first two pages; page number p has index p + 1
+            let page_index = position / self.page_size;
+            let offset_in_page: usize = (position % self.page_size).try_into().unwrap();
+            let page = page_index.checked_sub(1)
+                .and_then(|page_number| self.pages.get(&page_number))
+                .ok_or_else(|| IoError::Std(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("page at offset {} has not been fetched", position),
+                )))?;
+
+            let available = &page[offset_in_page..];
+            let count = available.len().min(buf.len() - done);
+            buf[done..done+count].copy_from_slice(&available[..count]);
+            done += count;
+        }
+        Ok(())
+    }
+
+    fn byte_len(&self) -> Result<u64, IoError> {
+        Ok(self.file_len)
+    }
+}
+
+
+/// Reads the database header asynchronously; see [`parse_header`].
+pub async fn read_header_async<R: AsyncByteRead>(reader: &mut R) -> Result<Header, ReadError> {
+    let mut header_bytes = vec![0u8; PAGE_SIZE_OFFSET + 4];
+    reader.read_exact_at(0, &mut header_bytes).await?;
+
+    // read the rest of the page so that the checksum can be verified
+    let page_size_u32 = u32::from_le_bytes(header_bytes[PAGE_SIZE_OFFSET..PAGE_SIZE_OFFSET+4].try_into().unwrap());
+    let page_size: usize = page_size_u32.try_into().unwrap();
+    if page_size > header_bytes.len() {
+        let read_len = header_bytes.len();
+        header_bytes.resize(page_size, 0);
+        reader.read_exact_at(read_len.try_into().unwrap(), &mut header_bytes[read_len..]).await?;
+    }
+
+    parse_header(&header_bytes)
+}
+
+/// Reads and decodes the rows of a table asynchronously; see [`read_table_from_pages`].
+///
+/// All pages of the table's tree and of its long value tree are read into memory before the rows
+/// are decoded.
+pub async fn read_table_from_pages_async<R: AsyncByteRead>(
+    reader: &mut R,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+) -> Result<Vec<BTreeMap<i32, Value>>, ReadError> {
+    let file_len = reader.byte_len().await?;
+    let mut cache = PageCache::new(header.page_size, file_len);
+    cache.fetch_tree(reader, header, page_number).await?;
+    if let Some(lv_page_number) = large_value_page_number {
+        cache.fetch_tree(reader, header, lv_page_number).await?;
+    }
+
+    read_table_from_pages(&cache, header, page_number, columns, large_value_page_number)
+}
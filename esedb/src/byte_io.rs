@@ -1,38 +1,42 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::io::{self, Read, Seek, Write};
 
+use crate::error::IoError;
+
 
 pub trait ByteRead {
-    fn read_u8(&mut self) -> Result<u8, io::Error>;
-    fn read_u16(&mut self) -> Result<u16, io::Error>;
-    fn read_u32(&mut self) -> Result<u32, io::Error>;
-    fn read_u64(&mut self) -> Result<u64, io::Error>;
-    fn read_u128(&mut self) -> Result<u128, io::Error>;
-    fn read_usize(&mut self) -> Result<usize, io::Error>;
-    fn read_i8(&mut self) -> Result<i8, io::Error>;
-    fn read_i16(&mut self) -> Result<i16, io::Error>;
-    fn read_i32(&mut self) -> Result<i32, io::Error>;
-    fn read_i64(&mut self) -> Result<i64, io::Error>;
-    fn read_i128(&mut self) -> Result<i128, io::Error>;
-    fn read_isize(&mut self) -> Result<isize, io::Error>;
-    fn read_f32(&mut self) -> Result<f32, io::Error>;
-    fn read_f64(&mut self) -> Result<f64, io::Error>;
+    fn read_u8(&mut self) -> Result<u8, IoError>;
+    fn read_u16(&mut self) -> Result<u16, IoError>;
+    fn read_u32(&mut self) -> Result<u32, IoError>;
+    fn read_u64(&mut self) -> Result<u64, IoError>;
+    fn read_u128(&mut self) -> Result<u128, IoError>;
+    fn read_usize(&mut self) -> Result<usize, IoError>;
+    fn read_i8(&mut self) -> Result<i8, IoError>;
+    fn read_i16(&mut self) -> Result<i16, IoError>;
+    fn read_i32(&mut self) -> Result<i32, IoError>;
+    fn read_i64(&mut self) -> Result<i64, IoError>;
+    fn read_i128(&mut self) -> Result<i128, IoError>;
+    fn read_isize(&mut self) -> Result<isize, IoError>;
+    fn read_f32(&mut self) -> Result<f32, IoError>;
+    fn read_f64(&mut self) -> Result<f64, IoError>;
 }
 
 pub trait ByteWrite {
-    fn write_u8(&mut self, value: u8) -> Result<(), io::Error>;
-    fn write_u16(&mut self, value: u16) -> Result<(), io::Error>;
-    fn write_u32(&mut self, value: u32) -> Result<(), io::Error>;
-    fn write_u64(&mut self, value: u64) -> Result<(), io::Error>;
-    fn write_u128(&mut self, value: u128) -> Result<(), io::Error>;
-    fn write_usize(&mut self, value: usize) -> Result<(), io::Error>;
-    fn write_i8(&mut self, value: i8) -> Result<(), io::Error>;
-    fn write_i16(&mut self, value: i16) -> Result<(), io::Error>;
-    fn write_i32(&mut self, value: i32) -> Result<(), io::Error>;
-    fn write_i64(&mut self, value: i64) -> Result<(), io::Error>;
-    fn write_i128(&mut self, value: i128) -> Result<(), io::Error>;
-    fn write_isize(&mut self, value: isize) -> Result<(), io::Error>;
-    fn write_f32(&mut self, value: f32) -> Result<(), io::Error>;
-    fn write_f64(&mut self, value: f64) -> Result<(), io::Error>;
+    fn write_u8(&mut self, value: u8) -> Result<(), IoError>;
+    fn write_u16(&mut self, value: u16) -> Result<(), IoError>;
+    fn write_u32(&mut self, value: u32) -> Result<(), IoError>;
+    fn write_u64(&mut self, value: u64) -> Result<(), IoError>;
+    fn write_u128(&mut self, value: u128) -> Result<(), IoError>;
+    fn write_usize(&mut self, value: usize) -> Result<(), IoError>;
+    fn write_i8(&mut self, value: i8) -> Result<(), IoError>;
+    fn write_i16(&mut self, value: i16) -> Result<(), IoError>;
+    fn write_i32(&mut self, value: i32) -> Result<(), IoError>;
+    fn write_i64(&mut self, value: i64) -> Result<(), IoError>;
+    fn write_i128(&mut self, value: i128) -> Result<(), IoError>;
+    fn write_isize(&mut self, value: isize) -> Result<(), IoError>;
+    fn write_f32(&mut self, value: f32) -> Result<(), IoError>;
+    fn write_f64(&mut self, value: f64) -> Result<(), IoError>;
 }
 
 
@@ -50,7 +54,7 @@ pub trait WriteToBytes {
 macro_rules! impl_read_write_primitive {
     ($type:ty, $read_func:ident, $write_func:ident) => {
         impl ReadFromBytes for $type {
-            type Error = std::io::Error;
+            type Error = IoError;
             fn read_from_bytes<R: ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
                 let value = reader.$read_func()?;
                 Ok(value)
@@ -58,7 +62,7 @@ macro_rules! impl_read_write_primitive {
         }
 
         impl WriteToBytes for $type {
-            type Error = std::io::Error;
+            type Error = IoError;
             fn write_to_bytes<W: ByteWrite>(&self, writer: &mut W) -> Result<(), Self::Error> {
                 writer.$write_func(*self)
             }
@@ -106,16 +110,92 @@ impl<T: WriteToBytes, const N: usize> WriteToBytes for [T; N] {
     }
 }
 
+/// Reads little-endian values from a byte slice.
+///
+/// Unlike `LittleEndianRead`, this does not require the standard library.
+#[derive(Clone, Debug)]
+pub struct SliceRead<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+impl<'a> SliceRead<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            position: 0,
+        }
+    }
+
+    /// Returns the offset of the next byte to be read.
+    pub fn position(&self) -> usize { self.position }
+
+    /// Moves to the given offset. The offset may be beyond the end of the slice, in which case the
+    /// next read fails.
+    pub fn set_position(&mut self, position: usize) { self.position = position; }
+
+    /// Returns the bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes.get(self.position..).unwrap_or(&[])
+    }
+
+    /// Fills the buffer with the next bytes.
+    pub fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), IoError> {
+        let bytes = self.remaining().get(..buf.len())
+            .ok_or(IoError::UnexpectedEof)?;
+        buf.copy_from_slice(bytes);
+        self.position += buf.len();
+        Ok(())
+    }
+}
+
+macro_rules! impl_read {
+    ($func:ident, $type:ty) => {
+        impl_read!($func, $type, (<$type>::BITS / 8) as usize);
+    };
+    ($func:ident, $type:ty, $bytes:expr) => {
+        fn $func(&mut self) -> Result<$type, IoError> {
+            let mut buf = [0u8; $bytes];
+            self.read_bytes(&mut buf)?;
+            Ok(<$type>::from_le_bytes(buf))
+        }
+    };
+}
+
+impl ByteRead for SliceRead<'_> {
+    impl_read!(read_u8, u8);
+    impl_read!(read_u16, u16);
+    impl_read!(read_u32, u32);
+    impl_read!(read_u64, u64);
+    impl_read!(read_u128, u128);
+    impl_read!(read_usize, usize);
+    impl_read!(read_i8, i8);
+    impl_read!(read_i16, i16);
+    impl_read!(read_i32, i32);
+    impl_read!(read_i64, i64);
+    impl_read!(read_i128, i128);
+    impl_read!(read_isize, isize);
+    impl_read!(read_f32, f32, 4);
+    impl_read!(read_f64, f64, 8);
+}
+
+#[cfg(feature = "std")]
 pub struct LittleEndianRead<R: Read> {
     reader: R,
 }
+#[cfg(feature = "std")]
 impl<R: Read> LittleEndianRead<R> {
     pub fn new(reader: R) -> Self {
         Self {
             reader,
         }
     }
+
+    fn read_bytes(&mut self, buf: &mut [u8]) -> Result<(), IoError> {
+        self.reader.read_exact(buf)?;
+        Ok(())
+    }
 }
+#[cfg(feature = "std")]
 impl<R: Read> Read for LittleEndianRead<R> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.reader.read(buf)
@@ -138,19 +218,7 @@ impl<R: Read> Read for LittleEndianRead<R> {
     }
 }
 
-macro_rules! impl_read {
-    ($func:ident, $type:ty) => {
-        impl_read!($func, $type, (<$type>::BITS / 8) as usize);
-    };
-    ($func:ident, $type:ty, $bytes:expr) => {
-        fn $func(&mut self) -> Result<$type, io::Error> {
-            let mut buf = [0u8; $bytes];
-            self.reader.read_exact(&mut buf)?;
-            Ok(<$type>::from_le_bytes(buf))
-        }
-    };
-}
-
+#[cfg(feature = "std")]
 impl<R: Read> ByteRead for LittleEndianRead<R> {
     impl_read!(read_u8, u8);
     impl_read!(read_u16, u16);
@@ -168,15 +236,18 @@ impl<R: Read> ByteRead for LittleEndianRead<R> {
     impl_read!(read_f64, f64, 8);
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> Seek for LittleEndianRead<R> {
     fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
         self.reader.seek(pos)
     }
 }
 
+#[cfg(feature = "std")]
 pub struct LittleEndianWrite<W: Write> {
     writer: W,
 }
+#[cfg(feature = "std")]
 impl<W: Write> LittleEndianWrite<W> {
     pub fn new(writer: W) -> Self {
         Self {
@@ -185,15 +256,18 @@ impl<W: Write> LittleEndianWrite<W> {
     }
 }
 
+#[cfg(feature = "std")]
 macro_rules! impl_write {
     ($func:ident, $type:ty) => {
-        fn $func(&mut self, value: $type) -> Result<(), io::Error> {
+        fn $func(&mut self, value: $type) -> Result<(), IoError> {
             let buf = value.to_le_bytes();
-            self.writer.write_all(&buf)
+            self.writer.write_all(&buf)?;
+            Ok(())
         }
     };
 }
 
+#[cfg(feature = "std")]
 impl<W: Write> ByteWrite for LittleEndianWrite<W> {
     impl_write!(write_u8, u8);
     impl_write!(write_u16, u16);
@@ -1,6 +1,14 @@
 use esedb_macros::ReadFromAndWriteToBytes;
 
 
+/// A database time.
+///
+/// Despite the names of its fields, this is not a wall-clock time but a logical clock: a counter
+/// that the database engine increments whenever it modifies the database. The header stores the
+/// current value and each page stores the value at which it was last modified, so the values can
+/// only be compared with each other.
+///
+/// The fields are the 16-bit pieces of the counter, from least to most significant.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, ReadFromAndWriteToBytes)]
 pub struct DbTime {
     pub hour: u16,
@@ -8,3 +16,17 @@ pub struct DbTime {
     pub second: u16,
     pub padding: u16,
 }
+impl DbTime {
+    /// The fields that are traditionally interpreted as a time of day.
+    pub fn as_tuple(&self) -> (u16, u16, u16) {
+        (self.hour, self.minute, self.second)
+    }
+
+    /// The value of the logical clock.
+    pub fn as_u64_logical(&self) -> u64 {
+        u64::from(self.hour)
+        | (u64::from(self.minute) << 16)
+        | (u64::from(self.second) << 32)
+        | (u64::from(self.padding) << 48)
+    }
+}
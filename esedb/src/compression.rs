@@ -0,0 +1,415 @@
+use alloc::vec::Vec;
+
+use from_to_repr::from_to_other;
+
+use crate::error::ReadError;
+
+
+/// The number of symbols in the LZXPRESS Huffman alphabet (256 literals plus 256 match symbols).
+const HUFFMAN_SYMBOL_COUNT: usize = 512;
+
+/// The maximum length of a Huffman code in LZXPRESS Huffman.
+const HUFFMAN_MAX_CODE_LENGTH: u32 = 15;
+
+/// The number of bytes output before LZXPRESS Huffman switches to the next block (with a new Huffman
+/// table).
+const HUFFMAN_BLOCK_SIZE: usize = 64 * 1024;
+
+
+/// The compression scheme of a value, stored in the top five bits of its first byte.
+#[derive(Clone, Copy, Debug)]
+#[from_to_other(base_type = u8, derive_compare = "as_int")]
+pub enum CompressionScheme {
+    None = 0,
+    SevenBitAscii = 1,
+    SevenBitUnicode = 2,
+    Xpress = 3,
+    Scrub = 4,
+    Xpress9 = 5,
+    Xpress10 = 6,
+    Other(u8),
+}
+impl CompressionScheme {
+    pub fn from_header_byte(header_byte: u8) -> Self {
+        Self::from_base_type(header_byte >> 3)
+    }
+}
+
+
+/// Decompresses a compressed value, choosing the algorithm according to its first byte.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, ReadError> {
+    let Some(&header_byte) = data.first() else {
+        return Err(ReadError::CorruptCompressedData { scheme: CompressionScheme::None })
+    };
+    let scheme = CompressionScheme::from_header_byte(header_byte);
+    match scheme {
+        CompressionScheme::SevenBitAscii => decompress_7bit(data, false),
+        CompressionScheme::SevenBitUnicode => decompress_7bit(data, true),
+        CompressionScheme::Xpress => {
+            // header byte, then u16 decompressed length, then the plain LZXPRESS stream
+            let Some(length_bytes) = data.get(1..3) else {
+                return Err(ReadError::CorruptCompressedData { scheme })
+            };
+            let decompressed_length: usize = u16::from_le_bytes(length_bytes.try_into().unwrap()).into();
+            decompress_lzxpress(&data[3..], decompressed_length)
+        },
+        other => Err(ReadError::UnsupportedCompressionScheme { scheme: other }),
+    }
+}
+
+fn decompress_7bit(data: &[u8], unicode: bool) -> Result<Vec<u8>, ReadError> {
+    // the bottom three bits of the header byte are the number of bits used in the final byte, minus one
+    let final_byte_bits = usize::from(data[0] & 0b111) + 1;
+    let packed = &data[1..];
+    let bit_count = match packed.len() {
+        0 => 0,
+        n => (n - 1) * 8 + final_byte_bits,
+    };
+    let char_count = bit_count / 7;
+
+    let mut output = Vec::with_capacity(if unicode { 2 * char_count } else { char_count });
+    let mut bit_buffer: u32 = 0;
+    let mut buffered_bits = 0;
+    let mut packed_iter = packed.iter();
+    for _ in 0..char_count {
+        // bits are packed starting from the least significant bit
+        while buffered_bits < 7 {
+            let Some(&b) = packed_iter.next() else { break };
+            bit_buffer |= u32::from(b) << buffered_bits;
+            buffered_bits += 8;
+        }
+        let c = u8::try_from(bit_buffer & 0x7F).unwrap();
+        bit_buffer >>= 7;
+        buffered_bits -= 7;
+
+        output.push(c);
+        if unicode {
+            // UTF-16LE with the top byte always zero
+            output.push(0x00);
+        }
+    }
+    Ok(output)
+}
+
+
+/// Decompresses data compressed using the plain LZ77 variant of XPRESS ([MS-XCA] section 2.4).
+pub fn decompress_lzxpress(input: &[u8], decompressed_length: usize) -> Result<Vec<u8>, ReadError> {
+    let corrupt = || ReadError::CorruptCompressedData { scheme: CompressionScheme::Xpress };
+    let read_u16 = |position: usize| input.get(position..position+2)
+        .map(|bytes| usize::from(u16::from_le_bytes(bytes.try_into().unwrap())))
+        .ok_or_else(corrupt);
+
+    let mut output = Vec::with_capacity(decompressed_length);
+    let mut position = 0;
+    let mut flags: u32 = 0;
+    let mut flag_count = 0;
+    // the position of the byte whose upper nybble holds the next nybble-sized match length
+    let mut last_length_half_byte = None;
+    while output.len() < decompressed_length && position < input.len() {
+        if flag_count == 0 {
+            // each of the 32 bits, starting with the most significant one, says whether the next
+            // item is a literal (0) or a match (1)
+            let flag_bytes = input.get(position..position+4).ok_or_else(corrupt)?;
+            flags = u32::from_le_bytes(flag_bytes.try_into().unwrap());
+            flag_count = 32;
+            position += 4;
+        }
+        flag_count -= 1;
+
+        if flags & (1 << flag_count) == 0 {
+            output.push(*input.get(position).ok_or_else(corrupt)?);
+            position += 1;
+            continue;
+        }
+
+        if position == input.len() {
+            // a trailing match flag without a match marks the end of the data
+            break;
+        }
+
+        // match: offset minus one in the top 13 bits, length minus three in the bottom 3 bits
+        let match_bytes = read_u16(position)?;
+        position += 2;
+        let match_offset = (match_bytes >> 3) + 1;
+        let mut match_length = match_bytes & 0b111;
+        if match_length == 7 {
+            // longer lengths continue in a nybble, shared with the next match that needs one
+            match last_length_half_byte.take() {
+                None => {
+                    match_length = usize::from(*input.get(position).ok_or_else(corrupt)? & 0x0F);
+                    last_length_half_byte = Some(position);
+                    position += 1;
+                },
+                Some(half_byte_position) => {
+                    match_length = usize::from(input[half_byte_position] >> 4);
+                },
+            }
+            if match_length == 15 {
+                // then in a byte
+                match_length = (*input.get(position).ok_or_else(corrupt)?).into();
+                position += 1;
+                if match_length == 255 {
+                    // then in a u16 (or a u32 if the u16 is zero), which contains the whole length
+                    match_length = read_u16(position)?;
+                    position += 2;
+                    if match_length == 0 {
+                        let length_bytes = input.get(position..position+4).ok_or_else(corrupt)?;
+                        match_length = u32::from_le_bytes(length_bytes.try_into().unwrap()).try_into().unwrap();
+                        position += 4;
+                    }
+                    match_length = match_length.checked_sub(15 + 7).ok_or_else(corrupt)?;
+                }
+                match_length += 15;
+            }
+            match_length += 7;
+        }
+        match_length += 3;
+
+        if match_offset > output.len() {
+            return Err(corrupt());
+        }
+        // a corrupt length may claim up to 4 GiB; copy no more than the announced length needs
+        let match_length = match_length.min(decompressed_length - output.len());
+        // byte by byte because the source and destination may overlap
+        let match_start = output.len() - match_offset;
+        for i in 0..match_length {
+            let b = output[match_start + i];
+            output.push(b);
+        }
+    }
+
+    Ok(output)
+}
+
+
+struct HuffmanBitReader<'a> {
+    input: &'a [u8],
+    position: usize,
+    next_bits: u32,
+    extra_bit_count: i32,
+}
+impl<'a> HuffmanBitReader<'a> {
+    pub fn new(input: &'a [u8], position: usize) -> Self {
+        let mut ret = Self {
+            input,
+            position,
+            next_bits: 0,
+            extra_bit_count: 16,
+        };
+        let high = ret.read_padded_u16();
+        let low = ret.read_padded_u16();
+        ret.next_bits = (u32::from(high) << 16) | u32::from(low);
+        ret
+    }
+
+    pub fn position(&self) -> usize { self.position }
+
+    fn read_padded_u16(&mut self) -> u16 {
+        // the bit buffer is refilled ahead of time; pretend any bytes past the end are zero
+        let lo = self.input.get(self.position).copied().unwrap_or(0);
+        let hi = self.input.get(self.position + 1).copied().unwrap_or(0);
+        self.position += 2;
+        u16::from_le_bytes([lo, hi])
+    }
+
+    pub fn peek(&self, bit_count: u32) -> u32 {
+        if bit_count == 0 {
+            0
+        } else {
+            self.next_bits >> (32 - bit_count)
+        }
+    }
+
+    pub fn consume(&mut self, bit_count: u32) {
+        self.next_bits = self.next_bits.checked_shl(bit_count).unwrap_or(0);
+        self.extra_bit_count -= i32::try_from(bit_count).unwrap();
+        if self.extra_bit_count < 0 {
+            let word = self.read_padded_u16();
+            self.next_bits |= u32::from(word) << (-self.extra_bit_count);
+            self.extra_bit_count += 16;
+        }
+    }
+
+    pub fn read_byte(&mut self) -> Option<u8> {
+        let b = *self.input.get(self.position)?;
+        self.position += 1;
+        Some(b)
+    }
+
+    pub fn read_u16(&mut self) -> Option<u16> {
+        let bytes = self.input.get(self.position..self.position+2)?;
+        self.position += 2;
+        Some(u16::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+fn build_huffman_decoding_table(code_lengths: &[u8; HUFFMAN_SYMBOL_COUNT]) -> Option<Vec<u16>> {
+    // canonical Huffman code: shorter codes first, then by symbol value
+    // each code of length n occupies 2**(15-n) consecutive slots in the table
+    let table_size = 1usize << HUFFMAN_MAX_CODE_LENGTH;
+    let mut table = Vec::with_capacity(table_size);
+    for bit_length in 1..=HUFFMAN_MAX_CODE_LENGTH {
+        for (symbol, code_length) in code_lengths.iter().enumerate() {
+            if u32::from(*code_length) != bit_length {
+                continue;
+            }
+            let entry_count = 1usize << (HUFFMAN_MAX_CODE_LENGTH - bit_length);
+            if table.len() + entry_count > table_size {
+                return None;
+            }
+            let symbol_u16 = u16::try_from(symbol).unwrap();
+            table.extend(core::iter::repeat_n(symbol_u16, entry_count));
+        }
+    }
+    if table.len() != table_size {
+        return None;
+    }
+    Some(table)
+}
+
+/// Decompresses data compressed using the LZ77+Huffman variant of XPRESS ([MS-XCA] section 2.2).
+///
+/// Values in databases are compressed with plain XPRESS (see [`decompress_lzxpress`]), not with
+/// this variant.
+pub fn decompress_lzxpress_huffman(input: &[u8], decompressed_length: usize) -> Result<Vec<u8>, ReadError> {
+    let corrupt = || ReadError::CorruptCompressedData { scheme: CompressionScheme::Xpress };
+
+    let mut output = Vec::with_capacity(decompressed_length);
+    let mut block_position = 0;
+    while output.len() < decompressed_length {
+        // each block starts with the code lengths of all symbols, one nybble per symbol
+        let Some(table_bytes) = input.get(block_position..block_position + HUFFMAN_SYMBOL_COUNT/2) else {
+            return Err(corrupt())
+        };
+        let mut code_lengths = [0u8; HUFFMAN_SYMBOL_COUNT];
+        for (i, b) in table_bytes.iter().enumerate() {
+            code_lengths[2*i] = *b & 0x0F;
+            code_lengths[2*i + 1] = *b >> 4;
+        }
+        let decoding_table = build_huffman_decoding_table(&code_lengths)
+            .ok_or_else(corrupt)?;
+
+        let mut bits = HuffmanBitReader::new(input, block_position + HUFFMAN_SYMBOL_COUNT/2);
+        let block_end = (output.len() + HUFFMAN_BLOCK_SIZE).min(decompressed_length);
+        while output.len() < block_end {
+            let symbol = decoding_table[usize::try_from(bits.peek(HUFFMAN_MAX_CODE_LENGTH)).unwrap()];
+            bits.consume(code_lengths[usize::from(symbol)].into());
+
+            if symbol < 256 {
+                // literal
+                output.push(u8::try_from(symbol).unwrap());
+                continue;
+            }
+
+            // match: low nybble is the length, high nybble is the bit length of the offset
+            let match_symbol = symbol - 256;
+            let mut match_length = usize::from(match_symbol & 0x0F);
+            let offset_bit_length = u32::from(match_symbol >> 4);
+            if match_length == 15 {
+                // longer lengths are stored as bytes in the stream
+                match_length = bits.read_byte().ok_or_else(corrupt)?.into();
+                if match_length == 255 {
+                    let long_length: usize = bits.read_u16().ok_or_else(corrupt)?.into();
+                    if long_length < 15 {
+                        return Err(corrupt());
+                    }
+                    match_length = long_length - 15;
+                }
+                match_length += 15;
+            }
+            match_length += 3;
+
+            let match_offset_u32 = bits.peek(offset_bit_length) + (1 << offset_bit_length);
+            bits.consume(offset_bit_length);
+            let match_offset = usize::try_from(match_offset_u32).unwrap();
+            if match_offset > output.len() {
+                return Err(corrupt());
+            }
+
+            let match_length = match_length.min(decompressed_length - output.len());
+            // byte by byte because the source and destination may overlap
+            let match_start = output.len() - match_offset;
+            for i in 0..match_length {
+                let b = output[match_start + i];
+                output.push(b);
+            }
+        }
+
+        block_position = bits.position();
+    }
+
+    output.truncate(decompressed_length);
+    Ok(output)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seven_bit_ascii() {
+        // 'a' (110_0001) and 'b' (110_0010) packed from the least significant bit: 0x3161
+        // 6 bits are used in the final byte, so the header byte is (1 << 3) | (6 - 1)
+        assert_eq!(decompress(&[0x0D, 0x61, 0x31]).unwrap(), b"ab");
+    }
+
+    #[test]
+    fn seven_bit_unicode() {
+        assert_eq!(decompress(&[0x15, 0x61, 0x31]).unwrap(), b"a\0b\0");
+    }
+
+    #[test]
+    fn plain_lzxpress_literals() {
+        // [MS-XCA] section 3.1
+        let mut input = vec![0x3F, 0x00, 0x00, 0x00];
+        input.extend_from_slice(b"abcdefghijklmnopqrstuvwxyz");
+        assert_eq!(decompress_lzxpress(&input, 26).unwrap(), b"abcdefghijklmnopqrstuvwxyz");
+    }
+
+    #[test]
+    fn plain_lzxpress_long_match() {
+        // [MS-XCA] section 3.2: "abc" followed by a match of length 297 at offset 3
+        let input = [0xFF, 0xFF, 0xFF, 0x1F, 0x61, 0x62, 0x63, 0x17, 0x00, 0x0F, 0xFF, 0x26, 0x01];
+        assert_eq!(decompress_lzxpress(&input, 300).unwrap(), b"abc".repeat(100));
+    }
+
+    #[test]
+    fn plain_lzxpress_oversized_match() {
+        // "abc" followed by a match at offset 3 whose u32 length claims almost 4 GiB
+        let input = [
+            0xFF, 0xFF, 0xFF, 0x1F, 0x61, 0x62, 0x63, 0x17, 0x00, 0x0F, 0xFF, 0x00, 0x00,
+            0xF0, 0xFF, 0xFF, 0xFF,
+        ];
+        let output = decompress_lzxpress(&input, 10).unwrap();
+        assert_eq!(output, b"abcabcabca");
+        assert!(output.capacity() < 1024);
+    }
+
+    #[test]
+    fn xpress_value() {
+        // header byte of scheme 3, decompressed length, then the plain LZXPRESS stream
+        let data = [0x18, 0x2C, 0x01, 0xFF, 0xFF, 0xFF, 0x1F, 0x61, 0x62, 0x63, 0x17, 0x00, 0x0F, 0xFF, 0x26, 0x01];
+        assert_eq!(decompress(&data).unwrap(), b"abc".repeat(100));
+    }
+
+    #[test]
+    fn lzxpress_huffman() {
+        // 'a', 'b', 'c' and the match symbol 256 + (1 << 4) + 3 (one offset bit, length 3 + 3) all
+        // have codes of length 2: 00, 01, 10 and 11 in canonical order
+        let mut input = vec![0u8; HUFFMAN_SYMBOL_COUNT/2];
+        input[0x61 / 2] = 0x20;
+        input[0x62 / 2] = 0x22;
+        input[(256 + 0x13) / 2] = 0x20;
+        // 00 01 10 11, then the offset bit 1 (offset 2 + 1): 0001_1011_1000_0000, as u16 words
+        input.extend_from_slice(&[0x80, 0x1B, 0x00, 0x00]);
+        assert_eq!(decompress_lzxpress_huffman(&input, 9).unwrap(), b"abcabcabc");
+    }
+
+    #[test]
+    fn lzxpress_match_before_start() {
+        // a match at offset 1 without any preceding output
+        let input = [0x00, 0x00, 0x00, 0x80, 0x00, 0x00];
+        assert!(decompress_lzxpress(&input, 3).is_err());
+    }
+}
@@ -1,6 +1,25 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io::Write;
+
 use from_to_repr::from_to_other;
 use uuid::Uuid;
 
+#[cfg(feature = "std")]
+use crate::error::WriteError;
+#[cfg(feature = "std")]
+use crate::table::encode_string;
+
+
+/// The factor by which currency values are scaled when stored.
+pub const CURRENCY_SCALE: i64 = 10_000;
+
+/// The number of decimal places of currency values; the base-10 logarithm of [`CURRENCY_SCALE`].
+#[cfg(feature = "rust_decimal")]
+const CURRENCY_DECIMAL_PLACES: u32 = 4;
+
 
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Bit(pub u8);
@@ -62,6 +81,31 @@ impl DataType {
         }
     }
 }
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nil => write!(f, "Nil"),
+            Self::Bit => write!(f, "Bit"),
+            Self::UnsignedByte => write!(f, "UnsignedByte"),
+            Self::Short => write!(f, "Short"),
+            Self::Long => write!(f, "Long"),
+            Self::Currency => write!(f, "Currency"),
+            Self::IeeeSingle => write!(f, "IeeeSingle"),
+            Self::IeeeDouble => write!(f, "IeeeDouble"),
+            Self::DateTime => write!(f, "DateTime"),
+            Self::Binary => write!(f, "Binary"),
+            Self::Text => write!(f, "Text"),
+            Self::LongBinary => write!(f, "LongBinary"),
+            Self::LongText => write!(f, "LongText"),
+            Self::SuperLongValue => write!(f, "SuperLongValue"),
+            Self::UnsignedLong => write!(f, "UnsignedLong"),
+            Self::LongLong => write!(f, "LongLong"),
+            Self::Guid => write!(f, "Guid"),
+            Self::UnsignedShort => write!(f, "UnsignedShort"),
+            Self::Other(code) => write!(f, "Other({})", code),
+        }
+    }
+}
 
 
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
@@ -110,4 +154,138 @@ impl Data {
             Self::Other(code, _value) => DataType::Other(*code),
         }
     }
+
+    /// Returns the value of an integral type, widened to 64 bits.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::UnsignedByte(v) => Some((*v).into()),
+            Self::Short(v) => Some((*v).into()),
+            Self::Long(v) => Some((*v).into()),
+            Self::UnsignedLong(v) => Some((*v).into()),
+            Self::LongLong(v) => Some(*v),
+            Self::UnsignedShort(v) => Some((*v).into()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a bit.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bit(b) => Some((*b).into()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a textual type.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Text(s)|Self::LongText(s) => Some(s.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a binary type.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Self::Binary(bs)|Self::LongBinary(bs)|Self::SuperLongValue(bs) => Some(bs.as_slice()),
+            _ => None,
+        }
+    }
+
+    pub fn as_guid(&self) -> Option<Uuid> {
+        match self {
+            Self::Guid(g) => Some(*g),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a currency column in currency units.
+    ///
+    /// Like OLE `CURRENCY`, ESE stores currency amounts as integers scaled by 10000, i.e. in
+    /// ten-thousandths of a unit; a stored value of 125000 is 12.5 units.
+    pub fn currency_as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Currency(v) => Some(*v as f64 / CURRENCY_SCALE as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the exact value of a currency column in currency units; see
+    /// [`currency_as_f64`](Self::currency_as_f64) for the scaling.
+    #[cfg(feature = "rust_decimal")]
+    pub fn currency_as_decimal(&self) -> Option<rust_decimal::Decimal> {
+        match self {
+            Self::Currency(v) => Some(rust_decimal::Decimal::new(*v, CURRENCY_DECIMAL_PLACES)),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a GUID, also interpreting 16 bytes of binary data as a GUID in the
+    /// mixed-endian Windows layout.
+    pub fn to_guid_lenient(&self) -> Option<Uuid> {
+        match self {
+            Self::Guid(g) => Some(*g),
+            Self::Binary(bs)|Self::LongBinary(bs) => {
+                let bytes: [u8; 16] = bs.as_slice().try_into().ok()?;
+                Some(Uuid::from_bytes_le(bytes))
+            },
+            _ => None,
+        }
+    }
+
+    /// Writes the value in the form in which it is stored in a record; the inverse of the
+    /// decoding performed by [`decode_row`](crate::table::decode_row).
+    ///
+    /// Numbers are stored in little-endian byte order, GUIDs in the mixed-endian Windows layout and
+    /// text in the given codepage.
+    #[cfg(feature = "std")]
+    pub fn write_value_to_bytes<W: Write>(&self, writer: &mut W, codepage: i32) -> Result<(), WriteError> {
+        match self {
+            Self::Nil => {},
+            Self::Bit(b) => writer.write_all(&[b.0])?,
+            Self::UnsignedByte(v) => writer.write_all(&[*v])?,
+            Self::Short(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::Long(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::Currency(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::IeeeSingle(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::IeeeDouble(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::DateTime(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::Text(s)|Self::LongText(s) => writer.write_all(&encode_string(s, codepage)?)?,
+            Self::UnsignedLong(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::LongLong(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::Guid(g) => writer.write_all(&g.to_bytes_le())?,
+            Self::UnsignedShort(v) => writer.write_all(&v.to_le_bytes())?,
+            Self::Binary(bs)|Self::LongBinary(bs)|Self::SuperLongValue(bs)|Self::Other(_, bs) => writer.write_all(bs)?,
+        }
+        Ok(())
+    }
+}
+impl fmt::Display for Data {
+    /// Formats the value for end users: text as-is, binary data as hex, GUIDs in their canonical
+    /// form and null values as the empty string.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Nil => Ok(()),
+            Self::Bit(b) => write!(f, "{}", bool::from(*b)),
+            Self::UnsignedByte(v) => write!(f, "{}", v),
+            Self::Short(v) => write!(f, "{}", v),
+            Self::Long(v) => write!(f, "{}", v),
+            Self::Currency(v) => write!(f, "{}", v),
+            Self::IeeeSingle(v) => write!(f, "{}", v),
+            Self::IeeeDouble(v) => write!(f, "{}", v),
+            Self::DateTime(v) => write!(f, "{}", v),
+            Self::Text(s)|Self::LongText(s) => write!(f, "{}", s),
+            Self::UnsignedLong(v) => write!(f, "{}", v),
+            Self::LongLong(v) => write!(f, "{}", v),
+            Self::Guid(g) => write!(f, "{}", g),
+            Self::UnsignedShort(v) => write!(f, "{}", v),
+            Self::Binary(bs)|Self::LongBinary(bs)|Self::SuperLongValue(bs)|Self::Other(_, bs) => {
+                for b in bs {
+                    write!(f, "{:02x}", b)?;
+                }
+                Ok(())
+            },
+        }
+    }
 }
+
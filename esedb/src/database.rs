@@ -0,0 +1,207 @@
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+use tracing::instrument;
+
+use crate::error::ReadError;
+use crate::header::{Header, read_header_at};
+use crate::page::{CATALOG_PAGE_NUMBER, RootPageHeader};
+#[cfg(feature = "std")]
+use crate::source::CachedSource;
+use crate::source::DbSource;
+use crate::system_tables::{DEFRAG_TABLE_NAME, DefragInfo, LOCALES_TABLE_NAME, LocaleEntry};
+use crate::table::{
+    collect_tables, count_table_rows, DecodedRows, DecodeOptions, for_each_row, metadata_column_defs, read_table_from_pages,
+    read_table_from_pages_with_options, recover_orphan_records, Table, Value,
+};
+
+
+/// The name of the table containing the definitions of all objects in the database.
+pub const OBJECTS_TABLE_NAME: &str = "MSysObjects";
+
+
+/// An open database: a source along with the database header and the definitions of all tables.
+#[derive(Debug)]
+pub struct Database<S> {
+    source: S,
+    header: Header,
+    shadow_header: Header,
+    tables: Vec<Table>,
+}
+impl<S: DbSource> Database<S> {
+    /// Opens a database by reading its header and its catalog of tables.
+    #[instrument(skip(source))]
+    pub fn open(source: S) -> Result<Self, ReadError> {
+        // the shadow copy of the header takes up the page after the header
+        let header = read_header_at(&source, 0)?;
+        let shadow_header = read_header_at(&source, header.page_size.into())?;
+
+        // read the catalog with the built-in column definitions to find MSysObjects
+        let metadata_columns = metadata_column_defs();
+        let naive_rows = read_table_from_pages(&source, &header, CATALOG_PAGE_NUMBER, &metadata_columns, None)?;
+        let naive_tables = collect_tables(&naive_rows, &metadata_columns)?;
+        let mso = naive_tables.iter()
+            .find(|t| t.header.name == OBJECTS_TABLE_NAME)
+            .ok_or(ReadError::MissingTable { name: Cow::Borrowed(OBJECTS_TABLE_NAME) })?;
+
+        // re-read the catalog given the actual definition of MSysObjects
+        let meta_rows = read_table_from_pages(&source, &header, mso.fdp_page_number()?, &mso.columns, mso.long_value_page_number()?)?;
+        let tables = collect_tables(&meta_rows, &mso.columns)?;
+
+        Ok(Self {
+            source,
+            header,
+            shadow_header,
+            tables,
+        })
+    }
+
+    /// Reads and decodes all rows of the table with the given name.
+    pub fn read_table(&self, name: &str) -> Result<Vec<BTreeMap<i32, Value>>, ReadError> {
+        let table = self.existing_table(name)?;
+        read_table_from_pages(&self.source, &self.header, table.fdp_page_number()?, &table.columns, table.long_value_page_number()?)
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+
+    /// Reads and decodes all rows of the table with the given name according to the given options.
+    pub fn read_table_with_options(&self, name: &str, options: &DecodeOptions) -> Result<DecodedRows, ReadError> {
+        let table = self.existing_table(name)?;
+        read_table_from_pages_with_options(&self.source, &self.header, table.fdp_page_number()?, &table.columns, table.long_value_page_number()?, options)
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+
+    /// Salvages the rows of the table with the given name from leaf pages that are no longer linked
+    /// into its tree; see [`recover_orphan_records`].
+    pub fn recover_orphan_records(&self, name: &str, options: &DecodeOptions) -> Result<DecodedRows, ReadError> {
+        let table = self.existing_table(name)?;
+        recover_orphan_records(&self.source, &self.header, table, options)
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+
+    /// Reads the root page header of the table with the given name, which describes how the table's
+    /// space is allocated.
+    pub fn table_root_page_header(&self, name: &str) -> Result<RootPageHeader, ReadError> {
+        let table = self.existing_table(name)?;
+        table.read_root_page_header(&self.source, &self.header)
+    }
+
+    /// Decodes the rows of the table with the given name one at a time, passing each to `f`.
+    pub fn for_each_row<F: FnMut(&BTreeMap<i32, Value>) -> ControlFlow<()>>(&self, name: &str, f: F) -> Result<(), ReadError> {
+        let table = self.existing_table(name)?;
+        for_each_row(&self.source, &self.header, table.fdp_page_number()?, &table.columns, table.long_value_page_number()?, f)
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+
+    /// Counts the rows of the table with the given name without decoding them.
+    pub fn count_table_rows(&self, name: &str, exclude_deleted: bool) -> Result<u64, ReadError> {
+        let table = self.existing_table(name)?;
+        count_table_rows(&self.source, &self.header, table.fdp_page_number()?, exclude_deleted)
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+
+    /// Reads the locales used by the indexes of the database from the locales table.
+    pub fn read_locales(&self) -> Result<Vec<LocaleEntry>, ReadError> {
+        let rows = self.read_table(LOCALES_TABLE_NAME)?;
+        let table = self.existing_table(LOCALES_TABLE_NAME)?;
+        Ok(rows.iter()
+            .map(|row| LocaleEntry::from_row(row, &table.columns))
+            .collect())
+    }
+
+    /// Reads the state of online defragmentation from the defragmentation table.
+    pub fn read_defrag_info(&self) -> Result<Vec<DefragInfo>, ReadError> {
+        let rows = self.read_table(DEFRAG_TABLE_NAME)?;
+        let table = self.existing_table(DEFRAG_TABLE_NAME)?;
+        Ok(rows.iter()
+            .map(|row| DefragInfo::from_row(row, &table.columns))
+            .collect())
+    }
+}
+impl<S> Database<S> {
+    pub fn header(&self) -> &Header { &self.header }
+    pub fn shadow_header(&self) -> &Header { &self.shadow_header }
+    pub fn tables(&self) -> &[Table] { &self.tables }
+    pub fn source(&self) -> &S { &self.source }
+    pub fn into_source(self) -> S { self.source }
+
+    /// Whether the header and its shadow copy are identical.
+    pub fn shadow_header_matches(&self) -> bool {
+        self.header == self.shadow_header
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.iter()
+            .find(|t| t.header.name == name)
+    }
+
+    /// Returns the table with the given name or fails with [`ReadError::MissingTable`].
+    fn existing_table(&self, name: &str) -> Result<&Table, ReadError> {
+        self.table(name)
+            .ok_or_else(|| ReadError::MissingTable { name: Cow::Owned(name.to_owned()) })
+    }
+}
+#[cfg(feature = "std")]
+impl Database<File> {
+    /// Opens the database file at the given path.
+    pub fn open_path<P: AsRef<Path>>(path: P) -> Result<Self, ReadError> {
+        let file = File::open(path)?;
+        Self::open(file)
+    }
+}
+#[cfg(feature = "std")]
+impl Database<CachedSource<File>> {
+    /// Opens the database file at the given path, keeping the parsed headers and tags of up to
+    /// `cache_pages` of the most recently read pages in memory; see [`CachedSource`].
+    ///
+    /// This speeds up repeated lookups, which descend through the same branch pages, at the cost of
+    /// the memory taken up by the cached headers and tags.
+    pub fn open_path_cached<P: AsRef<Path>>(path: P, cache_pages: usize) -> Result<Self, ReadError> {
+        let file = File::open(path)?;
+        Self::open(CachedSource::new(file, cache_pages))
+    }
+}
+#[cfg(feature = "parallel")]
+impl<S: DbSource + Sync> Database<S> {
+    /// Reads and decodes all rows of the table with the given name, decoding them in parallel.
+    pub fn read_table_parallel(&self, name: &str) -> Result<Vec<BTreeMap<i32, Value>>, ReadError> {
+        let table = self.existing_table(name)?;
+        crate::table::read_table_from_pages_parallel(
+            &self.source,
+            &self.header,
+            table.fdp_page_number()?,
+            &table.columns,
+            table.long_value_page_number()?,
+        )
+            .map_err(|e| e.in_table(table.header.table_object_id))
+    }
+}
+impl<'a> Database<&'a [u8]> {
+    /// Opens a database that is already in memory.
+    pub fn open_bytes(bytes: &'a [u8]) -> Result<Self, ReadError> {
+        Self::open(bytes)
+    }
+}
+#[cfg(feature = "mmap")]
+impl Database<memmap2::Mmap> {
+    /// Opens the database file at the given path by mapping it into memory.
+    ///
+    /// Reading then no longer requires system calls, which speeds up the many small
+    /// reads performed while walking the trees of large databases. On a synthetic table of 1.6 GiB
+    /// whose pages were all cached, the `mmap` benchmark walked the tree four times as fast and
+    /// decoded all rows in 40% less time than through the file.
+    ///
+    /// The file must not be modified while it is mapped; among other things, this means that the
+    /// database must not be in use by the database engine.
+    pub fn open_path_mmap<P: AsRef<Path>>(path: P) -> Result<Self, ReadError> {
+        let file = File::open(path)?;
+        // SAFETY: the caller promises not to modify the file while it is mapped
+        let mmap = unsafe { memmap2::Mmap::map(&file) }?;
+        Self::open(mmap)
+    }
+}
@@ -0,0 +1,26 @@
+//! Decryption of the values of encrypted columns.
+
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+
+
+/// The error type returned by a [`DecryptProvider`].
+pub type DecryptError = Box<dyn Error + Send + Sync>;
+
+
+/// Decrypts the values of columns stored encrypted.
+///
+/// The database engine encrypts values with key material supplied by the application (e.g. keys
+/// protected by DPAPI), which is not stored in the database. Implement this trait to supply the
+/// decryption and pass the implementation via [`DecodeOptions::decrypt_provider`].
+///
+/// [`DecodeOptions::decrypt_provider`]: crate::table::DecodeOptions::decrypt_provider
+pub trait DecryptProvider: fmt::Debug + Send + Sync {
+    /// Decrypts the stored bytes of a value of the column with the given ID.
+    ///
+    /// Encrypted values are decrypted before they are decompressed.
+    fn decrypt(&self, column_id: i32, ciphertext: &[u8]) -> Result<Vec<u8>, DecryptError>;
+}
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+
+use crate::data::{Data, DataType};
+use crate::error::WriteError;
+use crate::page::MAX_SIZE_SMALL_PAGE;
+use crate::table::{column_codepage, Column, DecodedColumns, DEFAULT_CODEPAGE, SmallTagFlags, TagFlags, Value};
+
+
+/// Encodes a row into the record format parsed by [`decode_row`](crate::table::decode_row).
+///
+/// Fixed columns must have consecutive IDs starting at 1; all fixed columns up to the one with the
+/// highest ID in `values` are stored at their record offsets, with missing or [`Data::Nil`] values
+/// marked as null. Values of tagged columns are stored with the layout given by their flags.
+///
+/// Compressed and separated values cannot be encoded, since `decode_row` returns them
+/// decompressed and resolved.
+pub fn encode_row(values: &BTreeMap<i32, Value>, columns: &[Column], page_size: u32) -> Result<Vec<u8>, WriteError> {
+    let decoded_columns = DecodedColumns::new(columns);
+
+    // fixed columns
+    let last_fixed_column_id = values.keys()
+        .copied()
+        .filter(|id| *id >= 1 && *id < 128)
+        .max()
+        .unwrap_or(0);
+    let last_fixed_column_index: usize = last_fixed_column_id.try_into().unwrap();
+    let mut fixed_data = Vec::new();
+    let mut next_fixed_offset = 0;
+    let mut nullity = vec![0u8; last_fixed_column_index.div_ceil(8)];
+    for column_id in 1..=last_fixed_column_id {
+        let column_index: usize = (column_id - 1).try_into().unwrap();
+        let Some(column) = decoded_columns.fixed.get(column_index).filter(|c| c.column_id == column_id) else {
+            return Err(WriteError::value_not_encodable(column_id, "no definition for fixed column"));
+        };
+        let size = fixed_column_size(column)?;
+
+        // like decode_row, place the value at its record offset if it has one (counting from the
+        // start of the record, after the 4-byte header) and directly after the previous one if not
+        let offset = column.record_offset
+            .and_then(|ro| usize::try_from(ro).ok())
+            .and_then(|ro| ro.checked_sub(4))
+            .unwrap_or(next_fixed_offset);
+        next_fixed_offset = offset + size;
+        if fixed_data.len() < next_fixed_offset {
+            fixed_data.resize(next_fixed_offset, 0);
+        }
+
+        match values.get(&column_id).and_then(|v| v.single()) {
+            None|Some(Data::Nil) => {
+                nullity[column_index / 8] |= 1 << (column_index % 8);
+            },
+            Some(data) => {
+                let bytes = encode_data(data, column)?;
+                if bytes.len() > size {
+                    return Err(WriteError::value_not_encodable(column_id, "value too long for fixed column"));
+                }
+                fixed_data[offset..offset+bytes.len()].copy_from_slice(&bytes);
+            },
+        }
+    }
+
+    // variable columns
+    let last_variable_column_id = values.iter()
+        .filter(|(id, value)| **id >= 128 && **id < 256 && !matches!(value.single(), None|Some(Data::Nil)))
+        .map(|(id, _value)| *id)
+        .max()
+        .unwrap_or(127);
+    let mut variable_offsets = Vec::new();
+    let mut variable_data = Vec::new();
+    for column_id in 128..=last_variable_column_id {
+        let data_opt = values.get(&column_id)
+            .and_then(|v| v.single())
+            .filter(|d| **d != Data::Nil);
+        match data_opt {
+            None => {
+                // topmost bit set => no value for this column
+                let end = u16::try_from(variable_data.len()).unwrap_or(u16::MAX);
+                variable_offsets.push(end | (1 << 15));
+            },
+            Some(data) => {
+                let bytes = match decoded_columns.variable.get(&column_id) {
+                    Some(column) => encode_data(data, column)?,
+                    // decode_row passes undefined columns on as binary
+                    None => data.as_bytes()
+                        .ok_or_else(|| WriteError::value_not_encodable(column_id, "undefined variable column has non-binary value"))?
+                        .to_vec(),
+                };
+                variable_data.extend_from_slice(&bytes);
+                let end = check_offset("variable column offset", variable_data.len(), 0b0111_1111_1111_1111)?;
+                variable_offsets.push(end);
+            },
+        }
+    }
+
+    // tagged columns
+    let mut tagged_items = Vec::new();
+    for (&column_id, value) in values.range(256..) {
+        let Some(column) = decoded_columns.tagged.get(&column_id) else {
+            return Err(WriteError::value_not_encodable(column_id, "no definition for tagged column"));
+        };
+        let (flags, item) = encode_tagged_value(value, column, page_size)?;
+        tagged_items.push((column_id, flags, item));
+    }
+    let mut tagged_data = Vec::new();
+    let mut tagged_header = Vec::with_capacity(4 * tagged_items.len());
+    let mut item_offset = 4 * tagged_items.len();
+    for (column_id, flags, item) in &tagged_items {
+        let tag = u16::try_from(*column_id)
+            .map_err(|_| WriteError::value_not_encodable(*column_id, "tagged column ID out of range"))?;
+        let offset_and_flags = if page_size <= MAX_SIZE_SMALL_PAGE {
+            let offset = check_offset("tagged column offset", item_offset, 0b0001_1111_1111_1111)?;
+            offset | flags.bits()
+        } else {
+            let offset = check_offset("tagged column offset", item_offset, 0b0111_1111_1111_1111)?;
+            offset | (flags.bits() & SmallTagFlags::DERIVED.bits())
+        };
+        tagged_header.extend_from_slice(&tag.to_le_bytes());
+        tagged_header.extend_from_slice(&offset_and_flags.to_le_bytes());
+        tagged_data.extend_from_slice(item);
+        item_offset += item.len();
+    }
+
+    // assemble
+    let end_fixed_values_offset = check_offset("end of fixed values", 4 + fixed_data.len() + nullity.len(), 0xFFFF)?;
+    let mut row = Vec::with_capacity(
+        usize::from(end_fixed_values_offset) + 2*variable_offsets.len() + variable_data.len()
+        + tagged_header.len() + tagged_data.len()
+    );
+    row.push(last_fixed_column_id.try_into().unwrap());
+    row.push(last_variable_column_id.try_into().unwrap());
+    row.extend_from_slice(&end_fixed_values_offset.to_le_bytes());
+    row.extend_from_slice(&fixed_data);
+    row.extend_from_slice(&nullity);
+    for offset in &variable_offsets {
+        row.extend_from_slice(&offset.to_le_bytes());
+    }
+    row.extend_from_slice(&variable_data);
+    row.extend_from_slice(&tagged_header);
+    row.extend_from_slice(&tagged_data);
+    Ok(row)
+}
+
+fn check_offset(name: &'static str, value: usize, max_value: u16) -> Result<u16, WriteError> {
+    WriteError::check_unsigned_max(name, value.try_into().unwrap(), max_value.into())?;
+    Ok(value.try_into().unwrap())
+}
+
+fn fixed_column_size(column: &Column) -> Result<usize, WriteError> {
+    let size = match column.column_type {
+        DataType::Bit|DataType::UnsignedByte => 1,
+        DataType::Short|DataType::UnsignedShort => 2,
+        DataType::Long|DataType::UnsignedLong|DataType::IeeeSingle => 4,
+        DataType::Currency|DataType::IeeeDouble|DataType::DateTime|DataType::LongLong => 8,
+        DataType::Guid => 16,
+        DataType::Binary|DataType::Text => column.length.try_into()
+            .map_err(|_| WriteError::value_not_encodable(column.column_id, "negative column length"))?,
+        DataType::Other(_) if column.length > 0 => column.length.try_into().unwrap(),
+        DataType::Nil|DataType::LongBinary|DataType::LongText|DataType::SuperLongValue|DataType::Other(_)
+            => return Err(WriteError::value_not_encodable(column.column_id, "data type cannot be stored in a fixed column")),
+    };
+    Ok(size)
+}
+
+/// Encodes a value of a tagged column, returning the flags to store in the tag and the bytes to
+/// store in the tagged data area.
+fn encode_tagged_value(value: &Value, column: &Column, page_size: u32) -> Result<(SmallTagFlags, Vec<u8>), WriteError> {
+    let (data_values, flags) = match value {
+        Value::Simple(data) => (vec![data], TagFlags::empty()),
+        Value::Complex { data, flags, .. } => (vec![data], *flags),
+        Value::Multiple { values, flags, .. } => (values.iter().collect(), *flags),
+    };
+    if flags.intersects(TagFlags::COMPRESSED | TagFlags::SEPARATED | TagFlags::ENCRYPTED) {
+        return Err(WriteError::value_not_encodable(column.column_id, "compressed, separated and encrypted values are not supported"));
+    }
+
+    let mut encoded_values = Vec::with_capacity(data_values.len());
+    for data in data_values {
+        encoded_values.push(encode_data(data, column)?);
+    }
+
+    let mut item = Vec::new();
+    let small_flags_only = TagFlags::NULL | TagFlags::DERIVED;
+    let extended = page_size > MAX_SIZE_SMALL_PAGE || !small_flags_only.contains(flags);
+    let mut small_flags = SmallTagFlags::empty();
+    if flags.contains(TagFlags::DERIVED) {
+        small_flags |= SmallTagFlags::DERIVED;
+    }
+    if extended {
+        small_flags |= SmallTagFlags::HAS_EXTENDED_FLAGS;
+        item.push(flags.bits());
+    } else if flags.contains(TagFlags::NULL) {
+        small_flags |= SmallTagFlags::NULL;
+    }
+
+    if flags.contains(TagFlags::TWO_VALUES) {
+        let [first, second] = encoded_values.as_slice() else {
+            return Err(WriteError::value_not_encodable(column.column_id, "two-valued column does not have two values"));
+        };
+        let first_length = u8::try_from(first.len())
+            .map_err(|_| WriteError::value_not_encodable(column.column_id, "first value of two-valued column too long"))?;
+        item.push(first_length);
+        item.extend_from_slice(first);
+        item.extend_from_slice(second);
+    } else if flags.contains(TagFlags::MULTI_VALUES) {
+        // offsets relative to the start of the offsets, followed by the values
+        let mut offset = 2 * encoded_values.len();
+        for encoded_value in &encoded_values {
+            let offset_u16 = check_offset("multi-value offset", offset, 0xFFFF)?;
+            item.extend_from_slice(&offset_u16.to_le_bytes());
+            offset += encoded_value.len();
+        }
+        for encoded_value in &encoded_values {
+            item.extend_from_slice(encoded_value);
+        }
+    } else {
+        let [single] = encoded_values.as_slice() else {
+            return Err(WriteError::value_not_encodable(column.column_id, "multiple values without multi-value flag"));
+        };
+        item.extend_from_slice(single);
+    }
+
+    Ok((small_flags, item))
+}
+
+/// Encodes a single piece of data the way it is stored for the given column.
+fn encode_data(data: &Data, column: &Column) -> Result<Vec<u8>, WriteError> {
+    let mut bytes = Vec::new();
+    data.write_value_to_bytes(&mut bytes, column_codepage(column, DEFAULT_CODEPAGE))
+        .map_err(|e| WriteError::value_not_encodable(column.column_id, e.to_string()))?;
+    Ok(bytes)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::table::{ColumnFlags, decode_row};
+
+    fn test_column(column_id: i32, column_type: DataType, record_offset: Option<i16>) -> Column {
+        Column {
+            table_object_id: 100,
+            column_id,
+            column_type,
+            length: 0,
+            flags: ColumnFlags::empty(),
+            codepage: DEFAULT_CODEPAGE,
+            root_flag: None,
+            record_offset,
+            name: format!("column{}", column_id),
+            default_value: None,
+        }
+    }
+
+    #[test]
+    fn decoded_row_encodes_to_same_bytes() {
+        let columns = [
+            test_column(1, DataType::Long, Some(4)),
+            test_column(2, DataType::Short, Some(8)),
+            test_column(128, DataType::Text, None),
+            test_column(256, DataType::LongText, None),
+            test_column(257, DataType::Long, None),
+        ];
+        let extended = SmallTagFlags::HAS_EXTENDED_FLAGS.bits();
+        let mut record = vec![
+            2, 128, 11, 0, // header: last fixed column 2, last variable column 128, end of fixed values
+            42, 0, 0, 0, // column 1
+            7, 0, // column 2
+            0b00, // nullity bitmap
+            2, 0, // end of column 128
+            b'h', b'i', // column 128
+        ];
+        // tags: column 256 at offset 8 of the tagged area, column 257 at offset 16
+        record.extend_from_slice(&256u16.to_le_bytes());
+        record.extend_from_slice(&(8 | extended).to_le_bytes());
+        record.extend_from_slice(&257u16.to_le_bytes());
+        record.extend_from_slice(&16u16.to_le_bytes());
+        // column 256: two values "a" and "bc"
+        record.extend_from_slice(&[TagFlags::MULTI_VALUES.bits(), 4, 0, 5, 0, b'a', b'b', b'c']);
+        // column 257
+        record.extend_from_slice(&5i32.to_le_bytes());
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let values = decode_row(source, &header, &record, &columns, 8 * 1024, None).unwrap();
+        assert_eq!(values.get(&1), Some(&Value::Simple(Data::Long(42))));
+        assert_eq!(values.get(&128), Some(&Value::Simple(Data::Text("hi".to_owned()))));
+        assert_eq!(values.get(&256).map(|v| v.to_data_vec().len()), Some(2));
+
+        let encoded = encode_row(&values, &columns, 8 * 1024).unwrap();
+        assert_eq!(encoded, record);
+    }
+
+    #[test]
+    fn null_fixed_column_encodes_to_same_bytes() {
+        let columns = [
+            test_column(1, DataType::Long, Some(4)),
+            test_column(2, DataType::Short, Some(8)),
+        ];
+        let record = [
+            2, 127, 11, 0, // header: last fixed column 2, no variable columns, end of fixed values
+            0, 0, 0, 0, // column 1 (null)
+            9, 0, // column 2
+            0b01, // nullity bitmap: column 1 is null
+        ];
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let values = decode_row(source, &header, &record, &columns, 8 * 1024, None).unwrap();
+        let encoded = encode_row(&values, &columns, 8 * 1024).unwrap();
+        assert_eq!(encoded, record);
+    }
+}
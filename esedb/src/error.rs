@@ -1,14 +1,73 @@
-use std::borrow::Cow;
-use std::fmt;
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::io;
 
+use crate::compression::CompressionScheme;
 use crate::data::DataType;
+use crate::decrypt::DecryptError;
 use crate::table::ObjectType;
 
 
+/// An error that occurs while reading or writing bytes.
+///
+/// Reading from a byte slice can only fail by running out of bytes; readers and writers of the
+/// standard library can fail in other ways as well.
 #[derive(Debug)]
+#[non_exhaustive]
+pub enum IoError {
+    UnexpectedEof,
+    #[cfg(feature = "std")]
+    Std(io::Error),
+}
+impl fmt::Display for IoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof
+                => write!(f, "unexpected end of data"),
+            #[cfg(feature = "std")]
+            Self::Std(e)
+                => write!(f, "{}", e),
+        }
+    }
+}
+impl core::error::Error for IoError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::UnexpectedEof => None,
+            #[cfg(feature = "std")]
+            Self::Std(e) => e.source(),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<io::Error> for IoError {
+    fn from(value: io::Error) -> Self {
+        // the same error regardless of whether the bytes come from a slice or a reader
+        if value.kind() == io::ErrorKind::UnexpectedEof {
+            Self::UnexpectedEof
+        } else {
+            Self::Std(value)
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<IoError> for io::Error {
+    fn from(value: IoError) -> Self {
+        match value {
+            IoError::UnexpectedEof => io::Error::from(io::ErrorKind::UnexpectedEof),
+            IoError::Std(e) => e,
+        }
+    }
+}
+
+
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum ReadError {
-    Io(io::Error),
+    Io(IoError),
     WrongHeaderSignature { expected: u32, read: u32 },
     WrongHeaderChecksum { calculated: u32, read: u32 },
     HeaderLongerThanPage { header_length: usize, page_size: usize },
@@ -23,6 +82,22 @@ pub enum ReadError {
     WrongColumnType { name: Cow<'static, str>, expected: DataType, obtained: DataType },
     WrongObjectType { expected: ObjectType, obtained: ObjectType },
     SeparatedValueWithoutLongValueInfo,
+    UnsupportedCompressionScheme { scheme: CompressionScheme },
+    CorruptCompressedData { scheme: CompressionScheme },
+    WithContext { page: Option<u64>, table: Option<i32>, source: Box<ReadError> },
+    MalformedRecord { reason: Cow<'static, str> },
+    MissingTable { name: Cow<'static, str> },
+    UnsupportedRecordFormat,
+    PageBeyondEof { page_number: u64, file_len: u64 },
+    ValueTooLarge { len: usize, max: usize },
+    PageOwnershipMismatch { page_number: u64, expected: u32, found: u32 },
+    PageChainCycle { page_number: u64 },
+    InvalidEnumValue { type_name: Cow<'static, str> },
+    InvalidString { codepage: i32, reason: Cow<'static, str>, lossy: String },
+    EncodingUnavailable { codepage: i32 },
+    DecryptionFailed { column_id: i32, error: DecryptError },
+    InvalidCatalogValue { name: Cow<'static, str>, value: i32 },
+    PageTooShort { length: usize, min_length: usize },
 }
 impl ReadError {
     #[must_use]
@@ -42,6 +117,55 @@ impl ReadError {
             Err(Self::WrongObjectType { expected, obtained })
         }
     }
+
+    /// Fails if a value of the given length is longer than the given maximum.
+    pub fn ensure_value_len(len: usize, max: usize) -> Result<(), Self> {
+        if len <= max {
+            Ok(())
+        } else {
+            Err(Self::ValueTooLarge { len, max })
+        }
+    }
+
+    /// Converts a page number or object ID read from the catalog, failing if it is out of range
+    /// (e.g. negative) for the given type.
+    pub fn catalog_value<T: TryFrom<i32>>(name: &'static str, value: i32) -> Result<T, Self> {
+        T::try_from(value)
+            .map_err(|_| Self::InvalidCatalogValue { name: Cow::Borrowed(name), value })
+    }
+
+    pub fn malformed_record<S: Into<Cow<'static, str>>>(reason: S) -> Self {
+        Self::MalformedRecord { reason: reason.into() }
+    }
+
+    /// Annotates the error with the number of the page being processed.
+    ///
+    /// If the error is already annotated with a page, it is returned unchanged, since the innermost
+    /// page is the most precise one.
+    pub fn at_page(self, page_number: u64) -> Self {
+        match self {
+            Self::WithContext { page: None, table, source }
+                => Self::WithContext { page: Some(page_number), table, source },
+            Self::WithContext { page: Some(_), .. }
+                => self,
+            other
+                => Self::WithContext { page: Some(page_number), table: None, source: Box::new(other) },
+        }
+    }
+
+    /// Annotates the error with the ID of the table being processed.
+    ///
+    /// If the error is already annotated with a table, it is returned unchanged.
+    pub fn in_table(self, table_id: i32) -> Self {
+        match self {
+            Self::WithContext { page, table: None, source }
+                => Self::WithContext { page, table: Some(table_id), source },
+            Self::WithContext { table: Some(_), .. }
+                => self,
+            other
+                => Self::WithContext { page: None, table: Some(table_id), source: Box::new(other) },
+        }
+    }
 }
 impl fmt::Display for ReadError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -76,11 +200,51 @@ impl fmt::Display for ReadError {
                 => write!(f, "object has type {:?}, expected {:?}", obtained, expected),
             Self::SeparatedValueWithoutLongValueInfo
                 => write!(f, "table contains a separated value but no long value info"),
+            Self::UnsupportedCompressionScheme { scheme }
+                => write!(f, "unsupported compression scheme {:?}", scheme),
+            Self::CorruptCompressedData { scheme }
+                => write!(f, "corrupt data compressed with scheme {:?}", scheme),
+            Self::WithContext { page, table, source } => {
+                write!(f, "{}", source)?;
+                if let Some(table) = table {
+                    write!(f, " in table {}", table)?;
+                }
+                if let Some(page) = page {
+                    write!(f, " at page {}", page)?;
+                }
+                Ok(())
+            },
+            Self::MalformedRecord { reason }
+                => write!(f, "malformed record: {}", reason),
+            Self::MissingTable { name }
+                => write!(f, "missing table {:?}", name),
+            Self::UnsupportedRecordFormat
+                => write!(f, "record is stored in the unsupported legacy record format"),
+            Self::PageBeyondEof { page_number, file_len }
+                => write!(f, "page {} is missing; file is truncated at {} bytes", page_number, file_len),
+            Self::ValueTooLarge { len, max }
+                => write!(f, "value length ({}) exceeds maximum ({})", len, max),
+            Self::PageOwnershipMismatch { page_number, expected, found }
+                => write!(f, "page {} belongs to object {}, expected object {}", page_number, found, expected),
+            Self::PageChainCycle { page_number }
+                => write!(f, "page {} is linked to more than once in a chain of sibling pages", page_number),
+            Self::InvalidEnumValue { type_name }
+                => write!(f, "invalid value for {}", type_name),
+            Self::InvalidString { codepage, reason, .. }
+                => write!(f, "invalid string in codepage {}: {}", codepage, reason),
+            Self::EncodingUnavailable { codepage }
+                => write!(f, "support for codepage {} is not compiled in", codepage),
+            Self::DecryptionFailed { column_id, error }
+                => write!(f, "failed to decrypt value of column {}: {}", column_id, error),
+            Self::InvalidCatalogValue { name, value }
+                => write!(f, "catalog value {} out of range ({})", name, value),
+            Self::PageTooShort { length, min_length }
+                => write!(f, "page of {} bytes is shorter than its header ({} bytes)", length, min_length),
         }
     }
 }
-impl std::error::Error for ReadError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl core::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
         match self {
             Self::Io(e) => Some(e),
             Self::WrongHeaderSignature { .. } => None,
@@ -97,22 +261,61 @@ impl std::error::Error for ReadError {
             Self::WrongColumnType { .. } => None,
             Self::WrongObjectType { .. } => None,
             Self::SeparatedValueWithoutLongValueInfo => None,
+            Self::UnsupportedCompressionScheme { .. } => None,
+            Self::CorruptCompressedData { .. } => None,
+            Self::WithContext { source, .. } => Some(source.as_ref()),
+            Self::MalformedRecord { .. } => None,
+            Self::MissingTable { .. } => None,
+            Self::UnsupportedRecordFormat => None,
+            Self::PageBeyondEof { .. } => None,
+            Self::ValueTooLarge { .. } => None,
+            Self::PageOwnershipMismatch { .. } => None,
+            Self::PageChainCycle { .. } => None,
+            Self::InvalidEnumValue { .. } => None,
+            Self::InvalidString { .. } => None,
+            Self::EncodingUnavailable { .. } => None,
+            Self::DecryptionFailed { error, .. } => Some(error.as_ref()),
+            Self::InvalidCatalogValue { .. } => None,
+            Self::PageTooShort { .. } => None,
         }
     }
 }
+impl From<IoError> for ReadError {
+    fn from(value: IoError) -> Self {
+        Self::Io(value)
+    }
+}
+#[cfg(feature = "std")]
 impl From<io::Error> for ReadError {
     fn from(value: io::Error) -> Self {
-        Self::Io(value)
+        Self::Io(value.into())
+    }
+}
+impl From<IoOrInvalidValueError> for ReadError {
+    fn from(value: IoOrInvalidValueError) -> Self {
+        match value {
+            IoOrInvalidValueError::Io(e) => Self::Io(e),
+            IoOrInvalidValueError::InvalidValue { type_name } => Self::InvalidEnumValue { type_name: Cow::Borrowed(type_name) },
+        }
     }
 }
 
 
 #[derive(Debug)]
 pub enum WriteError {
-    Io(io::Error),
+    Io(IoError),
     UnsignedOutOfRange { name: Cow<'static, str>, value: u128, min_value: u128, max_value: u128 },
+    ValueNotEncodable { column_id: i32, reason: Cow<'static, str> },
+    WrongPageSize { expected: usize, obtained: usize },
+    Page0,
+    UnsupportedCodepage { codepage: i32 },
+    StringNotRepresentable { codepage: i32 },
 }
 impl WriteError {
+    pub fn value_not_encodable<S: Into<Cow<'static, str>>>(column_id: i32, reason: S) -> Self {
+        Self::ValueNotEncodable { column_id, reason: reason.into() }
+    }
+
     pub fn check_unsigned_max(name: &'static str, value: u128, max_value: u128) -> Result<(), Self> {
         if value <= max_value {
             Ok(())
@@ -128,19 +331,121 @@ impl fmt::Display for WriteError {
                 => write!(f, "I/O error: {}", e),
             Self::UnsignedOutOfRange { name, value, min_value, max_value }
                 => write!(f, "unsigned value {} of {:?} out of range (minimum {}, maximum {})", value, name, min_value, max_value),
+            Self::ValueNotEncodable { column_id, reason }
+                => write!(f, "value of column {} cannot be encoded: {}", column_id, reason),
+            Self::WrongPageSize { expected, obtained }
+                => write!(f, "page has {} bytes, expected {}", obtained, expected),
+            Self::Page0
+                => write!(f, "attempted to write page 0"),
+            Self::UnsupportedCodepage { codepage }
+                => write!(f, "cannot find encoder for codepage {}", codepage),
+            Self::StringNotRepresentable { codepage }
+                => write!(f, "string cannot be represented in codepage {}", codepage),
         }
     }
 }
-impl std::error::Error for WriteError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl core::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
         match self {
             Self::Io(e) => Some(e),
             Self::UnsignedOutOfRange { .. } => None,
+            Self::ValueNotEncodable { .. } => None,
+            Self::WrongPageSize { .. } => None,
+            Self::Page0 => None,
+            Self::UnsupportedCodepage { .. } => None,
+            Self::StringNotRepresentable { .. } => None,
         }
     }
 }
+impl From<IoError> for WriteError {
+    fn from(value: IoError) -> Self {
+        Self::Io(value)
+    }
+}
+#[cfg(feature = "std")]
 impl From<io::Error> for WriteError {
     fn from(value: io::Error) -> Self {
+        Self::Io(value.into())
+    }
+}
+
+
+/// An error that occurs while reading a value that can only take on specific values, such as an
+/// enumeration without a catch-all variant.
+#[derive(Debug)]
+pub enum IoOrInvalidValueError {
+    Io(IoError),
+    InvalidValue { type_name: &'static str },
+}
+impl fmt::Display for IoOrInvalidValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e)
+                => write!(f, "I/O error: {}", e),
+            Self::InvalidValue { type_name }
+                => write!(f, "invalid value for {}", type_name),
+        }
+    }
+}
+impl core::error::Error for IoOrInvalidValueError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::InvalidValue { .. } => None,
+        }
+    }
+}
+impl From<IoError> for IoOrInvalidValueError {
+    fn from(value: IoError) -> Self {
         Self::Io(value)
     }
 }
+#[cfg(feature = "std")]
+impl From<io::Error> for IoOrInvalidValueError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value.into())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use esedb_macros::ReadFromAndWriteToBytes;
+    use from_to_repr::FromToRepr;
+
+    use crate::byte_io::{LittleEndianWrite, ReadFromBytes, SliceRead, WriteToBytes};
+
+    #[derive(Clone, Copy, Debug, Eq, FromToRepr, PartialEq, ReadFromAndWriteToBytes)]
+    #[repr(u16)]
+    enum Shade {
+        Light = 1,
+        Dark = 2,
+    }
+
+    fn read_shade(bytes: &[u8]) -> Result<Shade, IoOrInvalidValueError> {
+        Shade::read_from_bytes(&mut SliceRead::new(bytes))
+    }
+
+    #[test]
+    fn repr_enum_round_trip() {
+        for shade in [Shade::Light, Shade::Dark] {
+            let mut bytes = Vec::new();
+            shade.write_to_bytes(&mut LittleEndianWrite::new(&mut bytes)).unwrap();
+            assert_eq!(read_shade(&bytes).unwrap(), shade);
+        }
+    }
+
+    #[test]
+    fn repr_enum_out_of_range() {
+        let error = read_shade(&[0x03, 0x00]).unwrap_err();
+        assert!(matches!(error, IoOrInvalidValueError::InvalidValue { type_name: "Shade" }));
+        assert_eq!(error.to_string(), "invalid value for Shade");
+        assert!(matches!(ReadError::from(error), ReadError::InvalidEnumValue { .. }));
+
+        // too few bytes
+        let error = read_shade(&[0x01]).unwrap_err();
+        assert!(matches!(error, IoOrInvalidValueError::Io(IoError::UnexpectedEof)));
+    }
+}
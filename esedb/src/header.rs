@@ -1,12 +1,21 @@
-use std::io::{Cursor, Read};
-use std::mem::size_of;
+use alloc::string::String;
+use alloc::vec;
+use core::mem::size_of;
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
 
 use esedb_macros::ReadFromAndWriteToBytes;
 use from_to_repr::from_to_other;
 
-use crate::byte_io::{LittleEndianRead, ReadFromBytes};
+use crate::byte_io::{ReadFromBytes, SliceRead};
+#[cfg(feature = "std")]
+use crate::byte_io::{LittleEndianWrite, WriteToBytes};
 use crate::common::DbTime;
-use crate::error::ReadError;
+use crate::error::{IoError, ReadError};
+#[cfg(feature = "std")]
+use crate::error::WriteError;
+use crate::source::DbSource;
+use crate::table::decode_string;
 
 
 pub const HEADER_SIGNATURE: u32 = 0x89ABCDEF;
@@ -72,6 +81,77 @@ impl Header {
         (u64::from(self.version) << 32)
         | u64::from(self.format_revision)
     }
+
+    /// A human-readable name of the database format, usually the first product using it.
+    ///
+    /// | version | revision | name                                                     |
+    /// |---------|----------|----------------------------------------------------------|
+    /// | 0x620   | 0x00     | ESE97 beta                                               |
+    /// | 0x620   | 0x01     | ESE97 with conditional indexing                          |
+    /// | 0x620   | 0x02     | ESE97 (Exchange 5.5, Windows 2000)                       |
+    /// | 0x620   | 0x03     | ESE98 with callbacks                                     |
+    /// | 0x620   | 0x04     | ESE98 with super long values                             |
+    /// | 0x620   | 0x05     | ESE98 with SLV space tree                                |
+    /// | 0x620   | 0x06     | ESE98 with SLV space map                                 |
+    /// | 0x620   | 0x07     | ESE98 with 4-byte index segments                         |
+    /// | 0x620   | 0x08     | ESE98 with new template column format                    |
+    /// | 0x620   | 0x09     | Windows XP, Windows Server 2003                          |
+    /// | 0x620   | 0x0B     | Exchange 2007 (ECC checksums)                            |
+    /// | 0x620   | 0x0C     | Windows Vista                                            |
+    /// | 0x620   | 0x11     | Windows 7 (large pages, extended page headers)           |
+    /// | 0x620   | 0x14     | Exchange 2013, Windows Server 2016                       |
+    ///
+    /// Other combinations are reported as `"unknown"`.
+    pub fn format_version_name(&self) -> &'static str {
+        match (self.version, self.format_revision) {
+            (0x620, 0x00) => "ESE97 beta",
+            (0x620, 0x01) => "ESE97 with conditional indexing",
+            (0x620, 0x02) => "ESE97 (Exchange 5.5, Windows 2000)",
+            (0x620, 0x03) => "ESE98 with callbacks",
+            (0x620, 0x04) => "ESE98 with super long values",
+            (0x620, 0x05) => "ESE98 with SLV space tree",
+            (0x620, 0x06) => "ESE98 with SLV space map",
+            (0x620, 0x07) => "ESE98 with 4-byte index segments",
+            (0x620, 0x08) => "ESE98 with new template column format",
+            (0x620, 0x09) => "Windows XP, Windows Server 2003",
+            (0x620, 0x0B) => "Exchange 2007 (ECC checksums)",
+            (0x620, 0x0C) => "Windows Vista",
+            (0x620, 0x11) => "Windows 7 (large pages, extended page headers)",
+            (0x620, 0x14) => "Exchange 2013, Windows Server 2016",
+            _ => "unknown",
+        }
+    }
+
+    pub fn db_state(&self) -> DbState {
+        self.state
+    }
+
+    /// Whether the database was shut down cleanly, i.e. all changes from the transaction logs
+    /// have been applied to the database file, which is therefore consistent.
+    pub fn is_clean_shutdown(&self) -> bool {
+        self.state == DbState::CleanShutdown
+    }
+
+    /// Whether the database was not shut down cleanly and transaction logs must be replayed to
+    /// make it consistent.
+    ///
+    /// Readers of such a database may encounter pages that were only partially written or that
+    /// refer to data that is missing from the file.
+    pub fn needs_recovery(&self) -> bool {
+        self.state == DbState::DirtyShutdown
+    }
+
+    /// The lowest and highest generation of the transaction logs required to recover the
+    /// database, or `None` if no logs are required.
+    pub fn required_log_generations(&self) -> Option<(u32, u32)> {
+        let lowest: u32 = (self.required_log & 0xFFFF_FFFF).try_into().unwrap();
+        let highest: u32 = (self.required_log >> 32).try_into().unwrap();
+        if lowest == 0 && highest == 0 {
+            None
+        } else {
+            Some((lowest, highest))
+        }
+    }
 }
 
 
@@ -94,6 +174,26 @@ pub struct LogTime {
     pub padding: u8,
     pub backup_type: BackupType,
 }
+impl LogTime {
+    /// Whether all the date and time fields are zero, which denotes that the event never happened.
+    pub fn is_never(&self) -> bool {
+        self.second == 0 && self.minute == 0 && self.hour == 0
+            && self.day == 0 && self.month == 0 && self.year == 0
+    }
+
+    /// Converts the time into a date and time, or `None` if it is the "never" value or any of the
+    /// components are out of range.
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_datetime(&self) -> Option<chrono::NaiveDateTime> {
+        if self.is_never() {
+            return None;
+        }
+        // the year is stored as an offset from 1900
+        let year = 1900 + i32::from(self.year);
+        chrono::NaiveDate::from_ymd_opt(year, self.month.into(), self.day.into())?
+            .and_hms_opt(self.hour.into(), self.minute.into(), self.second.into())
+    }
+}
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, ReadFromAndWriteToBytes)]
 pub struct DbSignature {
@@ -101,6 +201,23 @@ pub struct DbSignature {
     pub creation_timestamp: LogTime,
     pub computer_name: [u8; 16],
 }
+impl DbSignature {
+    /// The name of the computer on which the database was created, decoded from the
+    /// NUL-padded computer name field.
+    pub fn computer_name_string(&self) -> String {
+        let name_bytes = self.computer_name
+            .split(|b| *b == 0x00)
+            .next()
+            .unwrap_or(&[]);
+        decode_string(name_bytes, 1252)
+            .unwrap_or_else(|_| String::from_utf8_lossy(name_bytes).into_owned())
+    }
+
+    /// The time at which the database was created.
+    pub fn creation_time(&self) -> LogTime {
+        self.creation_timestamp
+    }
+}
 
 #[derive(Clone, Copy, Debug, ReadFromAndWriteToBytes)]
 #[from_to_other(base_type = u8, derive_compare = "as_int")]
@@ -113,11 +230,22 @@ pub enum BackupType {
 #[derive(Clone, Copy, Debug, ReadFromAndWriteToBytes)]
 #[from_to_other(base_type = u32, derive_compare = "as_int")]
 pub enum DbState {
+    /// The database is being created and has not yet been shut down.
     JustCreated = 1,
+
+    /// The database is in use or was not shut down cleanly; transaction logs must be replayed to
+    /// make it consistent.
     DirtyShutdown = 2,
+
+    /// The database was shut down cleanly and is consistent.
     CleanShutdown = 3,
+
+    /// The database is being converted to a newer format.
     BeingConverted = 4,
+
+    /// The database was forcibly detached from its transaction logs.
     ForceDetach = 5,
+
     Other(u32),
 }
 
@@ -152,12 +280,15 @@ pub struct ErrorStats {
 }
 
 
-pub fn read_header<R: Read>(reader: &mut R) -> Result<Header, ReadError> {
-    // read bytes of the header
-    const HEADER_SIZE: usize = size_of::<Header>();
+/// The length of the header at the start of its page.
+const HEADER_SIZE: usize = size_of::<Header>();
 
-    let mut header_bytes = vec![0u8; HEADER_SIZE];
-    reader.read_exact(&mut header_bytes)?;
+/// Checks the signature of the header at the start of the given bytes and returns the page size
+/// that the header declares.
+fn header_page_size(header_bytes: &[u8]) -> Result<usize, ReadError> {
+    if header_bytes.len() < HEADER_SIZE {
+        return Err(IoError::UnexpectedEof.into());
+    }
 
     // check magic (signature)
     let signature = u32::from_le_bytes(header_bytes[4..8].try_into().unwrap());
@@ -174,10 +305,17 @@ pub fn read_header<R: Read>(reader: &mut R) -> Result<Header, ReadError> {
     if page_size % 4 != 0 {
         return Err(ReadError::PageSizeNotDivisibleBy4 { page_size });
     }
+    Ok(page_size)
+}
 
-    // read the rest of the page
-    header_bytes.resize(page_size, 0);
-    reader.read_exact(&mut header_bytes[HEADER_SIZE..page_size])?;
+/// Decodes the header from the bytes of the page that holds it, verifying its signature and
+/// checksum.
+///
+/// The page size is taken from the header; any bytes beyond the page are ignored.
+pub fn parse_header(bytes: &[u8]) -> Result<Header, ReadError> {
+    let page_size = header_page_size(bytes)?;
+    let header_bytes = bytes.get(..page_size)
+        .ok_or(IoError::UnexpectedEof)?;
 
     // run the checksum (xor of all u32)
     let file_checksum = u32::from_le_bytes(header_bytes[0..4].try_into().unwrap());
@@ -191,8 +329,94 @@ pub fn read_header<R: Read>(reader: &mut R) -> Result<Header, ReadError> {
     }
 
     // decode the header
-    let cursor = Cursor::new(header_bytes.as_slice());
-    let mut reader = LittleEndianRead::new(cursor);
+    let mut reader = SliceRead::new(header_bytes);
     let header = Header::read_from_bytes(&mut reader)?;
     Ok(header)
 }
+
+/// Reads the header from the page starting at the given offset of the source; see
+/// [`parse_header`].
+pub fn read_header_at<S: DbSource + ?Sized>(source: &S, offset: u64) -> Result<Header, ReadError> {
+    let mut header_bytes = vec![0u8; HEADER_SIZE];
+    source.read_at(offset, &mut header_bytes)?;
+    let page_size = header_page_size(&header_bytes)?;
+
+    // read the rest of the page
+    header_bytes.resize(page_size, 0);
+    source.read_at(offset + u64::try_from(HEADER_SIZE).unwrap(), &mut header_bytes[HEADER_SIZE..])?;
+    parse_header(&header_bytes)
+}
+
+/// Reads the header from the page at the current position of the reader; see [`parse_header`].
+#[cfg(feature = "std")]
+pub fn read_header<R: Read>(reader: &mut R) -> Result<Header, ReadError> {
+    let mut header_bytes = vec![0u8; HEADER_SIZE];
+    reader.read_exact(&mut header_bytes)?;
+    let page_size = header_page_size(&header_bytes)?;
+
+    // read the rest of the page
+    header_bytes.resize(page_size, 0);
+    reader.read_exact(&mut header_bytes[HEADER_SIZE..])?;
+    parse_header(&header_bytes)
+}
+
+/// Writes the header, padded to the page size and with a freshly calculated checksum.
+///
+/// The `checksum` field of the header is ignored.
+#[cfg(feature = "std")]
+pub fn write_header<W: Write>(writer: &mut W, header: &Header) -> Result<(), WriteError> {
+    let page_size = header.page_size_as_usize();
+
+    let mut header_bytes = Vec::with_capacity(page_size);
+    {
+        let mut write = LittleEndianWrite::new(&mut header_bytes);
+        header.write_to_bytes(&mut write)?;
+    }
+    if header_bytes.len() > page_size {
+        return Err(WriteError::WrongPageSize { expected: page_size, obtained: header_bytes.len() });
+    }
+    header_bytes.resize(page_size, 0);
+
+    // same checksum as validated by read_header (xor of all u32 after the checksum and signature)
+    let mut checksum = 0;
+    for chunk in header_bytes[8..].chunks_exact(4) {
+        let value = u32::from_le_bytes(chunk.try_into().unwrap());
+        checksum ^= value;
+    }
+    header_bytes[0..4].copy_from_slice(&checksum.to_le_bytes());
+
+    writer.write_all(&header_bytes)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::test_header;
+
+    #[test]
+    fn header_round_trip() {
+        let mut header = test_header(8 * 1024);
+        header.version = 0x620;
+        header.format_revision = 0x14;
+        header.state = DbState::DirtyShutdown;
+        header.dbid = 1;
+        header.last_oid = 0x1234;
+
+        let mut written = Vec::new();
+        write_header(&mut written, &header).unwrap();
+        assert_eq!(written.len(), 8 * 1024);
+        let read = parse_header(&written).unwrap();
+        assert_eq!(Header { checksum: read.checksum, ..header }, read);
+
+        // flip the state like a clean shutdown would and make sure the checksum follows
+        let clean = Header { state: DbState::CleanShutdown, ..read };
+        let mut rewritten = Vec::new();
+        write_header(&mut rewritten, &clean).unwrap();
+        let reread = read_header_at(rewritten.as_slice(), 0).unwrap();
+        assert_eq!(reread.state, DbState::CleanShutdown);
+        assert_ne!(reread.checksum, read.checksum);
+        assert_eq!(Header { checksum: reread.checksum, ..clean }, reread);
+    }
+}
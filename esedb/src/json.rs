@@ -0,0 +1,66 @@
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Map as JsonMap, Number as JsonNumber, Value as JsonValue};
+
+use crate::data::Data;
+use crate::table::{Column, Value};
+
+
+/// Converts a piece of data into JSON.
+///
+/// Numbers become JSON numbers (non-finite floating-point values become null), text and GUIDs
+/// become strings, and binary data becomes a base64 string.
+pub fn data_to_json(data: &Data) -> JsonValue {
+    match data {
+        Data::Nil => JsonValue::Null,
+        Data::Bit(b) => JsonValue::Bool((*b).into()),
+        Data::UnsignedByte(v) => JsonValue::from(*v),
+        Data::Short(v) => JsonValue::from(*v),
+        Data::Long(v) => JsonValue::from(*v),
+        Data::Currency(v) => JsonValue::from(*v),
+        Data::IeeeSingle(v) => JsonNumber::from_f64((*v).into())
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Data::IeeeDouble(v) => JsonNumber::from_f64(*v)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Data::DateTime(v) => JsonValue::from(*v),
+        Data::Text(s)|Data::LongText(s) => JsonValue::String(s.clone()),
+        Data::UnsignedLong(v) => JsonValue::from(*v),
+        Data::LongLong(v) => JsonValue::from(*v),
+        Data::Guid(g) => JsonValue::String(g.to_string()),
+        Data::UnsignedShort(v) => JsonValue::from(*v),
+        Data::Binary(bs)|Data::LongBinary(bs)|Data::SuperLongValue(bs)|Data::Other(_, bs)
+            => JsonValue::String(BASE64.encode(bs)),
+    }
+}
+
+/// Converts a value into JSON. Multi-valued columns become arrays.
+pub fn value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Simple(data) => data_to_json(data),
+        Value::Complex { data, .. } => data_to_json(data),
+        Value::Multiple { values, .. } => JsonValue::Array(
+            values.iter()
+                .map(data_to_json)
+                .collect()
+        ),
+    }
+}
+
+/// Converts a row into a JSON object keyed by column name.
+///
+/// Values in columns that are not defined in `columns` are keyed by their column ID.
+pub fn row_to_json(row: &BTreeMap<i32, Value>, columns: &[Column]) -> JsonValue {
+    let mut object = JsonMap::new();
+    for (column_id, value) in row {
+        let key = columns.iter()
+            .find(|c| c.column_id == *column_id)
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| column_id.to_string());
+        object.insert(key, value_to_json(value));
+    }
+    JsonValue::Object(object)
+}
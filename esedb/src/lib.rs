@@ -1,8 +1,38 @@
+//! Reading (and writing) Extensible Storage Engine (ESE) databases.
+//!
+//! With the default `std` feature disabled, the crate only requires `core` and `alloc`. What remains
+//! is the parsing layer: database headers ([`header::parse_header`]), pages
+//! ([`page::parse_page_header`], [`page::parse_page_tags`], [`page::parse_page_entry`]) and records
+//! ([`table::decode_record`]) can be decoded from byte slices, and whole tables can be read from
+//! any [`source::DbSource`], such as a database that is held in memory. Opening files, memory maps,
+//! caching sources, writing and everything built on them require `std`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "async")]
+pub mod async_read;
 pub mod byte_io;
 pub mod common;
+pub mod compression;
 pub mod data;
+pub mod database;
+pub mod decrypt;
+#[cfg(feature = "std")]
+pub mod encode;
 pub mod error;
 pub mod header;
+#[cfg(feature = "serde")]
+pub mod json;
 mod macros;
 pub mod page;
+pub mod source;
+pub mod sql;
+pub mod system_tables;
 pub mod table;
+#[cfg(any(test, feature = "testing"))]
+#[doc(hidden)]
+pub mod testing;
@@ -15,14 +15,14 @@ macro_rules! bitflags_read_write_bytes {
             }
         }
         impl crate::byte_io::ReadFromBytes for $name {
-            type Error = ::std::io::Error;
+            type Error = crate::error::IoError;
             fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
                 let value: $base_type = crate::byte_io::ReadFromBytes::read_from_bytes(reader)?;
                 Ok(Self::from_bits_retain(value))
             }
         }
         impl crate::byte_io::WriteToBytes for $name {
-            type Error = ::std::io::Error;
+            type Error = crate::error::IoError;
             fn write_to_bytes<W: crate::byte_io::ByteWrite>(&self, writer: &mut W) -> Result<(), Self::Error> {
                 let bits = self.bits();
                 crate::byte_io::WriteToBytes::write_to_bytes(&bits, writer)
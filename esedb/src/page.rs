@@ -1,16 +1,34 @@
-use std::io::{Cursor, Read, Seek, SeekFrom};
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
+use std::io::{Seek, SeekFrom, Write};
 
 use esedb_macros::ReadFromAndWriteToBytes;
 use from_to_repr::from_to_other;
 use tracing::{instrument, trace};
 
 use crate::bitflags_read_write_bytes;
-use crate::byte_io::{ByteRead, LittleEndianRead, ReadFromBytes, WriteToBytes};
+use crate::byte_io::{ByteRead, ReadFromBytes, SliceRead, WriteToBytes};
+#[cfg(feature = "std")]
+use crate::byte_io::LittleEndianWrite;
 use crate::common::DbTime;
-use crate::error::{ReadError, WriteError};
+use crate::error::{IoError, ReadError, WriteError};
 use crate::header::Header;
+use crate::source::DbSource;
 
 
+/// The initial value of page checksums.
+const PAGE_CHECKSUM_SEED: u32 = 0x89AB_CDEF;
+
+/// The size of the raw page header.
+const PAGE_HEADER_SIZE: usize = 40;
+
+/// The size of the raw page header followed by the extended page header.
+const MAX_PAGE_HEADER_SIZE: usize = 80;
+
 /// The maximum size of a small page. If the page size is greater than this, page tags switch from
 /// the small to the large format.
 pub(crate) const MAX_SIZE_SMALL_PAGE: u32 = 1024 * 8;
@@ -43,6 +61,99 @@ impl PageHeader {
             ChecksumAndPageNumber::V3 { extended_header, .. } => extended_header.page_header_size_bytes(),
         }
     }
+
+    /// The number of bytes available in the data area of the page.
+    ///
+    /// This includes space freed by uncommitted transactions
+    /// ([`available_uncommitted_data_size`](Self::available_uncommitted_data_size)).
+    pub fn free_space(&self) -> u16 { self.available_data_size }
+
+    /// The percentage of the data area of the page (the page minus its header) that is in use.
+    pub fn fill_percent(&self, page_size: u32) -> f32 {
+        let data_area_size = u64::from(page_size).saturating_sub(self.size_bytes());
+        if data_area_size == 0 {
+            return 0.0;
+        }
+        let used_size = data_area_size.saturating_sub(self.free_space().into());
+        (used_size as f32) * 100.0 / (data_area_size as f32)
+    }
+
+    /// Whether the page contains entries that can be read.
+    ///
+    /// Empty pages, pages that have been allocated but not yet initialized, and pages without any
+    /// of the root, branch or leaf flags (such as pages that have never been written) are not
+    /// usable.
+    pub fn is_usable(&self) -> bool {
+        !self.flags.intersects(PageFlags::EMPTY_PAGE | PageFlags::PREINITIALIZED)
+            && self.flags.intersects(PageFlags::ROOT_PAGE | PageFlags::BRANCH_PAGE | PageFlags::LEAF_PAGE)
+    }
+
+    /// Classifies the page according to its role in its tree.
+    ///
+    /// The flags are checked in the same order as when decoding page entries:
+    ///
+    /// 1. Empty and preinitialized pages are [`PageKind::Empty`].
+    /// 2. The leaf flag takes precedence over the root flag, so the root page of a tree that fits
+    ///    on a single page is a leaf. Leaves are space tree leaves if the space tree flag is set,
+    ///    otherwise index leaves if the index flag is set (INDEX+LEAF), otherwise long value
+    ///    pages if the long value flag is set, otherwise data leaves.
+    /// 3. Root pages that are not leaves are [`PageKind::RootData`], whichever tree they belong
+    ///    to, since their entries are decoded uniformly.
+    /// 4. Branch pages are distinguished like leaves: space tree, then index, then long value,
+    ///    then data.
+    /// 5. Pages with none of the root, branch or leaf flags are [`PageKind::Unknown`].
+    pub fn classify(&self) -> PageKind {
+        if self.flags.intersects(PageFlags::EMPTY_PAGE | PageFlags::PREINITIALIZED) {
+            PageKind::Empty
+        } else if self.flags.contains(PageFlags::LEAF_PAGE) {
+            if self.flags.contains(PageFlags::SPACE_TREE_PAGE) {
+                PageKind::SpaceTreeLeaf
+            } else if self.flags.contains(PageFlags::INDEX_PAGE) {
+                PageKind::IndexLeaf
+            } else if self.flags.contains(PageFlags::LONG_VALUE_PAGE) {
+                PageKind::LongValue
+            } else {
+                PageKind::LeafData
+            }
+        } else if self.flags.contains(PageFlags::ROOT_PAGE) {
+            PageKind::RootData
+        } else if self.flags.contains(PageFlags::BRANCH_PAGE) {
+            if self.flags.contains(PageFlags::SPACE_TREE_PAGE) {
+                PageKind::SpaceTreeBranch
+            } else if self.flags.contains(PageFlags::INDEX_PAGE) {
+                PageKind::IndexBranch
+            } else if self.flags.contains(PageFlags::LONG_VALUE_PAGE) {
+                PageKind::LongValue
+            } else {
+                PageKind::BranchData
+            }
+        } else {
+            PageKind::Unknown
+        }
+    }
+}
+
+/// The role of a page in its tree, as determined by [`PageHeader::classify`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PageKind {
+    /// The root page of a tree spanning multiple pages.
+    RootData,
+
+    BranchData,
+    LeafData,
+    SpaceTreeBranch,
+    SpaceTreeLeaf,
+    IndexBranch,
+    IndexLeaf,
+
+    /// A branch or leaf page of a long value tree.
+    LongValue,
+
+    /// A page without readable entries because it is empty or not yet initialized.
+    Empty,
+
+    /// A page whose flags do not describe any known role.
+    Unknown,
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -153,7 +264,7 @@ struct PageTagSmall {
     pub unknown: u8, // u3
 }
 impl ReadFromBytes for PageTagSmall {
-    type Error = std::io::Error;
+    type Error = IoError;
     fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
         let value_size_and_flags = reader.read_u16()?;
         let value_offset_and_flags = reader.read_u16()?;
@@ -204,7 +315,7 @@ struct PageTagLarge {
     pub size_flag: bool, // u1
 }
 impl ReadFromBytes for PageTagLarge {
-    type Error = std::io::Error;
+    type Error = IoError;
     fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
         let value_size_and_flags = reader.read_u16()?;
         let value_offset_and_flags = reader.read_u16()?;
@@ -228,13 +339,18 @@ impl WriteToBytes for PageTagLarge {
         WriteError::check_unsigned_max("value_offset", self.value_offset.into(), 0b0111_1111_1111_1111)?;
         WriteError::check_unsigned_max("value_size", self.value_size.into(), 0b0111_1111_1111_1111)?;
 
-        let full_tag: u32 =
-            (u32::from(self.value_offset) << 17)
-            | if self.offset_flag { 1 << 16 } else { 0 }
-            | (u32::from(self.value_size) << 1)
-            | if self.size_flag { 1 << 0 } else { 0 }
+        // same layout as read_from_bytes
+        let value_size_and_flags =
+            (self.value_size << 0)
+            | if self.size_flag { 1 << 15 } else { 0 }
+        ;
+        let value_offset_and_flags =
+            (self.value_offset << 0)
+            | if self.offset_flag { 1 << 15 } else { 0 }
         ;
-        writer.write_u32(full_tag)?;
+
+        writer.write_u16(value_size_and_flags)?;
+        writer.write_u16(value_offset_and_flags)?;
         Ok(())
     }
 }
@@ -283,10 +399,17 @@ pub struct RootPageHeaderLong {
     pub unknown3: u32,
 }
 
-#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub enum RootPageHeader {
     Short(RootPageHeaderShort),
     Long(RootPageHeaderLong),
+
+    /// A header of unknown length, of which only the fields shared with the short header are
+    /// understood.
+    Other {
+        header: RootPageHeaderShort,
+        extra: Vec<u8>,
+    },
 }
 macro_rules! impl_rph_variant {
     ($name:ident, $type:ty) => {
@@ -294,6 +417,7 @@ macro_rules! impl_rph_variant {
             match self {
                 Self::Short(h) => h.$name,
                 Self::Long(h) => h.$name,
+                Self::Other { header, .. } => header.$name,
             }
         }
     };
@@ -303,14 +427,54 @@ impl RootPageHeader {
     impl_rph_variant!(parent_father_data_page_number, u32);
     impl_rph_variant!(extent_space, ExtentSpace);
     impl_rph_variant!(space_tree_page_number, u32);
+
+    /// Returns the bytes following the fields of a header of unknown length.
+    pub fn extra(&self) -> &[u8] {
+        match self {
+            Self::Short(_) => &[],
+            Self::Long(_) => &[],
+            Self::Other { extra, .. } => extra.as_slice(),
+        }
+    }
 }
 
+/// The key of a page entry.
+///
+/// Entries whose tag is flagged [`PageTagFlags::COMPRESSED`] only store the part of their key that
+/// differs from the key prefix of the page (the data of the page's first tag);
+/// `common_page_key_size` is the number of bytes of that prefix which precede the local key.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct CommonPageEntry {
     pub common_page_key_size: Option<u16>,
     pub local_page_key: Vec<u8>,
 }
 
+impl CommonPageEntry {
+    /// Reconstructs the full key of this entry like [`full_key`](Self::full_key), but fails if the
+    /// key prefix of the page is shorter than the common part of the key.
+    pub fn try_full_key(&self, page_key_prefix: &[u8]) -> Result<Vec<u8>, ReadError> {
+        let common_size = usize::from(self.common_page_key_size.unwrap_or(0));
+        if common_size > page_key_prefix.len() {
+            return Err(ReadError::malformed_record(format!(
+                "common key size {} exceeds page key prefix size {}", common_size, page_key_prefix.len(),
+            )));
+        }
+        Ok(self.full_key(page_key_prefix))
+    }
+
+    /// Reconstructs the full key of this entry, given the key prefix of the page on which it is stored.
+    ///
+    /// If the prefix is shorter than the common part of the key, all of it is used.
+    pub fn full_key(&self, page_key_prefix: &[u8]) -> Vec<u8> {
+        let common_size = usize::from(self.common_page_key_size.unwrap_or(0))
+            .min(page_key_prefix.len());
+        let mut key = Vec::with_capacity(common_size + self.local_page_key.len());
+        key.extend_from_slice(&page_key_prefix[..common_size]);
+        key.extend_from_slice(&self.local_page_key);
+        key
+    }
+}
+
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct BranchPageEntry {
     pub common: CommonPageEntry,
@@ -375,14 +539,13 @@ pub fn page_tag_data_offset(page_size: u32, page_number: u64, page_header_size:
     Ok(page_offset + page_header_size + u64::from(tag_value_offset))
 }
 
-#[instrument(skip(reader, header), fields(header.page_size, header.version, header.revision))]
-pub fn read_page_header<R: Read + Seek>(reader: &mut R, header: &Header, page_number: u64) -> Result<PageHeader, ReadError> {
-    let byte_offset = page_byte_offset(header.page_size, page_number)?;
-    trace!(byte_offset);
-    reader.seek(SeekFrom::Start(byte_offset))?;
-
+/// Decodes the header of the page with the given number from the bytes of the page.
+///
+/// The bytes need only cover the header; reading the page header from a source with
+/// [`read_page_header`] passes just these.
+pub fn parse_page_header(page_bytes: &[u8], header: &Header, page_number: u64) -> Result<PageHeader, ReadError> {
     // read raw page header
-    let mut read = LittleEndianRead::new(reader);
+    let mut read = SliceRead::new(page_bytes);
     let raw_header = RawPageHeader::read_from_bytes(&mut read)?;
     trace!(?raw_header);
     let checksum_and_page_number = if raw_header.flags.contains(PageFlags::NEW_CHECKSUM_FORMAT) {
@@ -410,7 +573,7 @@ pub fn read_page_header<R: Read + Seek>(reader: &mut R, header: &Header, page_nu
     };
     trace!(?checksum_and_page_number);
 
-    Ok(PageHeader {
+    let page_header = PageHeader {
         checksum_and_page_number,
         last_modification_time: raw_header.last_modification_time,
         prev_page_num: raw_header.prev_page_num,
@@ -421,11 +584,40 @@ pub fn read_page_header<R: Read + Seek>(reader: &mut R, header: &Header, page_nu
         first_available_data_offset: raw_header.first_available_data_offset,
         first_available_page_tag: raw_header.first_available_page_tag,
         flags: raw_header.flags,
-    })
+    };
+    Ok(page_header)
+}
+
+#[instrument(skip(source, header), fields(header.page_size, header.version, header.revision))]
+pub fn read_page_header<S: DbSource + ?Sized>(source: &S, header: &Header, page_number: u64) -> Result<PageHeader, ReadError> {
+    let byte_offset = page_byte_offset(header.page_size, page_number)?;
+    trace!(byte_offset);
+
+    if let Some(page_header) = source.cached_page_header(page_number) {
+        return Ok(page_header);
+    }
+
+    // report truncated files clearly instead of failing with an unexpected EOF somewhere
+    let file_len = source.byte_len()?;
+    if byte_offset + u64::from(header.page_size) > file_len {
+        return Err(ReadError::PageBeyondEof { page_number, file_len });
+    }
+
+    // the raw page header may be followed by an extended header; the page is large enough for both
+    let mut header_bytes = [0u8; MAX_PAGE_HEADER_SIZE];
+    source.read_at(byte_offset, &mut header_bytes)?;
+
+    let page_header = parse_page_header(&header_bytes, header, page_number)?;
+    source.cache_page_header(page_number, &page_header);
+    Ok(page_header)
 }
 
-#[instrument(skip(reader))]
-pub fn read_page_tags<R: Read + Seek>(reader: &mut R, page_size: u32, page_header: &PageHeader) -> Result<Vec<PageTag>, ReadError> {
+#[instrument(skip(source))]
+pub fn read_page_tags<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader) -> Result<Vec<PageTag>, ReadError> {
+    if let Some(tags) = source.cached_page_tags(page_header.page_number()) {
+        return Ok(tags);
+    }
+
     // tags are at the end of the page => skip to the beginning of the next page minus the tags used
     let tag_count = u64::from(page_header.first_available_page_tag);
     let tag_byte_count = 4 * tag_count;
@@ -433,15 +625,35 @@ pub fn read_page_tags<R: Read + Seek>(reader: &mut R, page_size: u32, page_heade
     let next_page_byte_offset = page_byte_offset(page_size, page_header.page_number() + 1)?;
     let tags_byte_offset = next_page_byte_offset - tag_byte_count;
     trace!(next_page_byte_offset, tags_byte_offset);
-    reader.seek(SeekFrom::Start(tags_byte_offset))?;
-
-    let mut read = LittleEndianRead::new(reader);
 
     let tag_count_usize: usize = tag_count.try_into().unwrap();
-    let mut tags = Vec::with_capacity(tag_count_usize);
+    let mut tag_bytes = vec![0u8; 4 * tag_count_usize];
+    source.read_at(tags_byte_offset, &mut tag_bytes)?;
+
+    let data_byte_offset = page_byte_offset(page_size, page_header.page_number())? + page_header.size_bytes();
+    let tags = decode_page_tags(&tag_bytes, page_size, |tag_data_offset| {
+        let mut flags_bytes = [0u8; 2];
+        source.read_at(data_byte_offset + u64::from(tag_data_offset), &mut flags_bytes)?;
+        Ok(flags_bytes)
+    })?;
+    source.cache_page_tags(page_header.page_number(), &tags);
+    Ok(tags)
+}
+
+/// Decodes the tags of a page from the bytes at the end of the page, where they are stored
+/// backwards.
+///
+/// On large pages, the flags of a tag are stored at the beginning of its data; `read_flags_bytes`
+/// returns the first two bytes of the data at the given offset from the end of the page header.
+fn decode_page_tags<F: FnMut(u16) -> Result<[u8; 2], ReadError>>(tag_bytes: &[u8], page_size: u32, mut read_flags_bytes: F) -> Result<Vec<PageTag>, ReadError> {
+    let tag_count = tag_bytes.len() / 4;
+    let mut read = SliceRead::new(tag_bytes);
+
+
+    let mut tags = Vec::with_capacity(tag_count);
     if page_size <= MAX_SIZE_SMALL_PAGE {
         // small tags
-        for _ in 0..tag_count_usize {
+        for _ in 0..tag_count {
             let tag = PageTagSmall::read_from_bytes(&mut read)?;
             tags.push(PageTag {
                 value_offset: tag.value_offset,
@@ -456,24 +668,14 @@ pub fn read_page_tags<R: Read + Seek>(reader: &mut R, page_size: u32, page_heade
         // (you wanted to store your own data in there? haha nope)
         // if there's less than two bytes of data, the flags are 0
         // the flags are also always 0 for the first tag
-        let mut tags = Vec::with_capacity(tag_count_usize);
-        for tag_index in 0..tag_count_usize {
+        // (the tags are stored backwards, so the first tag is the last one read)
+        for tag_index in 0..tag_count {
             let tag = PageTagLarge::read_from_bytes(&mut read)?;
-            let flags = if tag_index > 0 && tag.value_size >= 2 {
-                let orig_pos = read.stream_position()?;
-
-                let page_offset = page_byte_offset(page_size, page_header.page_number())?;
-                let page_header_length = page_header.size_bytes();
-                let tag_data_offset: u64 = tag.value_offset.into();
-                let tag_data_pos = page_offset + page_header_length + tag_data_offset;
-                read.seek(SeekFrom::Start(tag_data_pos))?;
-                let flags_u16 = read.read_u16()?;
+            let flags = if tag_index + 1 < tag_count && tag.value_size >= 2 {
+                let flags_u16 = u16::from_le_bytes(read_flags_bytes(tag.value_offset)?);
                 // only the top 3 bits count
                 let flags_u3 = u8::try_from((flags_u16 >> 13) & 0b111).unwrap();
-                let flags = PageTagFlags::from_bits_retain(flags_u3);
-
-                read.seek(SeekFrom::Start(orig_pos))?;
-                flags
+                PageTagFlags::from_bits_retain(flags_u3)
             } else {
                 PageTagFlags::empty()
             };
@@ -489,8 +691,34 @@ pub fn read_page_tags<R: Read + Seek>(reader: &mut R, page_size: u32, page_heade
     Ok(tags)
 }
 
-#[instrument(skip(reader, page_header), fields(page_header.page_number = page_header.page_number(), page_header.size_bytes = page_header.size_bytes()))]
-pub fn read_data_for_tag<R: Read + Seek>(reader: &mut R, page_size: u32, page_header: &PageHeader, tag: &PageTag) -> Result<Vec<u8>, ReadError> {
+/// Decodes the tags of a page from the bytes of the whole page.
+pub fn parse_page_tags(page_bytes: &[u8], page_size: u32, page_header: &PageHeader) -> Result<Vec<PageTag>, ReadError> {
+    let page_size_usize: usize = page_size.try_into().unwrap();
+    let tag_byte_count = 4 * usize::from(page_header.first_available_page_tag);
+    let tag_bytes = page_size_usize.checked_sub(tag_byte_count)
+        .and_then(|tags_start| page_bytes.get(tags_start..page_size_usize))
+        .ok_or(IoError::UnexpectedEof)?;
+
+    let data_start: usize = page_header.size_bytes().try_into().unwrap();
+    decode_page_tags(tag_bytes, page_size, |tag_data_offset| {
+        let flags_start = data_start + usize::from(tag_data_offset);
+        let flags_bytes = page_bytes.get(flags_start..flags_start + 2)
+            .ok_or(IoError::UnexpectedEof)?;
+        Ok(flags_bytes.try_into().unwrap())
+    })
+}
+
+/// Returns the data of the given tag from the bytes of the whole page.
+pub fn page_tag_data<'a>(page_bytes: &'a [u8], page_header: &PageHeader, tag: &PageTag) -> Result<&'a [u8], ReadError> {
+    let data_start: usize = page_header.size_bytes().try_into().unwrap();
+    let tag_start = data_start + usize::from(tag.value_offset);
+    let data = page_bytes.get(tag_start..tag_start + usize::from(tag.value_size))
+        .ok_or(IoError::UnexpectedEof)?;
+    Ok(data)
+}
+
+#[instrument(skip(source, page_header), fields(page_header.page_number = page_header.page_number(), page_header.size_bytes = page_header.size_bytes()))]
+pub fn read_data_for_tag<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader, tag: &PageTag) -> Result<Vec<u8>, ReadError> {
     let tag_data_position = page_tag_data_offset(
         page_size,
         page_header.page_number(),
@@ -500,15 +728,31 @@ pub fn read_data_for_tag<R: Read + Seek>(reader: &mut R, page_size: u32, page_he
     let tag_length: usize = tag.value_size.into();
     trace!(tag_data_position, tag_length);
 
-    reader.seek(SeekFrom::Start(tag_data_position))?;
     let mut buf = vec![0u8; tag_length];
-    reader.read_exact(&mut buf)?;
+    source.read_at(tag_data_position, &mut buf)?;
     Ok(buf)
 }
 
-#[instrument(skip(reader, header), fields(header.page_number, header.version, header.revision))]
-pub fn read_data_from_tree<R: Read + Seek>(
-    reader: &mut R,
+/// Reads the data for a tag like [`read_data_for_tag`], but into an existing buffer, whose
+/// allocation can then be reused across tags.
+pub fn read_data_for_tag_into<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader, tag: &PageTag, buf: &mut Vec<u8>) -> Result<(), ReadError> {
+    let tag_data_position = page_tag_data_offset(
+        page_size,
+        page_header.page_number(),
+        page_header.size_bytes(),
+        tag.value_offset,
+    )?;
+    let tag_length: usize = tag.value_size.into();
+
+    buf.clear();
+    buf.resize(tag_length, 0);
+    source.read_at(tag_data_position, buf)?;
+    Ok(())
+}
+
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_data_from_tree<S: DbSource + ?Sized>(
+    source: &S,
     header: &Header,
     page_number: u64,
     skip_first: usize,
@@ -520,9 +764,14 @@ pub fn read_data_from_tree<R: Read + Seek>(
         return Ok(());
     }
 
-    let page_header = read_page_header(reader, &header, page_number)?;
+    let page_header = read_page_header(source, header, page_number)
+        .map_err(|e| e.at_page(page_number))?;
     trace!(?page_header);
-    let page_tags = read_page_tags(reader, header.page_size, &page_header)?;
+    if !page_header.is_usable() {
+        return Ok(());
+    }
+    let page_tags = read_page_tags(source, header.page_size, &page_header)
+        .map_err(|e| e.at_page(page_number))?;
     trace!(?page_tags);
 
     for (tag_index, page_tag) in page_tags.iter().enumerate() {
@@ -535,12 +784,13 @@ pub fn read_data_from_tree<R: Read + Seek>(
             continue;
         }
 
-        let data = read_page_entry(reader, header.page_size, &page_header, page_tag)?;
+        let data = read_page_entry(source, header.page_size, &page_header, page_tag)
+            .map_err(|e| e.at_page(page_number))?;
         trace!(tag_index, page_entry = ?data);
         if let Some(branch) = data.as_branch() {
             // descend
             read_data_from_tree(
-                reader,
+                source,
                 header,
                 branch.child_page_number.into(),
                 skip_first,
@@ -561,9 +811,446 @@ pub fn read_data_from_tree<R: Read + Seek>(
     Ok(())
 }
 
-#[instrument(skip(reader))]
-pub fn read_page_entry<R: Read + Seek>(reader: &mut R, page_size: u32, page_header: &PageHeader, tag: &PageTag) -> Result<PageEntry, ReadError> {
-    let mut data = read_data_for_tag(reader, page_size, page_header, tag)?;
+/// Reads the leaf entries of the tree starting at the given page along with their keys, in key
+/// order.
+///
+/// For the rows of a table, the key is the primary key of the row. For the entries of an index
+/// tree, the key is the key of the referenced record and the data is empty.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_keys_and_data_from_tree<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+) -> Result<Vec<KeyedEntry>, ReadError> {
+    let mut entries = Vec::new();
+    read_keyed_data_from_tree(source, header, page_number, &mut |leaf| {
+        entries.push(KeyedEntry {
+            key: leaf.key,
+            data: leaf.data,
+        });
+        ControlFlow::Continue(())
+    })?;
+    Ok(entries)
+}
+
+/// Returns the number of the leftmost leaf page of the tree starting at the given page, i.e. the
+/// page at which a chain of sibling leaf pages begins.
+pub fn first_leaf_page_number<S: DbSource + ?Sized>(source: &S, header: &Header, root_page_number: u64) -> Result<u64, ReadError> {
+    let mut page_number = root_page_number;
+    let mut visited = BTreeSet::new();
+    loop {
+        if !visited.insert(page_number) {
+            return Err(ReadError::PageChainCycle { page_number });
+        }
+        let page_header = read_page_header(source, header, page_number)
+            .map_err(|e| e.at_page(page_number))?;
+        if page_header.flags.contains(PageFlags::LEAF_PAGE) {
+            return Ok(page_number);
+        }
+        let page_tags = read_page_tags(source, header.page_size, &page_header)
+            .map_err(|e| e.at_page(page_number))?;
+        let Some(first_tag) = page_tags.get(1) else {
+            // a branch page without entries has no leaves below it
+            return Err(ReadError::malformed_record("branch page has no entries").at_page(page_number));
+        };
+        let entry = read_page_entry(source, header.page_size, &page_header, first_tag)
+            .map_err(|e| e.at_page(page_number))?;
+        let Some(branch) = entry.as_branch() else {
+            return Err(ReadError::UnknownPageType.at_page(page_number));
+        };
+        page_number = branch.child_page_number.into();
+    }
+}
+
+/// Reads the leaf entries starting at the given leaf page by following the `next_page_num` links
+/// between sibling leaf pages instead of descending from the root through the branch pages.
+///
+/// On a healthy tree, starting at [`first_leaf_page_number`] yields the same entries as
+/// [`read_keys_and_data_from_tree`]; this traversal can still reach the leaves if branch pages
+/// are damaged.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_leaves_via_siblings<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    first_leaf_page_number: u64,
+) -> Result<Vec<KeyedEntry>, ReadError> {
+    let mut entries = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut scratch = Vec::new();
+    let mut page_number = first_leaf_page_number;
+    while page_number != 0 {
+        if !visited.insert(page_number) {
+            return Err(ReadError::PageChainCycle { page_number });
+        }
+
+        let page_header = read_page_header(source, header, page_number)
+            .map_err(|e| e.at_page(page_number))?;
+        trace!(?page_header);
+        if !page_header.flags.contains(PageFlags::LEAF_PAGE) {
+            return Err(ReadError::UnknownPageType.at_page(page_number));
+        }
+        if page_header.is_usable() {
+            let page_tags = read_page_tags(source, header.page_size, &page_header)
+                .map_err(|e| e.at_page(page_number))?;
+            let key_prefix = read_page_key_prefix(source, header.page_size, &page_header, &page_tags)
+                .map_err(|e| e.at_page(page_number))?;
+            for page_tag in page_tags.iter().skip(1) {
+                let data = read_page_entry_with_buffer(source, header.page_size, &page_header, page_tag, &mut scratch)
+                    .map_err(|e| e.at_page(page_number))?;
+                match data {
+                    PageEntry::Leaf(leaf) => {
+                        let key = leaf.common.try_full_key(&key_prefix)
+                            .map_err(|e| e.at_page(page_number))?;
+                        entries.push(KeyedEntry {
+                            key,
+                            data: leaf.entry_data,
+                        });
+                    },
+                    PageEntry::IndexLeaf(index_leaf) => {
+                        entries.push(KeyedEntry {
+                            key: index_leaf.record_page_key,
+                            data: Vec::new(),
+                        });
+                    },
+                    _ => {},
+                }
+            }
+        }
+
+        page_number = page_header.next_page_num.into();
+    }
+    Ok(entries)
+}
+
+/// Reads the key prefix shared by the entries of a page.
+///
+/// The prefix is stored as the data of the first tag, except on root pages, where the first tag
+/// contains the root page header instead.
+pub fn read_page_key_prefix<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader, page_tags: &[PageTag]) -> Result<Vec<u8>, ReadError> {
+    if page_header.flags.contains(PageFlags::ROOT_PAGE) {
+        return Ok(Vec::new());
+    }
+    match page_tags.first() {
+        Some(first_tag) => read_data_for_tag(source, page_size, page_header, first_tag),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// The data of a leaf entry along with its key.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct KeyedEntry {
+    /// The full key: the local key of the entry with the common part of the page's key prefix
+    /// prepended.
+    pub key: Vec<u8>,
+
+    pub data: Vec<u8>,
+}
+
+/// A leaf entry encountered while walking a tree.
+///
+/// For leaves of index trees, `key` is the key of the referenced record and `data` is empty.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub(crate) struct TreeLeaf {
+    pub page_number: u64,
+    pub page_flags: PageFlags,
+    pub tag_flags: PageTagFlags,
+    pub key: Vec<u8>,
+    pub data: Vec<u8>,
+}
+
+/// Walks the tree starting at the given page and passes each leaf entry to `visit`, in key order.
+/// The walk stops early if `visit` returns [`ControlFlow::Break`].
+#[instrument(skip(source, header, visit), fields(header.page_number, header.version, header.revision))]
+pub(crate) fn read_keyed_data_from_tree<S: DbSource + ?Sized, F: FnMut(TreeLeaf) -> ControlFlow<()>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    visit: &mut F,
+) -> Result<(), ReadError> {
+    read_keyed_data_from_tree_with_source(source, header, page_number, &mut |_source, leaf| Ok(visit(leaf)))
+}
+
+/// Walks the tree starting at the given page like [`read_keyed_data_from_tree`], but also passes
+/// the source to `visit`, which may use it to read further data, and stops the walk if `visit`
+/// fails.
+pub(crate) fn read_keyed_data_from_tree_with_source<S: DbSource + ?Sized, F: FnMut(&S, TreeLeaf) -> Result<ControlFlow<()>, ReadError>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    visit: &mut F,
+) -> Result<(), ReadError> {
+    // whether the walk was cut short does not matter to the caller
+    // the scratch buffer for entry data is shared by all pages of the walk
+    let mut scratch = Vec::new();
+    let _ = walk_keyed_tree(source, header, page_number, None, None, visit, &mut scratch)?;
+    Ok(())
+}
+
+/// Walks the tree starting at the given page like [`read_keyed_data_from_tree_with_source`], but
+/// skips the subtrees whose keys are all less than `start_key`.
+///
+/// `visit` is passed the first leaf with a key greater than or equal to `start_key` and possibly
+/// some of the leaves preceding it; it should stop the walk once it has seen all the leaves it is
+/// interested in.
+pub(crate) fn read_keyed_data_from_tree_starting_at<S: DbSource + ?Sized, F: FnMut(&S, TreeLeaf) -> Result<ControlFlow<()>, ReadError>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    start_key: &[u8],
+    visit: &mut F,
+) -> Result<(), ReadError> {
+    let mut scratch = Vec::new();
+    let _ = walk_keyed_tree(source, header, page_number, None, Some(start_key), visit, &mut scratch)?;
+    Ok(())
+}
+
+/// Walks the tree starting at the given page like [`read_keyed_data_from_tree_with_source`], but
+/// fails with [`ReadError::PageOwnershipMismatch`] on reaching a page that belongs to a different
+/// object than the root page, which indicates a cross-linked or otherwise corrupted tree.
+pub(crate) fn read_keyed_data_from_tree_verifying_owner<S: DbSource + ?Sized, F: FnMut(&S, TreeLeaf) -> Result<ControlFlow<()>, ReadError>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    visit: &mut F,
+) -> Result<(), ReadError> {
+    // the root page defines the owner of the tree
+    let root_page_header = read_page_header(source, header, page_number)
+        .map_err(|e| e.at_page(page_number))?;
+    let owner = root_page_header.father_data_page_oid;
+
+    let mut scratch = Vec::new();
+    let _ = walk_keyed_tree(source, header, page_number, Some(owner), None, visit, &mut scratch)?;
+    Ok(())
+}
+
+fn walk_keyed_tree<S: DbSource + ?Sized, F: FnMut(&S, TreeLeaf) -> Result<ControlFlow<()>, ReadError>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    expected_owner: Option<u32>,
+    start_key: Option<&[u8]>,
+    visit: &mut F,
+    scratch: &mut Vec<u8>,
+) -> Result<ControlFlow<()>, ReadError> {
+    let page_header = read_page_header(source, header, page_number)
+        .map_err(|e| e.at_page(page_number))?;
+    trace!(?page_header);
+    if let Some(expected) = expected_owner {
+        if page_header.father_data_page_oid != expected {
+            return Err(ReadError::PageOwnershipMismatch {
+                page_number,
+                expected,
+                found: page_header.father_data_page_oid,
+            });
+        }
+    }
+    if !page_header.is_usable() {
+        // nothing to read here; skip the page instead of failing on its tags
+        return Ok(ControlFlow::Continue(()));
+    }
+    let page_tags = read_page_tags(source, header.page_size, &page_header)
+        .map_err(|e| e.at_page(page_number))?;
+    trace!(?page_tags);
+    let key_prefix = read_page_key_prefix(source, header.page_size, &page_header, &page_tags)
+        .map_err(|e| e.at_page(page_number))?;
+    trace!(?key_prefix);
+
+    for (tag_index, page_tag) in page_tags.iter().enumerate().skip(1) {
+        let data = read_page_entry_with_buffer(source, header.page_size, &page_header, page_tag, scratch)
+            .map_err(|e| e.at_page(page_number))?;
+        if let Some(branch) = data.as_branch() {
+            if let Some(start_key) = start_key {
+                // the key of a branch entry is greater than or equal to all keys below it
+                // the last entry of a branch page covers all keys beyond the other ones
+                let is_last = tag_index + 1 == page_tags.len();
+                let branch_key = branch.common.try_full_key(&key_prefix)
+                    .map_err(|e| e.at_page(page_number))?;
+                if !is_last && branch_key.as_slice() < start_key {
+                    continue;
+                }
+            }
+
+            let flow = walk_keyed_tree(
+                source,
+                header,
+                branch.child_page_number.into(),
+                expected_owner,
+                start_key,
+                visit,
+                scratch,
+            )?;
+            if flow.is_break() {
+                return Ok(flow);
+            }
+        } else if let PageEntry::Leaf(leaf) = data {
+            let key = leaf.common.try_full_key(&key_prefix)
+                .map_err(|e| e.at_page(page_number))?;
+            let tree_leaf = TreeLeaf {
+                page_number,
+                page_flags: page_header.flags,
+                tag_flags: page_tag.flags,
+                key,
+                data: leaf.entry_data,
+            };
+            if visit(source, tree_leaf)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        } else if let PageEntry::IndexLeaf(index_leaf) = data {
+            let tree_leaf = TreeLeaf {
+                page_number,
+                page_flags: page_header.flags,
+                tag_flags: page_tag.flags,
+                key: index_leaf.record_page_key,
+                data: Vec::new(),
+            };
+            if visit(source, tree_leaf)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+    }
+
+    Ok(ControlFlow::Continue(()))
+}
+
+/// Iterates over the headers of all pages in a database file, in page order, regardless of
+/// whether they are linked into any tree.
+///
+/// Failing to read the header of a page does not end the scan; the error is returned along with
+/// the page number and the scan continues with the next page.
+pub struct PageScan<'a, S: ?Sized> {
+    source: &'a S,
+    header: &'a Header,
+    next_page_number: u64,
+    last_page_number: u64,
+}
+impl<'a, S: DbSource + ?Sized> PageScan<'a, S> {
+    pub fn new(source: &'a S, header: &'a Header) -> Result<Self, ReadError> {
+        let file_len = source.byte_len()?;
+        // the header and shadow header take up the first two pages
+        let last_page_number = (file_len / u64::from(header.page_size)).saturating_sub(2);
+        Ok(Self {
+            source,
+            header,
+            next_page_number: 1,
+            last_page_number,
+        })
+    }
+
+    /// The source, e.g. to read the tags of the page whose header was just returned.
+    pub fn source(&self) -> &'a S { self.source }
+}
+impl<'a, S: DbSource + ?Sized> Iterator for PageScan<'a, S> {
+    type Item = (u64, Result<PageHeader, ReadError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_page_number > self.last_page_number {
+            return None;
+        }
+        let page_number = self.next_page_number;
+        self.next_page_number += 1;
+
+        let page_header_res = read_page_header(self.source, self.header, page_number)
+            .map_err(|e| e.at_page(page_number));
+        Some((page_number, page_header_res))
+    }
+}
+
+/// Statistics about the shape of a B-tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct BTreeStats {
+    /// The number of levels of the tree; a tree consisting only of its root page has depth 1.
+    pub depth: u32,
+
+    pub branch_pages: u64,
+    pub leaf_pages: u64,
+
+    /// The number of leaf entries (rows, for the tree of a table).
+    pub entries: u64,
+
+    /// The average proportion of the data area of each page that is in use, between 0 and 1.
+    pub avg_fill: f64,
+}
+
+/// Walks the tree starting at the given page and collects statistics about its shape.
+///
+/// Pages without readable entries (see [`PageHeader::is_usable`]) are not counted.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn btree_stats<S: DbSource + ?Sized>(source: &S, header: &Header, root_page_number: u64) -> Result<BTreeStats, ReadError> {
+    let mut stats = BTreeStats::default();
+    let mut fill_sum = 0.0;
+    let mut scratch = Vec::new();
+    collect_btree_stats(source, header, root_page_number, 1, &mut stats, &mut fill_sum, &mut scratch)?;
+
+    let page_count = stats.branch_pages + stats.leaf_pages;
+    if page_count > 0 {
+        stats.avg_fill = fill_sum / (page_count as f64);
+    }
+    Ok(stats)
+}
+
+fn collect_btree_stats<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    level: u32,
+    stats: &mut BTreeStats,
+    fill_sum: &mut f64,
+    scratch: &mut Vec<u8>,
+) -> Result<(), ReadError> {
+    let page_header = read_page_header(source, header, page_number)
+        .map_err(|e| e.at_page(page_number))?;
+    if !page_header.is_usable() {
+        return Ok(());
+    }
+    let page_tags = read_page_tags(source, header.page_size, &page_header)
+        .map_err(|e| e.at_page(page_number))?;
+
+    stats.depth = stats.depth.max(level);
+    let data_area_size = u64::from(header.page_size).saturating_sub(page_header.size_bytes());
+    if data_area_size > 0 {
+        let used_size = data_area_size.saturating_sub(page_header.available_data_size.into());
+        *fill_sum += (used_size as f64) / (data_area_size as f64);
+    }
+
+    if page_header.flags.contains(PageFlags::LEAF_PAGE) {
+        stats.leaf_pages += 1;
+        // the first tag holds the page key prefix or root page header
+        stats.entries += u64::try_from(page_tags.len().saturating_sub(1)).unwrap();
+        return Ok(());
+    }
+
+    stats.branch_pages += 1;
+    for page_tag in page_tags.iter().skip(1) {
+        let entry = read_page_entry_with_buffer(source, header.page_size, &page_header, page_tag, scratch)
+            .map_err(|e| e.at_page(page_number))?;
+        if let Some(branch) = entry.as_branch() {
+            collect_btree_stats(source, header, branch.child_page_number.into(), level + 1, stats, fill_sum, scratch)?;
+        }
+    }
+    Ok(())
+}
+
+#[instrument(skip(source))]
+pub fn read_page_entry<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader, tag: &PageTag) -> Result<PageEntry, ReadError> {
+    let mut buf = Vec::new();
+    read_page_entry_with_buffer(source, page_size, page_header, tag, &mut buf)
+}
+
+/// Reads a page entry like [`read_page_entry`], using the given buffer to hold the raw data of the
+/// tag.
+pub fn read_page_entry_with_buffer<S: DbSource + ?Sized>(source: &S, page_size: u32, page_header: &PageHeader, tag: &PageTag, buf: &mut Vec<u8>) -> Result<PageEntry, ReadError> {
+    read_data_for_tag_into(source, page_size, page_header, tag, buf)?;
+    decode_page_entry(buf, page_header, tag)
+}
+
+/// Decodes the entry of the given tag from the bytes of the whole page; see [`read_page_entry`].
+pub fn parse_page_entry(page_bytes: &[u8], page_header: &PageHeader, tag: &PageTag) -> Result<PageEntry, ReadError> {
+    let mut data = page_tag_data(page_bytes, page_header, tag)?.to_vec();
+    decode_page_entry(&mut data, page_header, tag)
+}
+
+/// Decodes a page entry from the data of its tag, from which the flags of a large page are removed.
+fn decode_page_entry(data: &mut [u8], page_header: &PageHeader, tag: &PageTag) -> Result<PageEntry, ReadError> {
     trace!(page_entry_bytes = ?data, ?page_header.flags);
 
     if data.len() >= 2 && tag.flags_in_data {
@@ -575,12 +1262,11 @@ pub fn read_page_entry<R: Read + Seek>(reader: &mut R, page_size: u32, page_head
     if page_header.flags.contains(PageFlags::LEAF_PAGE | PageFlags::INDEX_PAGE) {
         // does not have the common key part
         return Ok(PageEntry::IndexLeaf(IndexLeafPageEntry {
-            record_page_key: data,
+            record_page_key: data.to_vec(),
         }))
     }
 
-    let cursor = Cursor::new(&data);
-    let mut read = LittleEndianRead::new(cursor);
+    let mut read = SliceRead::new(data);
 
     let common_page_key_size = if tag.flags.contains(PageTagFlags::COMPRESSED) {
         // starts with common page key size
@@ -595,7 +1281,7 @@ pub fn read_page_entry<R: Read + Seek>(reader: &mut R, page_size: u32, page_head
     let local_page_key_size_usize = usize::from(local_page_key_size);
     trace!(local_page_key_size);
     let mut local_page_key = vec![0u8; local_page_key_size_usize];
-    read.read_exact(&mut local_page_key)?;
+    read.read_bytes(&mut local_page_key)?;
     trace!(?local_page_key);
 
     let common = CommonPageEntry {
@@ -615,8 +1301,7 @@ pub fn read_page_entry<R: Read + Seek>(reader: &mut R, page_size: u32, page_head
             // we handled this before (because it has no common block)
             unreachable!();
         } else {
-            let mut entry_data = Vec::with_capacity(data.len());
-            read.read_to_end(&mut entry_data)?;
+            let entry_data = read.remaining().to_vec();
             Ok(PageEntry::Leaf(LeafPageEntry {
                 common,
                 entry_data,
@@ -648,8 +1333,7 @@ pub fn read_page_entry<R: Read + Seek>(reader: &mut R, page_size: u32, page_head
 }
 
 pub fn read_root_page_header(data: &[u8]) -> Result<RootPageHeader, ReadError> {
-    let cursor = Cursor::new(data);
-    let mut read = LittleEndianRead::new(cursor);
+    let mut read = SliceRead::new(data);
 
     if data.len() == 16 {
         let short = RootPageHeaderShort::read_from_bytes(&mut read)?;
@@ -657,7 +1341,563 @@ pub fn read_root_page_header(data: &[u8]) -> Result<RootPageHeader, ReadError> {
     } else if data.len() == 25 {
         let long = RootPageHeaderLong::read_from_bytes(&mut read)?;
         Ok(RootPageHeader::Long(long))
+    } else if data.len() > 16 {
+        // assume a short header followed by fields we don't know about
+        let header = RootPageHeaderShort::read_from_bytes(&mut read)?;
+        Ok(RootPageHeader::Other {
+            header,
+            extra: data[16..].to_vec(),
+        })
     } else {
         Err(ReadError::UnknownFormatVariant)
     }
 }
+
+/// Reads the root page header of the tree whose root is the given page.
+///
+/// The header is stored as the data of the first tag of the root page; both the small and the
+/// large page tag formats are handled by [`read_page_tags`].
+pub fn read_root_page<S: DbSource + ?Sized>(source: &S, header: &Header, page_number: u64) -> Result<RootPageHeader, ReadError> {
+    let page_header = read_page_header(source, header, page_number)
+        .map_err(|e| e.at_page(page_number))?;
+    if !page_header.flags.contains(PageFlags::ROOT_PAGE) {
+        return Err(ReadError::UnknownPageType.at_page(page_number));
+    }
+    let page_tags = read_page_tags(source, header.page_size, &page_header)
+        .map_err(|e| e.at_page(page_number))?;
+    let Some(header_tag) = page_tags.first() else {
+        return Err(ReadError::malformed_record("root page has no tags").at_page(page_number));
+    };
+    let data = read_data_for_tag(source, header.page_size, &page_header, header_tag)
+        .map_err(|e| e.at_page(page_number))?;
+    read_root_page_header(&data)
+        .map_err(|e| e.at_page(page_number))
+}
+
+
+/// Reads the bytes of the page with the given number.
+pub fn read_page_bytes<S: DbSource + ?Sized>(source: &S, header: &Header, page_number: u64) -> Result<Vec<u8>, ReadError> {
+    let byte_offset = page_byte_offset(header.page_size, page_number)?;
+    let file_len = source.byte_len()?;
+    if byte_offset + u64::from(header.page_size) > file_len {
+        return Err(ReadError::PageBeyondEof { page_number, file_len });
+    }
+    let mut page_bytes = vec![0u8; header.page_size_as_usize()];
+    source.read_at(byte_offset, &mut page_bytes)?;
+    Ok(page_bytes)
+}
+
+/// The outcome of verifying the checksum of a page.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PageChecksumStatus {
+    Valid,
+
+    /// The stored checksum differs from the computed one. With the newer checksum format, the
+    /// error-correcting code is stored in the upper 32 bits and the XOR checksum in the lower 32.
+    Mismatch { stored: u64, computed: u64 },
+
+    /// The page has never been written, so it has no checksum.
+    Uninitialized,
+}
+
+/// Verifies the checksum of a page.
+///
+/// Small pages in the newer checksum format do not store their page number but mix it into the
+/// checksum; `page_number` is therefore required. See [`recompute_page_checksum`] for the formats.
+///
+/// Fails if `page_bytes` is too short to contain a page header.
+pub fn verify_page_checksum(page_bytes: &[u8], page_number: u64) -> Result<PageChecksumStatus, ReadError> {
+    if page_bytes.len() < PAGE_HEADER_SIZE {
+        return Err(ReadError::PageTooShort { length: page_bytes.len(), min_length: PAGE_HEADER_SIZE });
+    }
+    if page_bytes.iter().all(|b| *b == 0) {
+        return Ok(PageChecksumStatus::Uninitialized);
+    }
+
+    let flags_bytes: [u8; 4] = page_bytes[36..40].try_into().unwrap();
+    let flags = PageFlags::from_bits_retain(u32::from_le_bytes(flags_bytes));
+    if !flags.contains(PageFlags::NEW_CHECKSUM_FORMAT) {
+        let stored = u32::from_le_bytes(page_bytes[0..4].try_into().unwrap());
+        let computed = legacy_page_checksum(page_bytes);
+        return if stored == computed {
+            Ok(PageChecksumStatus::Valid)
+        } else {
+            Ok(PageChecksumStatus::Mismatch { stored: stored.into(), computed: computed.into() })
+        };
+    }
+
+    // on large pages, each quarter of the page has its own checksum
+    let block_count = new_format_block_count(page_bytes.len());
+    let block_size = page_bytes.len() / block_count;
+    for (block_index, block) in page_bytes.chunks_exact(block_size).enumerate() {
+        let checksum_offset = new_format_checksum_offset(block_index);
+        let stored = u64::from_le_bytes(page_bytes[checksum_offset..checksum_offset+8].try_into().unwrap());
+        let computed = new_format_block_checksum(block, page_number, block_index == 0);
+        if stored != computed {
+            return Ok(PageChecksumStatus::Mismatch { stored, computed });
+        }
+    }
+    Ok(PageChecksumStatus::Valid)
+}
+
+/// Computes the legacy checksum of a page: an XOR of all 32-bit words following the checksum.
+fn legacy_page_checksum(page_bytes: &[u8]) -> u32 {
+    let mut checksum = PAGE_CHECKSUM_SEED;
+    for chunk in page_bytes[4..].chunks_exact(4) {
+        checksum ^= u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    checksum
+}
+
+/// The number of separately checksummed blocks of a page in the newer checksum format.
+fn new_format_block_count(page_size: usize) -> usize {
+    if page_size <= MAX_SIZE_SMALL_PAGE.try_into().unwrap() { 1 } else { 4 }
+}
+
+/// The offset of the checksum of the given block within a page in the newer checksum format.
+///
+/// The checksum of the first block is stored at the beginning of the page header, those of the
+/// other blocks in the extended page header.
+fn new_format_checksum_offset(block_index: usize) -> usize {
+    if block_index == 0 { 0 } else { 40 + 8 * (block_index - 1) }
+}
+
+/// Computes the checksum of a block of a page in the newer checksum format.
+///
+/// The lower 32 bits are an XOR of all 32-bit words of the block, the seed and the page number.
+/// The upper 32 bits are an error-correcting code: the upper 16 bits are an XOR of the indexes of
+/// all set bits, the lower 16 bits an XOR of their complements. Flipping a single bit therefore
+/// changes both halves by complementary values, which pinpoints the bit.
+///
+/// In the first block of a page, the checksum itself is skipped.
+fn new_format_block_checksum(block: &[u8], page_number: u64, header_block: bool) -> u64 {
+    let mut xor: u32 = 0;
+    let mut word_index_xor: u32 = 0;
+    for (word_index, chunk) in block.chunks_exact(4).enumerate() {
+        if header_block && word_index < 2 {
+            continue;
+        }
+        let word = u32::from_le_bytes(chunk.try_into().unwrap());
+        xor ^= word;
+        if word.count_ones() % 2 == 1 {
+            // the index of bit b of word w is 32*w + b; collect the 32*w parts here
+            word_index_xor ^= u32::try_from(32 * word_index).unwrap();
+        }
+    }
+
+    // xor now has a bit set at each position b that is set an odd number of times
+    let mut bit_index_xor = word_index_xor;
+    for bit in 0..32 {
+        if xor & (1 << bit) != 0 {
+            bit_index_xor ^= bit;
+        }
+    }
+    let complement_mask = if xor.count_ones() % 2 == 1 { 0xFFFF } else { 0x0000 };
+    let ecc = ((bit_index_xor & 0xFFFF) << 16) | ((bit_index_xor ^ complement_mask) & 0xFFFF);
+
+    // only the lower 32 bits of the page number are included
+    let xor_checksum = PAGE_CHECKSUM_SEED ^ xor ^ (page_number as u32);
+    (u64::from(ecc) << 32) | u64::from(xor_checksum)
+}
+
+/// Recomputes the checksum of a page and stores it in the header of the page.
+///
+/// In the legacy checksum format (pages without [`PageFlags::NEW_CHECKSUM_FORMAT`]), the checksum
+/// is an XOR of all 32-bit words of the page following the checksum itself.
+///
+/// In the newer format, which is stored as the [`ChecksumAndPageNumber::V2`] pair or the
+/// [`ChecksumAndPageNumber::V3`] 64-bit value, the checksum consists of an XOR checksum into which
+/// the page number is mixed and an error-correcting code. Pages larger than 8 KiB are split into
+/// four blocks; the checksums of the last three are stored in the extended page header and are
+/// computed before the checksum of the first block, which covers them.
+pub fn recompute_page_checksum(page_bytes: &mut [u8], header: &Header, page_number: u64) -> Result<(), WriteError> {
+    let page_size = header.page_size_as_usize();
+    if page_bytes.len() != page_size {
+        return Err(WriteError::WrongPageSize { expected: page_size, obtained: page_bytes.len() });
+    }
+
+    let flags_bytes: [u8; 4] = page_bytes[36..40].try_into().unwrap();
+    let flags = PageFlags::from_bits_retain(u32::from_le_bytes(flags_bytes));
+    if !flags.contains(PageFlags::NEW_CHECKSUM_FORMAT) {
+        let checksum = legacy_page_checksum(page_bytes);
+        page_bytes[0..4].copy_from_slice(&checksum.to_le_bytes());
+        return Ok(());
+    }
+
+    let block_count = new_format_block_count(page_size);
+    let block_size = page_size / block_count;
+    for block_index in (0..block_count).rev() {
+        let block = &page_bytes[block_index*block_size..(block_index+1)*block_size];
+        let checksum = new_format_block_checksum(block, page_number, block_index == 0);
+        let checksum_offset = new_format_checksum_offset(block_index);
+        page_bytes[checksum_offset..checksum_offset+8].copy_from_slice(&checksum.to_le_bytes());
+    }
+    Ok(())
+}
+
+/// Assembles a page from its header, its tags and its data area, recomputes its checksum and
+/// writes it to its position in the file.
+///
+/// `data` is the content of the page between the header and the tags; tag offsets are relative to
+/// its beginning. On large pages, the tag flags must already be stored within `data`.
+#[cfg(feature = "std")]
+pub fn write_page<W: Write + Seek>(writer: &mut W, header: &Header, page_header: &PageHeader, tags: &[PageTag], data: &[u8]) -> Result<(), WriteError> {
+    let page_size = header.page_size_as_usize();
+    let page_number = page_header.page_number();
+
+    let checksum_and_page_number_value = match &page_header.checksum_and_page_number {
+        ChecksumAndPageNumber::V1 { xor_checksum, page_number }
+            => (u64::from(*page_number) << 32) | u64::from(*xor_checksum),
+        ChecksumAndPageNumber::V2 { xor_checksum, ecc_checksum, .. }
+            => (u64::from(*ecc_checksum) << 32) | u64::from(*xor_checksum),
+        ChecksumAndPageNumber::V3 { checksum, .. }
+            => *checksum,
+    };
+    let raw_header = RawPageHeader {
+        checksum_and_page_number_value,
+        last_modification_time: page_header.last_modification_time,
+        prev_page_num: page_header.prev_page_num,
+        next_page_num: page_header.next_page_num,
+        father_data_page_oid: page_header.father_data_page_oid,
+        available_data_size: page_header.available_data_size,
+        available_uncommitted_data_size: page_header.available_uncommitted_data_size,
+        first_available_data_offset: page_header.first_available_data_offset,
+        first_available_page_tag: page_header.first_available_page_tag,
+        flags: page_header.flags,
+    };
+
+    let mut page_bytes = Vec::with_capacity(page_size);
+    {
+        let mut write = LittleEndianWrite::new(&mut page_bytes);
+        raw_header.write_to_bytes(&mut write)?;
+        let extended_header = match &page_header.checksum_and_page_number {
+            ChecksumAndPageNumber::V1 { .. } => None,
+            ChecksumAndPageNumber::V2 { extended_header, .. } => Some(extended_header),
+            ChecksumAndPageNumber::V3 { extended_header, .. } => Some(extended_header),
+        };
+        if let Some(ExtendedPageHeaderOrPageNumber::ExtendedPageHeader(eph)) = extended_header {
+            eph.write_to_bytes(&mut write)?;
+        }
+    }
+
+    let tags_size = 4 * tags.len();
+    if page_bytes.len() + data.len() + tags_size > page_size {
+        return Err(WriteError::WrongPageSize { expected: page_size, obtained: page_bytes.len() + data.len() + tags_size });
+    }
+    page_bytes.extend_from_slice(data);
+    page_bytes.resize(page_size - tags_size, 0);
+
+    {
+        // tags are stored backwards from the end of the page
+        let mut write = LittleEndianWrite::new(&mut page_bytes);
+        for tag in tags.iter().rev() {
+            if header.page_size <= MAX_SIZE_SMALL_PAGE {
+                let small_tag = PageTagSmall {
+                    value_offset: tag.value_offset,
+                    flags: tag.flags,
+                    value_size: tag.value_size,
+                    unknown: 0,
+                };
+                small_tag.write_to_bytes(&mut write)?;
+            } else {
+                let large_tag = PageTagLarge {
+                    value_offset: tag.value_offset,
+                    offset_flag: false,
+                    value_size: tag.value_size,
+                    size_flag: false,
+                };
+                large_tag.write_to_bytes(&mut write)?;
+            }
+        }
+    }
+
+    recompute_page_checksum(&mut page_bytes, header, page_number)?;
+
+    let byte_offset = page_byte_offset(header.page_size, page_number)
+        .map_err(|_| WriteError::Page0)?;
+    writer.seek(SeekFrom::Start(byte_offset))?;
+    writer.write_all(&page_bytes)?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    use crate::testing::{branch_entry, compressed_leaf_entry, leaf_entry, test_header, test_page_header, TestDb};
+
+    #[test]
+    fn large_page_tags_round_trip() {
+        // pages larger than 8 KiB use the large tag format with the flags stored in the data
+        let page_size = 16 * 1024;
+        let header = test_header(page_size);
+        let page_header = test_page_header(1, 3, PageFlags::LEAF_PAGE);
+        let tags = [
+            PageTag { value_offset: 0, value_size: 4, flags: PageTagFlags::empty(), flags_in_data: true },
+            PageTag { value_offset: 4, value_size: 6, flags: PageTagFlags::empty(), flags_in_data: true },
+            PageTag { value_offset: 10, value_size: 1, flags: PageTagFlags::empty(), flags_in_data: true },
+        ];
+        // DELETED in the top bits of the first u16 of the second tag's data
+        let data = [0xAA, 0xBB, 0xCC, 0xDD, 0x01, 0x40, 0x02, 0x03, 0x04, 0x05, 0x06];
+
+        let mut file = Cursor::new(Vec::new());
+        write_page(&mut file, &header, &page_header, &tags, &data).unwrap();
+        assert_eq!(file.get_ref().len(), 3 * 16 * 1024);
+
+        let read_tags = read_page_tags(file.get_ref(), page_size, &page_header).unwrap();
+        assert_eq!(read_tags.len(), 3);
+        for (read_tag, tag) in read_tags.iter().zip(tags.iter()) {
+            assert_eq!(read_tag.value_offset, tag.value_offset);
+            assert_eq!(read_tag.value_size, tag.value_size);
+            assert!(read_tag.flags_in_data);
+        }
+        assert_eq!(read_tags[0].flags, PageTagFlags::empty());
+        assert_eq!(read_tags[1].flags, PageTagFlags::DELETED);
+        assert_eq!(read_tags[2].flags, PageTagFlags::empty());
+    }
+
+    #[test]
+    fn page_decodes_from_its_bytes_alone() {
+        let page_size = 16 * 1024;
+        let header = test_header(page_size);
+        let page_header = test_page_header(1, 2, PageFlags::LEAF_PAGE);
+        let tags = [
+            PageTag { value_offset: 0, value_size: 4, flags: PageTagFlags::empty(), flags_in_data: true },
+            PageTag { value_offset: 4, value_size: 6, flags: PageTagFlags::empty(), flags_in_data: true },
+        ];
+        // DELETED in the top bits of the key length of the second tag's entry
+        let data = [0xAA, 0xBB, 0xCC, 0xDD, 0x01, 0x40, 0x02, 0x03, 0x04, 0x05];
+        let mut file = Cursor::new(Vec::new());
+        write_page(&mut file, &header, &page_header, &tags, &data).unwrap();
+
+        let read_header = read_page_header(file.get_ref(), &header, 1).unwrap();
+        let read_tags = read_page_tags(file.get_ref(), page_size, &read_header).unwrap();
+        let read_entry = read_page_entry(file.get_ref(), page_size, &read_header, &read_tags[1]).unwrap();
+
+        let page_bytes = read_page_bytes(file.get_ref(), &header, 1).unwrap();
+        assert_eq!(parse_page_header(&page_bytes, &header, 1).unwrap(), read_header);
+        assert_eq!(parse_page_tags(&page_bytes, page_size, &read_header).unwrap(), read_tags);
+        assert_eq!(page_tag_data(&page_bytes, &read_header, &read_tags[1]).unwrap(), &data[4..10]);
+        assert_eq!(parse_page_entry(&page_bytes, &read_header, &read_tags[1]).unwrap(), read_entry);
+        assert_eq!(read_tags[1].flags, PageTagFlags::DELETED);
+        let PageEntry::Leaf(entry) = read_entry else { panic!("expected a leaf entry") };
+        assert_eq!(entry.common.local_page_key, [0x02]);
+        assert_eq!(entry.entry_data, [0x03, 0x04, 0x05]);
+
+        // a page cut short before its tags
+        assert!(matches!(
+            parse_page_tags(&page_bytes[..page_bytes.len() - 1], page_size, &read_header),
+            Err(ReadError::Io(IoError::UnexpectedEof)),
+        ));
+    }
+
+    #[test]
+    fn small_page_tags_round_trip() {
+        let page_size = 8 * 1024;
+        let header = test_header(page_size);
+        let page_header = test_page_header(1, 2, PageFlags::LEAF_PAGE);
+        let tags = [
+            PageTag { value_offset: 0, value_size: 4, flags: PageTagFlags::empty(), flags_in_data: false },
+            PageTag { value_offset: 4, value_size: 3, flags: PageTagFlags::COMPRESSED, flags_in_data: false },
+        ];
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+
+        let mut file = Cursor::new(Vec::new());
+        write_page(&mut file, &header, &page_header, &tags, &data).unwrap();
+
+        let read_tags = read_page_tags(file.get_ref(), page_size, &page_header).unwrap();
+        assert_eq!(read_tags, tags);
+    }
+
+    /// Writes a page, reads it back, rewrites it unchanged and checks that the checksums round-trip.
+    fn assert_checksum_round_trip(header: &Header, page_header: &PageHeader) {
+        let tags = [
+            PageTag { value_offset: 0, value_size: 4, flags: PageTagFlags::empty(), flags_in_data: false },
+            PageTag { value_offset: 4, value_size: 3, flags: PageTagFlags::empty(), flags_in_data: false },
+        ];
+        let data = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
+        let page_number = page_header.page_number();
+
+        let mut file = Cursor::new(Vec::new());
+        write_page(&mut file, header, page_header, &tags, &data).unwrap();
+        let page_bytes = read_page_bytes(file.get_ref(), header, page_number).unwrap();
+        assert_eq!(verify_page_checksum(&page_bytes, page_number).unwrap(), PageChecksumStatus::Valid);
+
+        // rewrite the page from what has been read
+        let read_header = read_page_header(file.get_ref(), header, page_number).unwrap();
+        let read_tags = read_page_tags(file.get_ref(), header.page_size, &read_header).unwrap();
+        let data_start: usize = read_header.size_bytes().try_into().unwrap();
+        let data_end = page_bytes.len() - 4 * read_tags.len();
+        let mut rewritten = Cursor::new(Vec::new());
+        write_page(&mut rewritten, header, &read_header, &read_tags, &page_bytes[data_start..data_end]).unwrap();
+        assert_eq!(read_page_bytes(rewritten.get_ref(), header, page_number).unwrap(), page_bytes);
+
+        // recompute the checksum after clobbering it
+        let mut recomputed = page_bytes.clone();
+        if !page_header.flags.contains(PageFlags::NEW_CHECKSUM_FORMAT) {
+            // the legacy format stores the page number next to the checksum
+            recomputed[0..4].fill(0xFF);
+        } else {
+            recomputed[0..8].fill(0xFF);
+            if recomputed.len() > 8 * 1024 {
+                recomputed[40..64].fill(0xFF);
+            }
+        }
+        recompute_page_checksum(&mut recomputed, header, page_number).unwrap();
+        assert_eq!(recomputed, page_bytes);
+
+        // corrupt a bit in the data area
+        recomputed[data_start + 5] ^= 0x10;
+        assert!(matches!(verify_page_checksum(&recomputed, page_number), Ok(PageChecksumStatus::Mismatch { .. })));
+    }
+
+    fn new_format_page_header(page_size: u32, page_number: u32, v3: bool) -> PageHeader {
+        let extended_header = if page_size <= MAX_SIZE_SMALL_PAGE {
+            ExtendedPageHeaderOrPageNumber::PageNumber(page_number.into())
+        } else {
+            ExtendedPageHeaderOrPageNumber::ExtendedPageHeader(ExtendedPageHeader {
+                extended_checksum_1: 0,
+                extended_checksum_2: 0,
+                extended_checksum_3: 0,
+                page_number: page_number.into(),
+                unknown: 0,
+            })
+        };
+        let checksum_and_page_number = if v3 {
+            ChecksumAndPageNumber::V3 { checksum: 0, extended_header }
+        } else {
+            ChecksumAndPageNumber::V2 { xor_checksum: 0, ecc_checksum: 0, extended_header }
+        };
+        PageHeader {
+            checksum_and_page_number,
+            ..test_page_header(page_number, 2, PageFlags::LEAF_PAGE | PageFlags::NEW_CHECKSUM_FORMAT)
+        }
+    }
+
+    #[test]
+    fn legacy_checksum_round_trip() {
+        let header = test_header(8 * 1024);
+        assert_checksum_round_trip(&header, &test_page_header(3, 2, PageFlags::LEAF_PAGE));
+    }
+
+    #[test]
+    fn ecc_checksum_round_trip() {
+        let header = test_header(8 * 1024);
+        assert_checksum_round_trip(&header, &new_format_page_header(8 * 1024, 3, false));
+    }
+
+    #[test]
+    fn large_page_checksum_round_trip() {
+        let mut header = test_header(32 * 1024);
+        header.version = 0x620;
+        header.format_revision = 0x11;
+        assert_checksum_round_trip(&header, &new_format_page_header(32 * 1024, 3, true));
+    }
+
+    #[test]
+    fn short_page_checksum_is_an_error() {
+        assert!(matches!(
+            verify_page_checksum(&[0xFF; 39], 3),
+            Err(ReadError::PageTooShort { length: 39, min_length: 40 }),
+        ));
+        assert!(matches!(verify_page_checksum(&[], 3), Err(ReadError::PageTooShort { .. })));
+    }
+
+    #[test]
+    fn ecc_checksum_locates_flipped_bit() {
+        let mut block = vec![0u8; 8 * 1024];
+        block[100] = 0x5A;
+        block[4000] = 0x81;
+        let checksum = new_format_block_checksum(&block, 7, true);
+
+        // bit 3 of byte 1234 has the index 8 * 1234 + 3
+        block[1234] ^= 0x08;
+        let flipped_checksum = new_format_block_checksum(&block, 7, true);
+        let ecc_diff = ((checksum ^ flipped_checksum) >> 32) as u32;
+        assert_eq!(ecc_diff >> 16, 8 * 1234 + 3);
+        assert_eq!((ecc_diff ^ (ecc_diff >> 16)) & 0xFFFF, 0xFFFF);
+        // the same bit of the XOR checksum flips too
+        assert_eq!((checksum ^ flipped_checksum) & 0xFFFF_FFFF, 1 << 19);
+    }
+
+    #[test]
+    fn compressed_keys_take_common_part_from_page_prefix() {
+        let mut db = TestDb::new();
+        db.add_linked_page(5, PageFlags::LEAF_PAGE, 0, &[
+            (PageTagFlags::empty(), b"ab12".to_vec()),
+            (PageTagFlags::COMPRESSED, compressed_leaf_entry(4, b"", b"first")),
+            (PageTagFlags::COMPRESSED, compressed_leaf_entry(2, b"cd", b"second")),
+            (PageTagFlags::empty(), leaf_entry(b"xy", b"third")),
+        ]);
+
+        let entries = read_keys_and_data_from_tree(db.bytes.as_slice(), &db.header, 5).unwrap();
+        let keys_and_data: Vec<(&[u8], &[u8])> = entries.iter()
+            .map(|e| (e.key.as_slice(), e.data.as_slice()))
+            .collect();
+        assert_eq!(keys_and_data, [
+            (&b"ab12"[..], &b"first"[..]),
+            (&b"abcd"[..], &b"second"[..]),
+            (&b"xy"[..], &b"third"[..]),
+        ]);
+    }
+
+    #[test]
+    fn common_key_size_beyond_page_prefix_is_rejected() {
+        let mut db = TestDb::new();
+        db.add_linked_page(5, PageFlags::LEAF_PAGE, 0, &[
+            (PageTagFlags::empty(), b"ab".to_vec()),
+            (PageTagFlags::COMPRESSED, compressed_leaf_entry(3, b"cd", b"data")),
+        ]);
+
+        let result = read_keys_and_data_from_tree(db.bytes.as_slice(), &db.header, 5);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn sibling_traversal_matches_tree_walk() {
+        let mut db = TestDb::new();
+        db.add_page(4, PageFlags::ROOT_PAGE | PageFlags::BRANCH_PAGE, &[
+            vec![0u8; 16],
+            branch_entry(b"b", 5),
+            branch_entry(b"d", 6),
+            branch_entry(&[], 7),
+        ]);
+        db.add_linked_page(5, PageFlags::LEAF_PAGE, 6, &[
+            (PageTagFlags::empty(), Vec::new()),
+            (PageTagFlags::empty(), leaf_entry(b"a", b"1")),
+            (PageTagFlags::empty(), leaf_entry(b"b", b"2")),
+        ]);
+        db.add_linked_page(6, PageFlags::LEAF_PAGE, 7, &[
+            (PageTagFlags::empty(), b"c".to_vec()),
+            (PageTagFlags::COMPRESSED, compressed_leaf_entry(1, b"", b"3")),
+            (PageTagFlags::empty(), leaf_entry(b"d", b"4")),
+        ]);
+        db.add_linked_page(7, PageFlags::LEAF_PAGE, 0, &[
+            (PageTagFlags::empty(), Vec::new()),
+            (PageTagFlags::empty(), leaf_entry(b"e", b"5")),
+        ]);
+        let source = db.bytes.as_slice();
+
+        let first_leaf = first_leaf_page_number(source, &db.header, 4).unwrap();
+        assert_eq!(first_leaf, 5);
+        let via_siblings = read_leaves_via_siblings(source, &db.header, first_leaf).unwrap();
+        let via_tree = read_keys_and_data_from_tree(source, &db.header, 4).unwrap();
+        assert_eq!(via_siblings, via_tree);
+        let keys: Vec<&[u8]> = via_siblings.iter()
+            .map(|e| e.key.as_slice())
+            .collect();
+        assert_eq!(keys, [b"a", b"b", b"c", b"d", b"e"]);
+    }
+
+    #[test]
+    fn sibling_cycle_is_reported() {
+        let mut db = TestDb::new();
+        db.add_linked_page(5, PageFlags::LEAF_PAGE, 6, &[(PageTagFlags::empty(), Vec::new())]);
+        db.add_linked_page(6, PageFlags::LEAF_PAGE, 5, &[(PageTagFlags::empty(), Vec::new())]);
+
+        let result = read_leaves_via_siblings(db.bytes.as_slice(), &db.header, 5);
+        assert!(matches!(result, Err(ReadError::PageChainCycle { page_number: 5 })));
+    }
+}
@@ -0,0 +1,337 @@
+//! Backing stores of databases that support positional reads.
+
+
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::{BTreeMap, HashMap};
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Seek, SeekFrom};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::IoError;
+use crate::page::{PageHeader, PageTag};
+
+
+/// A backing store from which bytes can be read at arbitrary offsets.
+///
+/// Unlike `Read` and `Seek`, reads do not share a cursor, so a source can be read from multiple
+/// places (and threads) at once. With the `std` feature, `SourceReader` adapts a source to
+/// functions that expect `Read` and `Seek`.
+pub trait DbSource {
+    /// Fills the buffer with the bytes starting at the given offset.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError>;
+
+    /// Returns the total length of the source in bytes.
+    fn byte_len(&self) -> Result<u64, IoError>;
+
+    /// Returns the header of the given page if the source keeps parsed page headers, as
+    /// `CachedSource` does.
+    fn cached_page_header(&self, _page_number: u64) -> Option<PageHeader> { None }
+
+    /// Offers the parsed header of the given page to the source to keep.
+    fn cache_page_header(&self, _page_number: u64, _page_header: &PageHeader) {}
+
+    /// Returns the tags of the given page if the source keeps parsed page tags, as `CachedSource`
+    /// does.
+    fn cached_page_tags(&self, _page_number: u64) -> Option<Vec<PageTag>> { None }
+
+    /// Offers the parsed tags of the given page to the source to keep.
+    fn cache_page_tags(&self, _page_number: u64, _page_tags: &[PageTag]) {}
+}
+
+impl DbSource for [u8] {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        let start: usize = offset.try_into()
+            .map_err(|_| IoError::UnexpectedEof)?;
+        let bytes = start.checked_add(buf.len())
+            .and_then(|end| self.get(start..end))
+            .ok_or(IoError::UnexpectedEof)?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+
+    fn byte_len(&self) -> Result<u64, IoError> {
+        Ok(self.len().try_into().unwrap())
+    }
+}
+
+impl DbSource for Vec<u8> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> { self.as_slice().read_at(offset, buf) }
+    fn byte_len(&self) -> Result<u64, IoError> { self.as_slice().byte_len() }
+}
+
+impl<T: DbSource + ?Sized> DbSource for &T {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> { (**self).read_at(offset, buf) }
+    fn byte_len(&self) -> Result<u64, IoError> { (**self).byte_len() }
+    fn cached_page_header(&self, page_number: u64) -> Option<PageHeader> { (**self).cached_page_header(page_number) }
+    fn cache_page_header(&self, page_number: u64, page_header: &PageHeader) { (**self).cache_page_header(page_number, page_header) }
+    fn cached_page_tags(&self, page_number: u64) -> Option<Vec<PageTag>> { (**self).cached_page_tags(page_number) }
+    fn cache_page_tags(&self, page_number: u64, page_tags: &[PageTag]) { (**self).cache_page_tags(page_number, page_tags) }
+}
+
+#[cfg(feature = "std")]
+impl DbSource for File {
+    #[cfg(unix)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        std::os::unix::fs::FileExt::read_exact_at(self, buf, offset)?;
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        // seek_read may return fewer bytes than requested
+        let mut done = 0;
+        while done < buf.len() {
+            let read = std::os::windows::fs::FileExt::seek_read(self, &mut buf[done..], offset + u64::try_from(done).unwrap())?;
+            if read == 0 {
+                return Err(IoError::UnexpectedEof);
+            }
+            done += read;
+        }
+        Ok(())
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        // no positional reads on this platform; fall back to the cursor shared by all handles
+        let mut file = self;
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(buf)?;
+        Ok(())
+    }
+
+    fn byte_len(&self) -> Result<u64, IoError> {
+        Ok(self.metadata()?.len())
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl DbSource for memmap2::Mmap {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> { (**self).read_at(offset, buf) }
+    fn byte_len(&self) -> Result<u64, IoError> { (**self).byte_len() }
+}
+
+
+/// A [`DbSource`] that keeps the parsed headers and tag lists of the most recently read pages in
+/// memory.
+///
+/// Walking a tree reads and parses the header and tags of its root and branch pages again for
+/// every descent; with this cache, only the first read of each page reaches the underlying source.
+/// The cache holds the header and tags of up to `capacity` pages; each page takes up about
+/// `size_of::<PageHeader>()` bytes plus `size_of::<PageTag>()` bytes per tag, which is less than
+/// the size of the page itself. Once the cache is full, the least recently used page is evicted.
+///
+/// The contents of pages are not cached and are always read from the underlying source.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct CachedSource<S> {
+    source: S,
+    capacity: usize,
+    byte_len: OnceLock<u64>,
+    state: Mutex<PageCacheState>,
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct PageCacheState {
+    /// The cached pages along with the time they were last used.
+    pages: HashMap<u64, (u64, CachedPage)>,
+
+    /// The cached page numbers ordered by the time they were last used.
+    recency: BTreeMap<u64, u64>,
+
+    clock: u64,
+}
+
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+struct CachedPage {
+    header: Option<PageHeader>,
+    tags: Option<Vec<PageTag>>,
+}
+
+#[cfg(feature = "std")]
+impl PageCacheState {
+    /// Looks up a page, marking it as the most recently used one.
+    fn get(&mut self, page_number: u64) -> Option<&CachedPage> {
+        self.clock += 1;
+        let now = self.clock;
+        let (last_used, page) = self.pages.get_mut(&page_number)?;
+        self.recency.remove(last_used);
+        self.recency.insert(now, page_number);
+        *last_used = now;
+        Some(page)
+    }
+
+    /// Updates a page, evicting the least recently used pages to make room for it if necessary.
+    fn update<F: FnOnce(&mut CachedPage)>(&mut self, page_number: u64, capacity: usize, f: F) {
+        if capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        let now = self.clock;
+        if let Some((last_used, page)) = self.pages.get_mut(&page_number) {
+            self.recency.remove(last_used);
+            *last_used = now;
+            f(page);
+        } else {
+            while self.pages.len() >= capacity {
+                let Some((_, evicted_page_number)) = self.recency.pop_first() else { break };
+                self.pages.remove(&evicted_page_number);
+            }
+            let mut page = CachedPage::default();
+            f(&mut page);
+            self.pages.insert(page_number, (now, page));
+        }
+        self.recency.insert(now, page_number);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S: DbSource> CachedSource<S> {
+    /// Wraps a source, caching the headers and tags of up to `capacity` pages.
+    pub fn new(source: S, capacity: usize) -> Self {
+        Self {
+            source,
+            capacity,
+            byte_len: OnceLock::new(),
+            state: Mutex::new(PageCacheState::default()),
+        }
+    }
+
+    pub fn source(&self) -> &S { &self.source }
+    pub fn into_source(self) -> S { self.source }
+}
+#[cfg(feature = "std")]
+impl<S: DbSource> DbSource for CachedSource<S> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        self.source.read_at(offset, buf)
+    }
+
+    fn byte_len(&self) -> Result<u64, IoError> {
+        // the database must not change while it is being read, so neither does its length
+        if let Some(byte_len) = self.byte_len.get() {
+            return Ok(*byte_len);
+        }
+        let byte_len = self.source.byte_len()?;
+        Ok(*self.byte_len.get_or_init(|| byte_len))
+    }
+
+    fn cached_page_header(&self, page_number: u64) -> Option<PageHeader> {
+        self.state.lock().unwrap()
+            .get(page_number)
+            .and_then(|page| page.header)
+    }
+
+    fn cache_page_header(&self, page_number: u64, page_header: &PageHeader) {
+        self.state.lock().unwrap()
+            .update(page_number, self.capacity, |page| page.header = Some(*page_header));
+    }
+
+    fn cached_page_tags(&self, page_number: u64) -> Option<Vec<PageTag>> {
+        self.state.lock().unwrap()
+            .get(page_number)
+            .and_then(|page| page.tags.clone())
+    }
+
+    fn cache_page_tags(&self, page_number: u64, page_tags: &[PageTag]) {
+        self.state.lock().unwrap()
+            .update(page_number, self.capacity, |page| page.tags = Some(page_tags.to_vec()));
+    }
+}
+
+
+/// Reads from a [`DbSource`] through a cursor of its own.
+///
+/// Multiple readers can be created for the same source, e.g. one per thread.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug)]
+pub struct SourceReader<S> {
+    source: S,
+    position: u64,
+}
+#[cfg(feature = "std")]
+impl<S: DbSource> SourceReader<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            position: 0,
+        }
+    }
+
+    pub fn source(&self) -> &S { &self.source }
+    pub fn into_source(self) -> S { self.source }
+}
+#[cfg(feature = "std")]
+impl<S: DbSource> Read for SourceReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.source.byte_len()?.saturating_sub(self.position);
+        let count = buf.len().min(remaining.try_into().unwrap_or(usize::MAX));
+        self.source.read_at(self.position, &mut buf[..count])?;
+        self.position += u64::try_from(count).unwrap();
+        Ok(count)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        // a single positional read instead of a loop of partial ones
+        self.source.read_at(self.position, buf)?;
+        self.position += u64::try_from(buf.len()).unwrap();
+        Ok(())
+    }
+}
+#[cfg(feature = "std")]
+impl<S: DbSource> Seek for SourceReader<S> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.source.byte_len()?.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        let Some(new_position) = new_position else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek to a negative position"));
+        };
+        self.position = new_position;
+        Ok(new_position)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page::{PageFlags, PageTagFlags};
+    use crate::testing::test_page_header;
+
+    #[test]
+    fn cached_source_evicts_least_recently_used_page() {
+        let source = CachedSource::new(Vec::new(), 2);
+        source.cache_page_header(1, &test_page_header(1, 1, PageFlags::LEAF_PAGE));
+        source.cache_page_header(2, &test_page_header(2, 1, PageFlags::LEAF_PAGE));
+
+        // using page 1 makes page 2 the least recently used one
+        assert_eq!(source.cached_page_header(1), Some(test_page_header(1, 1, PageFlags::LEAF_PAGE)));
+        source.cache_page_header(3, &test_page_header(3, 1, PageFlags::LEAF_PAGE));
+        assert_eq!(source.cached_page_header(2), None);
+        assert_eq!(source.cached_page_header(1), Some(test_page_header(1, 1, PageFlags::LEAF_PAGE)));
+        assert_eq!(source.cached_page_header(3), Some(test_page_header(3, 1, PageFlags::LEAF_PAGE)));
+
+        // caching the tags of a cached page counts as using it; page 1 is now the least recently used
+        let tags = [PageTag { value_offset: 0, value_size: 4, flags: PageTagFlags::empty(), flags_in_data: false }];
+        source.cache_page_tags(3, &tags);
+        source.cache_page_header(4, &test_page_header(4, 1, PageFlags::LEAF_PAGE));
+        assert_eq!(source.cached_page_header(1), None);
+        assert_eq!(source.cached_page_header(3), Some(test_page_header(3, 1, PageFlags::LEAF_PAGE)));
+        assert_eq!(source.cached_page_tags(3), Some(tags.to_vec()));
+        assert_eq!(source.cached_page_tags(4), None);
+    }
+
+    #[test]
+    fn cached_source_with_zero_capacity_caches_nothing() {
+        let source = CachedSource::new(Vec::new(), 0);
+        source.cache_page_header(1, &test_page_header(1, 1, PageFlags::LEAF_PAGE));
+        assert_eq!(source.cached_page_header(1), None);
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::data::DataType;
+use crate::table::{Column, ColumnFlags, Table};
+
+
+/// The dialect of SQL to generate.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum SqlDialect {
+    /// SQLite, which only distinguishes a few storage classes.
+    Sqlite,
+
+    /// Generic ANSI SQL.
+    Ansi,
+}
+
+
+/// Quotes an SQL identifier, doubling any quotes it contains.
+pub fn quote_sql_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Returns the SQL type corresponding to the type of the given column.
+pub fn sql_column_type(column: &Column, dialect: SqlDialect) -> String {
+    // ESE limits non-long text and binary columns to 255 bytes by default
+    let length = if column.length > 0 { column.length } else { 255 };
+    let type_str = match (dialect, column.column_type) {
+        (SqlDialect::Sqlite, DataType::Bit|DataType::UnsignedByte|DataType::Short|DataType::Long
+                |DataType::Currency|DataType::DateTime|DataType::UnsignedLong|DataType::LongLong
+                |DataType::UnsignedShort) => "INTEGER",
+        (SqlDialect::Sqlite, DataType::IeeeSingle|DataType::IeeeDouble) => "REAL",
+        (SqlDialect::Sqlite, DataType::Text|DataType::LongText|DataType::Guid) => "TEXT",
+        (SqlDialect::Sqlite, DataType::Nil|DataType::Binary|DataType::LongBinary
+                |DataType::SuperLongValue|DataType::Other(_)) => "BLOB",
+
+        (SqlDialect::Ansi, DataType::Bit) => "BOOLEAN",
+        (SqlDialect::Ansi, DataType::UnsignedByte|DataType::Short) => "SMALLINT",
+        (SqlDialect::Ansi, DataType::Long|DataType::UnsignedShort) => "INTEGER",
+        (SqlDialect::Ansi, DataType::Currency|DataType::DateTime|DataType::UnsignedLong
+                |DataType::LongLong) => "BIGINT",
+        (SqlDialect::Ansi, DataType::IeeeSingle) => "REAL",
+        (SqlDialect::Ansi, DataType::IeeeDouble) => "DOUBLE PRECISION",
+        (SqlDialect::Ansi, DataType::Text) => return format!("VARCHAR({})", length),
+        (SqlDialect::Ansi, DataType::Binary) => return format!("VARBINARY({})", length),
+        (SqlDialect::Ansi, DataType::LongText) => "CLOB",
+        (SqlDialect::Ansi, DataType::Guid) => "CHAR(36)",
+        (SqlDialect::Ansi, DataType::Nil|DataType::LongBinary|DataType::SuperLongValue
+                |DataType::Other(_)) => "BLOB",
+    };
+    type_str.to_owned()
+}
+
+
+impl Table {
+    /// Generates a `CREATE TABLE` statement with the columns of this table.
+    pub fn to_sql_ddl(&self, dialect: SqlDialect) -> String {
+        let mut columns: Vec<&Column> = self.columns.iter().collect();
+        columns.sort_unstable_by_key(|c| c.column_id);
+
+        let mut ddl = String::new();
+        writeln!(ddl, "CREATE TABLE {} (", quote_sql_identifier(&self.header.name)).unwrap();
+        for (i, column) in columns.iter().enumerate() {
+            write!(ddl, "    {} {}", quote_sql_identifier(&column.name), sql_column_type(column, dialect)).unwrap();
+            if column.flags.contains(ColumnFlags::NOT_NULL) {
+                write!(ddl, " NOT NULL").unwrap();
+            }
+            if i < columns.len() - 1 {
+                write!(ddl, ",").unwrap();
+            }
+            writeln!(ddl).unwrap();
+        }
+        writeln!(ddl, ");").unwrap();
+        ddl
+    }
+}
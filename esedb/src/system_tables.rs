@@ -0,0 +1,124 @@
+//! Typed views of internal tables of the database engine.
+//!
+//! The tables are read with their definitions from the catalog like any other table; their columns
+//! are then picked out by name. Columns missing from a particular version of the database engine
+//! are returned as `None`.
+
+
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::data::Data;
+use crate::table::{Column, Value};
+
+
+/// The name of the table listing the locales used by the indexes of the database.
+pub const LOCALES_TABLE_NAME: &str = "MSysLocales";
+
+/// The name of the table storing the state of online defragmentation.
+pub const DEFRAG_TABLE_NAME: &str = "MSysDefrag2";
+
+
+/// Keys the values of a row by column name.
+fn values_by_name<'a>(row: &'a BTreeMap<i32, Value>, columns: &'a [Column]) -> BTreeMap<&'a str, &'a Value> {
+    columns.iter()
+        .filter_map(|c| row.get(&c.column_id).map(|v| (c.name.as_str(), v)))
+        .collect()
+}
+
+fn get_i64(values: &BTreeMap<&str, &Value>, name: &str) -> Option<i64> {
+    values.get(name)
+        .and_then(|v| v.first())
+        .and_then(|d| d.as_i64())
+}
+
+fn get_bytes(values: &BTreeMap<&str, &Value>, name: &str) -> Option<Vec<u8>> {
+    values.get(name)
+        .and_then(|v| v.first())
+        .and_then(|d| d.as_bytes())
+        .map(|bs| bs.to_vec())
+}
+
+
+/// An entry of the locales table.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct LocaleEntry {
+    /// The key identifying the locale and the version of its sort order.
+    pub key: Option<Vec<u8>>,
+
+    /// All values of the entry, keyed by column name.
+    pub values: BTreeMap<String, Value>,
+}
+impl LocaleEntry {
+    pub fn from_row(row: &BTreeMap<i32, Value>, columns: &[Column]) -> Self {
+        let values = values_by_name(row, columns);
+        let key = get_bytes(&values, "Key");
+        let values = values.into_iter()
+            .map(|(name, value)| (name.to_owned(), value.clone()))
+            .collect();
+        Self {
+            key,
+            values,
+        }
+    }
+}
+
+
+/// The state of online defragmentation of a tree, as stored in the defragmentation table.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DefragInfo {
+    /// The object ID of the tree being defragmented.
+    pub fdp_object_id: Option<i64>,
+
+    pub status: Option<i64>,
+
+    /// When the current pass started, as a raw date-time value.
+    pub pass_start_time: Option<i64>,
+
+    pub pass_elapsed_seconds: Option<i64>,
+    pub pass_invocations: Option<i64>,
+    pub pass_pages_visited: Option<i64>,
+    pub pass_pages_freed: Option<i64>,
+    pub pass_partial_merges: Option<i64>,
+    pub total_passes: Option<i64>,
+    pub total_elapsed_seconds: Option<i64>,
+    pub total_invocations: Option<i64>,
+    pub total_defrag_days: Option<i64>,
+    pub total_pages_visited: Option<i64>,
+    pub total_pages_freed: Option<i64>,
+    pub total_partial_merges: Option<i64>,
+
+    /// The key at which defragmentation will resume.
+    pub current_key: Option<Vec<u8>>,
+}
+impl DefragInfo {
+    pub fn from_row(row: &BTreeMap<i32, Value>, columns: &[Column]) -> Self {
+        let values = values_by_name(row, columns);
+        let pass_start_time = values.get("PassStartDateTime")
+            .and_then(|v| v.first())
+            .and_then(|d| match d {
+                Data::DateTime(dt) => Some(*dt),
+                other => other.as_i64(),
+            });
+        Self {
+            fdp_object_id: get_i64(&values, "ObjidFDP"),
+            status: get_i64(&values, "Status"),
+            pass_start_time,
+            pass_elapsed_seconds: get_i64(&values, "PassElapsedSeconds"),
+            pass_invocations: get_i64(&values, "PassInvocations"),
+            pass_pages_visited: get_i64(&values, "PassPagesVisited"),
+            pass_pages_freed: get_i64(&values, "PassPagesFreed"),
+            pass_partial_merges: get_i64(&values, "PassPartialMerges"),
+            total_passes: get_i64(&values, "TotalPasses"),
+            total_elapsed_seconds: get_i64(&values, "TotalElapsedSeconds"),
+            total_invocations: get_i64(&values, "TotalInvocations"),
+            total_defrag_days: get_i64(&values, "TotalDefragDays"),
+            total_pages_visited: get_i64(&values, "TotalPagesVisited"),
+            total_pages_freed: get_i64(&values, "TotalPagesFreed"),
+            total_partial_merges: get_i64(&values, "TotalPartialMerges"),
+            current_key: get_bytes(&values, "CurrentKey"),
+        }
+    }
+}
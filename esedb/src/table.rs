@@ -1,19 +1,35 @@
-use std::borrow::Cow;
-use std::collections::BTreeMap;
-use std::io::{Cursor, Read, Seek};
+use alloc::borrow::{Cow, ToOwned};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::ControlFlow;
+#[cfg(feature = "std")]
 use std::sync::LazyLock;
 
 use bitflags::bitflags;
-use encoding_rs::DecoderResult;
+#[cfg(feature = "encodings")]
+use encoding_rs::{DecoderResult, EncoderResult};
 use from_to_repr::from_to_other;
-use tracing::{instrument, trace};
+use tracing::{debug, instrument, trace};
 use uuid::Uuid;
 
-use crate::byte_io::{ByteRead, LittleEndianRead};
+use crate::byte_io::{ByteRead, SliceRead};
+use crate::compression::{CompressionScheme, decompress};
 use crate::data::{Bit, Data, DataType};
-use crate::error::ReadError;
+use crate::decrypt::DecryptProvider;
+use crate::error::{ReadError, WriteError};
 use crate::header::Header;
-use crate::page::{MAX_SIZE_SMALL_PAGE, read_data_from_tree};
+use crate::page::{
+    MAX_SIZE_SMALL_PAGE, PageEntry, PageFlags, PageScan, PageTagFlags, read_keyed_data_from_tree,
+    read_keyed_data_from_tree_starting_at, read_keyed_data_from_tree_verifying_owner,
+    read_keyed_data_from_tree_with_source, read_page_entry_with_buffer,
+    read_page_tags, read_root_page, RootPageHeader, TreeLeaf,
+};
+use crate::source::DbSource;
 
 
 // here we have a bit of a bootstrapping issue
@@ -118,6 +134,18 @@ pub enum ObjectType {
     Callback = 0x0005,
     Other(i16),
 }
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Table => write!(f, "table"),
+            Self::Column => write!(f, "column"),
+            Self::Index => write!(f, "index"),
+            Self::LongValue => write!(f, "long value"),
+            Self::Callback => write!(f, "callback"),
+            Self::Other(code) => write!(f, "unknown object type {}", code),
+        }
+    }
+}
 
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -130,6 +158,9 @@ pub struct TableHeader {
     pub page_count: i32,
     pub root_flag: Option<Bit>,
     pub name: String,
+
+    /// The name of the template table from which this table is derived, if any.
+    pub template_name: Option<String>,
 }
 impl TableHeader {
     #[instrument]
@@ -149,6 +180,13 @@ impl TableHeader {
         let page_count = *get_value!(@required, name_to_column, values, "PagesOrLocale", Long);
         let root_flag = get_value!(@optional_deref, name_to_column, values, "RootFlag", Bit);
         let name = get_value!(@required, name_to_column, values, "Name", Text);
+        let template_name = name_to_column.get("TemplateTable")
+            .and_then(|column_def| values.get(&column_def.column_id))
+            .and_then(|value| value.first())
+            .and_then(|data| match data {
+                Data::Text(s)|Data::LongText(s) => Some(s.clone()),
+                _ => None,
+            });
 
         Ok(TableHeader {
             table_object_id,
@@ -159,11 +197,12 @@ impl TableHeader {
             page_count,
             root_flag,
             name: name.clone(),
+            template_name,
         })
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Column {
     pub table_object_id: i32,
     pub column_id: i32,
@@ -174,6 +213,9 @@ pub struct Column {
     pub root_flag: Option<Bit>,
     pub record_offset: Option<i16>,
     pub name: String,
+
+    /// The value the database engine stores in this column if a new row does not set it.
+    pub default_value: Option<Data>,
 }
 impl Column {
     #[instrument]
@@ -195,8 +237,12 @@ impl Column {
         let root_flag = get_value!(@optional_deref, name_to_column, values, "RootFlag", Bit);
         let record_offset = get_value!(@optional_deref, name_to_column, values, "RecordOffset", Short);
         let name = get_value!(@required, name_to_column, values, "Name", Text);
+        let default_value_bytes = name_to_column.get("DefaultValue")
+            .and_then(|column_def| values.get(&column_def.column_id))
+            .and_then(|value| value.first())
+            .and_then(|data| data.as_bytes());
 
-        Ok(Column {
+        let mut column = Column {
             table_object_id,
             column_id,
             column_type,
@@ -206,7 +252,17 @@ impl Column {
             root_flag,
             record_offset,
             name: name.clone(),
-        })
+            default_value: None,
+        };
+        if let Some(bytes) = default_value_bytes {
+            // the default value is stored in the same form as a value of the column itself;
+            // a default that cannot be decoded should not make the whole catalog unreadable
+            match decode_value_bytes(bytes, &column, DEFAULT_CODEPAGE) {
+                Ok(default_value) => column.default_value = Some(default_value),
+                Err(e) => debug!(column_id, error = %e, "failed to decode default value; ignoring it"),
+            }
+        }
+        Ok(column)
     }
 }
 
@@ -221,6 +277,8 @@ pub struct Index {
     pub root_flag: Option<Bit>,
     pub record_offset: Option<i16>,
     pub name: String,
+    pub key_field_ids: Option<Vec<u8>>,
+    pub key_segments: Vec<IndexSegment>,
 }
 impl Index {
     #[instrument]
@@ -241,6 +299,14 @@ impl Index {
         let root_flag = get_value!(@optional_deref, name_to_column, values, "RootFlag", Bit);
         let record_offset = get_value!(@optional_deref, name_to_column, values, "RecordOffset", Short);
         let name = get_value!(@required, name_to_column, values, "Name", Text);
+        // the key column list may be split across multiple values of the tagged column
+        let key_field_ids = name_to_column.get("KeyFldIDs")
+            .and_then(|column_def| values.get(&column_def.column_id))
+            .map(|value| value.to_data_vec().into_iter()
+                .filter_map(|data| data.as_bytes())
+                .flatten()
+                .copied()
+                .collect());
 
         Ok(Index {
             table_object_id,
@@ -252,8 +318,65 @@ impl Index {
             root_flag,
             record_offset,
             name: name.clone(),
+            key_field_ids,
+            // filled in by collect_tables once the columns of the table are known
+            key_segments: Vec::new(),
         })
     }
+
+    /// The number of the root page of the index's tree.
+    pub fn fdp_page_number(&self) -> Result<u64, ReadError> {
+        ReadError::catalog_value("index FDP page number", self.fdp_page_number)
+            .map_err(|e| e.in_table(self.table_object_id))
+    }
+}
+
+/// A column that is part of the key of an index.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct IndexSegment {
+    pub column_id: i32,
+    pub descending: bool,
+}
+impl IndexSegment {
+    /// Returns the definition of the column referenced by this segment.
+    pub fn column<'a>(&self, columns: &'a [Column]) -> Option<&'a Column> {
+        columns.iter()
+            .find(|c| c.column_id == self.column_id)
+    }
+
+    /// Decodes the key segments stored in the `KeyFldIDs` column of an index.
+    ///
+    /// Newer databases store 4-byte segments (a flags byte, a reserved byte and a 16-bit column
+    /// ID); older ones store signed 16-bit column IDs which are negative for descending segments.
+    /// Since the data does not say which format it uses, the newer format is assumed if every
+    /// segment decoded that way refers to one of the given columns.
+    pub fn parse_key_field_ids(bytes: &[u8], columns: &[Column]) -> Vec<IndexSegment> {
+        const DESCENDING_FLAG: u8 = 0x01;
+
+        if bytes.len().is_multiple_of(4) {
+            let segments: Vec<IndexSegment> = bytes.chunks_exact(4)
+                .map(|chunk| IndexSegment {
+                    column_id: u16::from_le_bytes(chunk[2..4].try_into().unwrap()).into(),
+                    descending: chunk[0] & DESCENDING_FLAG != 0,
+                })
+                .collect();
+            let all_known = segments.iter()
+                .all(|seg| seg.column(columns).is_some());
+            if all_known {
+                return segments;
+            }
+        }
+
+        bytes.chunks_exact(2)
+            .map(|chunk| {
+                let value = i16::from_le_bytes(chunk.try_into().unwrap());
+                IndexSegment {
+                    column_id: i32::from(value).abs(),
+                    descending: value < 0,
+                }
+            })
+            .collect()
+    }
 }
 
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -296,17 +419,93 @@ impl LongValueInfo {
     }
 }
 
+/// A callback registered on a table, which the database engine calls into user-defined code for.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Callback {
+    pub table_object_id: i32,
+    pub callback_id: i32,
+
+    /// The events on which the callback is invoked (a combination of `JET_cbtyp` values).
+    pub callback_type: i32,
+
+    pub flags: ObjectFlags,
+    pub name: String,
+
+    /// The function to call, usually of the form `library!function`.
+    pub function: Option<String>,
+}
+impl Callback {
+    #[instrument]
+    pub fn try_from_metadata(column_defs: &[Column], values: &BTreeMap<i32, Value>) -> Result<Self, ReadError> {
+        let name_to_column = get_name_to_column(column_defs);
+
+        let type_value_i16 = *get_value!(@required, name_to_column, values, "Type", Short);
+        let type_value = ObjectType::from_base_type(type_value_i16);
+        ReadError::ensure_object_type(ObjectType::Callback, type_value)?;
+
+        let table_object_id = *get_value!(@required, name_to_column, values, "ObjidTable", Long);
+        let callback_id = *get_value!(@required, name_to_column, values, "Id", Long);
+        let callback_type = *get_value!(@required, name_to_column, values, "ColtypOrPgnoFDP", Long);
+        let flags_i32 = *get_value!(@required, name_to_column, values, "Flags", Long);
+        let flags = ObjectFlags::from_bits_retain(flags_i32);
+        let name = get_value!(@required, name_to_column, values, "Name", Text);
+        let function = name_to_column.get("Callback")
+            .and_then(|column_def| values.get(&column_def.column_id))
+            .and_then(|value| value.first())
+            .and_then(|data| match data {
+                Data::Text(s)|Data::LongText(s) => Some(s.clone()),
+                _ => None,
+            });
+
+        Ok(Callback {
+            table_object_id,
+            callback_id,
+            callback_type,
+            flags,
+            name: name.clone(),
+            function,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub struct Table {
     pub header: TableHeader,
     pub columns: Vec<Column>,
     pub indexes: Vec<Index>,
     pub long_value: Option<LongValueInfo>,
+    pub callbacks: Vec<Callback>,
 }
 impl Table {
-    pub fn long_value_page_number(&self) -> Option<u64> {
+    /// The number of the root page of the table's tree.
+    pub fn fdp_page_number(&self) -> Result<u64, ReadError> {
+        ReadError::catalog_value("table FDP page number", self.header.fdp_page_number)
+            .map_err(|e| e.in_table(self.header.table_object_id))
+    }
+
+    /// The number of the root page of the table's long value tree, if it has one.
+    pub fn long_value_page_number(&self) -> Result<Option<u64>, ReadError> {
         self.long_value.as_ref()
-            .map(|lv| lv.fdp_page_number.try_into().unwrap())
+            .map(|lv| ReadError::catalog_value("long value FDP page number", lv.fdp_page_number))
+            .transpose()
+            .map_err(|e| e.in_table(self.header.table_object_id))
+    }
+
+    /// Whether this is one of the internal tables of the database engine, as opposed to a table
+    /// created by an application.
+    ///
+    /// Template tables and tables derived from them are application tables even though their
+    /// definitions are fixed.
+    pub fn is_system(&self) -> bool {
+        self.header.flags.contains(ObjectFlags::SYSTEM)
+            || self.header.name.starts_with("MSys")
+    }
+
+    /// Reads the header of the table's root page, which describes how the table's space is
+    /// allocated (initial page count, single or multiple extents, location of the space tree).
+    pub fn read_root_page_header<S: DbSource + ?Sized>(&self, source: &S, header: &Header) -> Result<RootPageHeader, ReadError> {
+        read_root_page(source, header, self.fdp_page_number()?)
+            .map_err(|e| e.in_table(self.header.table_object_id))
     }
 }
 
@@ -344,19 +543,125 @@ impl TagFlags {
     }
 }
 
+#[cfg(not(feature = "smallvec"))]
+type MultiValuesStorage = Vec<Data>;
+#[cfg(not(feature = "smallvec"))]
+type MultiValuesStorageIntoIter = alloc::vec::IntoIter<Data>;
+
+#[cfg(feature = "smallvec")]
+type MultiValuesStorage = smallvec::SmallVec<[Data; 2]>;
+#[cfg(feature = "smallvec")]
+type MultiValuesStorageIntoIter = smallvec::IntoIter<[Data; 2]>;
+
+/// The data of a multi-valued column.
+///
+/// With the `smallvec` feature, up to two pieces of data (the most common case) are stored without
+/// an additional allocation. The public interface is the same with and without the feature.
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct MultiValues(MultiValuesStorage);
+impl MultiValues {
+    pub fn new() -> Self {
+        Self(MultiValuesStorage::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(MultiValuesStorage::with_capacity(capacity))
+    }
+
+    pub fn push(&mut self, data: Data) {
+        self.0.push(data);
+    }
+
+    pub fn len(&self) -> usize { self.0.len() }
+    pub fn is_empty(&self) -> bool { self.0.is_empty() }
+    pub fn first(&self) -> Option<&Data> { self.0.first() }
+    pub fn get(&self, index: usize) -> Option<&Data> { self.0.get(index) }
+    pub fn iter(&self) -> core::slice::Iter<'_, Data> { self.0.iter() }
+    pub fn as_slice(&self) -> &[Data] { &self.0 }
+
+    pub fn into_vec(self) -> Vec<Data> {
+        #[cfg(not(feature = "smallvec"))]
+        { self.0 }
+        #[cfg(feature = "smallvec")]
+        { self.0.into_vec() }
+    }
+}
+impl From<Vec<Data>> for MultiValues {
+    fn from(value: Vec<Data>) -> Self {
+        #[cfg(not(feature = "smallvec"))]
+        { Self(value) }
+        #[cfg(feature = "smallvec")]
+        { Self(smallvec::SmallVec::from_vec(value)) }
+    }
+}
+impl From<MultiValues> for Vec<Data> {
+    fn from(value: MultiValues) -> Self {
+        value.into_vec()
+    }
+}
+impl FromIterator<Data> for MultiValues {
+    fn from_iter<T: IntoIterator<Item = Data>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+impl<'a> IntoIterator for &'a MultiValues {
+    type Item = &'a Data;
+    type IntoIter = core::slice::Iter<'a, Data>;
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+impl IntoIterator for MultiValues {
+    type Item = Data;
+    type IntoIter = MultiValuesIntoIter;
+    fn into_iter(self) -> Self::IntoIter { MultiValuesIntoIter(self.0.into_iter()) }
+}
+
+/// An iterator over the data of a [`MultiValues`], taking ownership of it.
+#[derive(Clone, Debug)]
+pub struct MultiValuesIntoIter(MultiValuesStorageIntoIter);
+impl Iterator for MultiValuesIntoIter {
+    type Item = Data;
+    fn next(&mut self) -> Option<Self::Item> { self.0.next() }
+    fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+impl ExactSizeIterator for MultiValuesIntoIter {}
+
 #[derive(Clone, Debug, PartialEq, PartialOrd)]
 pub enum Value {
     Simple(Data),
     Complex {
         data: Data,
         flags: TagFlags,
+
+        /// The scheme with which the value was compressed, if it was stored compressed.
+        compression: Option<CompressionScheme>,
     },
     Multiple {
-        values: Vec<Data>,
+        values: MultiValues,
         flags: TagFlags,
+
+        /// The scheme with which the first compressed value was compressed, if any value was stored
+        /// compressed.
+        compression: Option<CompressionScheme>,
     },
 }
 impl Value {
+    /// Returns a value of the same shape with each piece of data transformed by the given function.
+    pub fn map_data<F: FnMut(&Data) -> Data>(&self, mut f: F) -> Value {
+        match self {
+            Value::Simple(data) => Value::Simple(f(data)),
+            Value::Complex { data, flags, compression } => Value::Complex {
+                data: f(data),
+                flags: *flags,
+                compression: *compression,
+            },
+            Value::Multiple { values, flags, compression } => Value::Multiple {
+                values: values.iter().map(f).collect(),
+                flags: *flags,
+                compression: *compression,
+            },
+        }
+    }
+
     pub fn to_data_vec(&self) -> Vec<&Data> {
         match self {
             Value::Simple(data) => vec![data],
@@ -364,162 +669,517 @@ impl Value {
             Value::Multiple { values, ..} => values.iter().collect(),
         }
     }
+
+    /// Returns the data if this value consists of exactly one piece of data.
+    pub fn single(&self) -> Option<&Data> {
+        match self {
+            Value::Simple(data) => Some(data),
+            Value::Complex { data, .. } => Some(data),
+            Value::Multiple { values, .. } => if values.len() == 1 { values.first() } else { None },
+        }
+    }
+
+    /// Returns the first piece of data of this value.
+    pub fn first(&self) -> Option<&Data> {
+        match self {
+            Value::Simple(data) => Some(data),
+            Value::Complex { data, .. } => Some(data),
+            Value::Multiple { values, .. } => values.first(),
+        }
+    }
+
+    /// Returns the flags of a value stored in a tagged column.
+    pub fn flags(&self) -> Option<TagFlags> {
+        match self {
+            Value::Simple(_) => None,
+            Value::Complex { flags, .. } => Some(*flags),
+            Value::Multiple { flags, .. } => Some(*flags),
+        }
+    }
+
+    /// Returns the compression scheme of a value stored compressed in a tagged column.
+    pub fn compression(&self) -> Option<CompressionScheme> {
+        match self {
+            Value::Simple(_) => None,
+            Value::Complex { compression, .. } => *compression,
+            Value::Multiple { compression, .. } => *compression,
+        }
+    }
+
+    /// Returns all pieces of data of this value as booleans, or `None` if any of them is not a bit.
+    pub fn as_bools(&self) -> Option<Vec<bool>> {
+        self.to_data_vec()
+            .into_iter()
+            .map(|d| d.as_bool())
+            .collect()
+    }
+}
+impl fmt::Display for Value {
+    /// Formats the value's data; multiple values are separated by commas.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, data) in self.to_data_vec().into_iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", data)?;
+        }
+        Ok(())
+    }
 }
 
 
-pub static METADATA_COLUMN_DEFS: LazyLock<[Column; 10]> = LazyLock::new(|| [
-    Column {
-        table_object_id: 2,
-        column_id: 1,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "ObjidTable".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 2,
-        column_type: DataType::Short,
-        length: 2,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "Type".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 3,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "Id".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 4,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "ColtypOrPgnoFDP".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 5,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "SpaceUsage".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 6,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "Flags".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 7,
-        column_type: DataType::Long,
-        length: 4,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "PagesOrLocale".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 8,
-        column_type: DataType::Bit,
-        length: 1,
-        flags: ColumnFlags::empty(),
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "RootFlag".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 9,
-        column_type: DataType::Short,
-        length: 2,
-        flags: ColumnFlags::empty(),
-        codepage: 1252,
-        root_flag: None,
-        record_offset: Some(4),
-        name: "RecordOffset".to_owned(),
-    },
-    Column {
-        table_object_id: 2,
-        column_id: 128,
-        column_type: DataType::Text,
-        length: 255,
-        flags: ColumnFlags::FIXED,
-        codepage: 1252,
-        root_flag: None,
-        record_offset: None,
-        name: "Name".to_owned(),
-    },
-]);
+/// The definitions of the columns of the catalog, with which the catalog can be read before the
+/// definitions stored in it are known.
+#[cfg(feature = "std")]
+pub static METADATA_COLUMN_DEFS: LazyLock<[Column; 10]> = LazyLock::new(metadata_column_defs);
+
+/// Returns the definitions of the columns of the catalog, like `METADATA_COLUMN_DEFS` does with the
+/// `std` feature.
+pub fn metadata_column_defs() -> [Column; 10] {
+    [
+        Column {
+            table_object_id: 2,
+            column_id: 1,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(4),
+            name: "ObjidTable".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 2,
+            column_type: DataType::Short,
+            length: 2,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(8),
+            name: "Type".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 3,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(10),
+            name: "Id".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 4,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(14),
+            name: "ColtypOrPgnoFDP".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 5,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(18),
+            name: "SpaceUsage".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 6,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(22),
+            name: "Flags".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 7,
+            column_type: DataType::Long,
+            length: 4,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(26),
+            name: "PagesOrLocale".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 8,
+            column_type: DataType::Bit,
+            length: 1,
+            flags: ColumnFlags::empty(),
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(30),
+            name: "RootFlag".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 9,
+            column_type: DataType::Short,
+            length: 2,
+            flags: ColumnFlags::empty(),
+            codepage: 1252,
+            root_flag: None,
+            record_offset: Some(31),
+            name: "RecordOffset".to_owned(),
+            default_value: None,
+        },
+        Column {
+            table_object_id: 2,
+            column_id: 128,
+            column_type: DataType::Text,
+            length: 255,
+            flags: ColumnFlags::FIXED,
+            codepage: 1252,
+            root_flag: None,
+            record_offset: None,
+            name: "Name".to_owned(),
+            default_value: None,
+        },
+    ]
+}
+
+
+/// Reads a separated long value from the long value tree and reassembles its chunks.
+///
+/// `reference` is the reference to the long value as stored in the record (a little-endian long
+/// value ID).
+#[instrument(skip(source, header))]
+pub fn read_long_value<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    long_value_page_number: u64,
+    reference: &[u8],
+) -> Result<Vec<u8>, ReadError> {
+    read_long_value_with_max(source, header, long_value_page_number, reference, DEFAULT_MAX_VALUE_LEN)
+}
+
+/// Reads a separated long value like [`read_long_value`], failing with
+/// [`ReadError::ValueTooLarge`] if it is longer than `max_value_len` bytes.
+#[instrument(skip(source, header))]
+pub fn read_long_value_with_max<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    long_value_page_number: u64,
+    reference: &[u8],
+    max_value_len: usize,
+) -> Result<Vec<u8>, ReadError> {
+    let chunks = read_long_value_chunks(source, header, long_value_page_number, reference, max_value_len)?;
+    Ok(chunks.reassemble())
+}
+
+
+/// The chunks of a separated long value as stored in the long value tree.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LongValueChunks {
+    /// The total size announced by the root of the long value, if it was found.
+    pub total_size: Option<u32>,
+
+    /// The byte offsets and data of the chunks, sorted by offset.
+    pub chunks: Vec<(u32, Vec<u8>)>,
+}
+impl LongValueChunks {
+    /// Concatenates the chunks, truncating the result to the total size if it is known.
+    pub fn reassemble(self) -> Vec<u8> {
+        let chunks_size = self.chunks.iter()
+            .map(|(_offset, chunk)| chunk.len())
+            .sum();
+        let mut value = Vec::with_capacity(chunks_size);
+        for (_offset, chunk) in self.chunks {
+            value.extend_from_slice(&chunk);
+        }
+        if let Some(total_size) = self.total_size {
+            let total_size_usize: usize = total_size.try_into().unwrap();
+            value.truncate(total_size_usize);
+        }
+        value
+    }
+}
+
+/// Reads the chunks of a separated long value from the long value tree without reassembling them.
+///
+/// `reference` is the reference to the long value as stored in the record (a little-endian long
+/// value ID). Fails with [`ReadError::ValueTooLarge`] if the value is longer than `max_value_len`
+/// bytes.
+#[instrument(skip(source, header))]
+pub fn read_long_value_chunks<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    long_value_page_number: u64,
+    reference: &[u8],
+    max_value_len: usize,
+) -> Result<LongValueChunks, ReadError> {
+    // in the long value tree, the ID is stored big-endian so that it sorts correctly
+    // the key of the long value root is the ID itself
+    // the key of each chunk is the ID followed by the chunk's byte offset (big-endian u32)
+    let id_key: Vec<u8> = reference.iter().rev().copied().collect();
+
+    let mut total_size = None;
+    let mut chunks_size: usize = 0;
+    let mut offsets_chunks: Vec<(u32, Vec<u8>)> = Vec::new();
+    // skip the parts of the tree holding the long values before this one
+    read_keyed_data_from_tree_starting_at(source, header, long_value_page_number, &id_key, &mut |_source, leaf| {
+        let TreeLeaf { key, data, .. } = leaf;
+        if !key.starts_with(&id_key) {
+            if key.as_slice() > id_key.as_slice() {
+                // we are past this long value
+                return Ok(ControlFlow::Break(()));
+            }
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let key_rest = &key[id_key.len()..];
+        if key_rest.is_empty() {
+            // long value root: reference count (u32) and total size (u32)
+            if data.len() >= 8 {
+                let size = u32::from_le_bytes(data[4..8].try_into().unwrap());
+                ReadError::ensure_value_len(size.try_into().unwrap(), max_value_len)?;
+                total_size = Some(size);
+            }
+        } else if key_rest.len() == 4 {
+            // don't trust the total size; a damaged tree may contain more chunks than it announces
+            chunks_size = chunks_size.saturating_add(data.len());
+            ReadError::ensure_value_len(chunks_size, max_value_len)?;
+            let offset = u32::from_be_bytes(key_rest.try_into().unwrap());
+            offsets_chunks.push((offset, data));
+        }
+        Ok(ControlFlow::Continue(()))
+    })?;
+    trace!(?total_size, chunk_count = offsets_chunks.len());
+
+    offsets_chunks.sort_unstable_by_key(|(offset, _chunk)| *offset);
+    Ok(LongValueChunks {
+        total_size,
+        chunks: offsets_chunks,
+    })
+}
+
+
+/// The columns of a table, partitioned by the way their values are stored in a row.
+///
+/// Building this once per table instead of once per row saves sorting and partitioning the
+/// columns for every row being decoded. According to the `decode_row` benchmark, this makes
+/// decoding the rows of a table with 1520 columns 25 times as fast.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct DecodedColumns<'a> {
+    /// Fixed-size columns (IDs 1 to 127), ordered by ID.
+    pub fixed: Vec<&'a Column>,
 
+    /// Variable-size columns (IDs 128 to 255), keyed by ID.
+    pub variable: BTreeMap<i32, &'a Column>,
 
-fn reference_bytes_to_value_number(bytes: &[u8]) -> usize {
-    let mut page_number = 0;
-    // little-endian encoding, so do Horner scheme in reverse
-    for b in bytes.iter().rev() {
-        page_number *= 256;
-        page_number += usize::from(*b);
+    /// Tagged columns (IDs 256 and above), keyed by ID.
+    pub tagged: BTreeMap<i32, &'a Column>,
+}
+impl<'a> DecodedColumns<'a> {
+    pub fn new(columns: &'a [Column]) -> Self {
+        let mut sorted_columns: Vec<&Column> = columns.iter().collect();
+        sorted_columns.sort_unstable_by_key(|c| c.column_id);
+
+        let fixed: Vec<&Column> = sorted_columns.iter()
+            .take_while(|c| c.column_id < 128)
+            .copied()
+            .collect();
+        let variable: BTreeMap<i32, &Column> = sorted_columns.iter()
+            .skip_while(|c| c.column_id < 128)
+            .take_while(|c| c.column_id < 256)
+            .map(|c| (c.column_id, *c))
+            .collect();
+        let tagged: BTreeMap<i32, &Column> = sorted_columns.iter()
+            .skip_while(|c| c.column_id < 256)
+            .map(|c| (c.column_id, *c))
+            .collect();
+
+        Self {
+            fixed,
+            variable,
+            tagged,
+        }
+    }
+}
+
+
+/// The default maximum length of a single value: 256 MiB.
+pub const DEFAULT_MAX_VALUE_LEN: usize = 256 * 1024 * 1024;
+
+/// The codepage assumed for text columns without one: Windows-1252.
+pub const DEFAULT_CODEPAGE: i32 = 1252;
+
+/// Options controlling how rows are decoded.
+#[derive(Clone, Debug)]
+pub struct DecodeOptions {
+    /// Whether a column whose value cannot be decoded fails the whole row.
+    ///
+    /// If not set, the raw bytes of such a column are stored as [`Data::Other`] with the type code
+    /// of the column, the problem is collected as a warning, and decoding continues. The same holds
+    /// for a value whose offset within the record is invalid, except that the column is left out.
+    /// Other problems with the structure of the record itself are always errors.
+    pub strict: bool,
+
+    /// The maximum length of a single value, checked before allocating memory for it based on a
+    /// length read from the file.
+    ///
+    /// This protects against excessive allocations when reading damaged or malicious files.
+    pub max_value_len: usize,
+
+    /// Whether to verify that all pages of the table's tree belong to the same object as its root
+    /// page, failing with [`ReadError::PageOwnershipMismatch`] otherwise.
+    pub verify_page_ownership: bool,
+
+    /// The codepage used to decode text columns whose codepage is 0 (unset) in the catalog.
+    ///
+    /// ESE treats such columns as ANSI text; the default is Windows-1252. Columns with an explicit
+    /// codepage, such as 1200 for Unicode columns, are unaffected.
+    pub default_codepage: i32,
+
+    /// Whether to remove trailing spaces from the values of fixed-length text columns.
+    ///
+    /// Trailing NUL characters, with which such values are padded to the length of the column, are
+    /// always removed.
+    pub trim_fixed_text_spaces: bool,
+
+    /// Decrypts the values of encrypted columns.
+    ///
+    /// If not set, the stored bytes of encrypted values are returned as [`Data::Binary`].
+    pub decrypt_provider: Option<Arc<dyn DecryptProvider>>,
+}
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            max_value_len: DEFAULT_MAX_VALUE_LEN,
+            verify_page_ownership: false,
+            default_codepage: DEFAULT_CODEPAGE,
+            trim_fixed_text_spaces: false,
+            decrypt_provider: None,
+        }
     }
-    page_number
 }
 
 
-#[instrument(skip(reader, header))]
-pub fn decode_row<R: Read + Seek>(
-    reader: &mut R,
+/// A decoded row along with the bytes of the record it was decoded from.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct RawRow {
+    /// The key of the record in the table's tree, i.e. its primary key.
+    pub key: Vec<u8>,
+    pub raw: Vec<u8>,
+    pub values: BTreeMap<i32, Value>,
+}
+
+
+/// A problem with the value of a specific column, encountered while decoding a row in lenient mode.
+#[derive(Debug)]
+pub struct ColumnDecodeError {
+    pub column_id: i32,
+    pub error: ReadError,
+}
+
+
+/// A row decoded in lenient mode along with the problems encountered with its individual columns.
+///
+/// The values of the columns that failed to decode are replaced as described in
+/// [`DecodeOptions::strict`]; the remaining values are unaffected.
+#[derive(Debug)]
+pub struct DecodedRow {
+    pub values: BTreeMap<i32, Value>,
+    pub errors: Vec<ColumnDecodeError>,
+}
+
+
+/// The rows of a table along with the problems encountered while decoding them in lenient mode.
+#[derive(Debug)]
+pub struct DecodedRows {
+    pub rows: Vec<BTreeMap<i32, Value>>,
+    pub warnings: Vec<ReadError>,
+}
+
+
+#[instrument(skip(source, header))]
+pub fn decode_row<S: DbSource + ?Sized>(
+    source: &S,
     header: &Header,
     row_data: &[u8],
     columns: &[Column],
     page_size: u32,
     large_value_page_number: Option<u64>,
 ) -> Result<BTreeMap<i32, Value>, ReadError> {
-    let mut sorted_columns: Vec<&Column> = columns.iter().collect();
-    sorted_columns.sort_unstable_by_key(|c| c.column_id);
+    let decoded_columns = DecodedColumns::new(columns);
+    decode_row_with(source, header, row_data, &decoded_columns, page_size, large_value_page_number)
+}
 
-    let fixed_columns: Vec<&Column> = sorted_columns.iter()
-        .take_while(|c| c.column_id < 128)
-        .map(|c| *c)
-        .collect();
-    let variable_columns: BTreeMap<i32, &Column> = sorted_columns.iter()
-        .skip_while(|c| c.column_id < 128)
-        .take_while(|c| c.column_id < 256)
-        .map(|c| (c.column_id, *c))
-        .collect();
-    let tagged_columns: BTreeMap<i32, &Column> = sorted_columns.iter()
-        .skip_while(|c| c.column_id < 256)
-        .map(|c| (c.column_id, *c))
-        .collect();
+/// Decodes a row given the already partitioned columns of its table.
+#[instrument(skip(source, header, columns))]
+pub fn decode_row_with<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    row_data: &[u8],
+    columns: &DecodedColumns,
+    page_size: u32,
+    large_value_page_number: Option<u64>,
+) -> Result<BTreeMap<i32, Value>, ReadError> {
+    let row = decode_row_with_options(
+        source, header, row_data, columns, page_size, large_value_page_number, &DecodeOptions::default(),
+    )?;
+    Ok(row.values)
+}
+
+/// Decodes a row from the bytes of its record alone, according to the given options.
+///
+/// Without a source to read them from, values stored separately in the long value tree of the
+/// table cannot be looked up; a record that references one fails to decode with
+/// [`ReadError::SeparatedValueWithoutLongValueInfo`].
+pub fn decode_record(
+    header: &Header,
+    row_data: &[u8],
+    columns: &DecodedColumns,
+    page_size: u32,
+    options: &DecodeOptions,
+) -> Result<DecodedRow, ReadError> {
+    let no_source: &[u8] = &[];
+    decode_row_with_options(no_source, header, row_data, columns, page_size, None, options)
+}
+
+/// Decodes a row given the already partitioned columns of its table, according to the given
+/// options.
+///
+/// In lenient mode, the row also lists the columns whose values could not be decoded.
+#[instrument(skip(source, header, columns))]
+pub fn decode_row_with_options<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    row_data: &[u8],
+    columns: &DecodedColumns,
+    page_size: u32,
+    large_value_page_number: Option<u64>,
+    options: &DecodeOptions,
+) -> Result<DecodedRow, ReadError> {
+    let fixed_columns = &columns.fixed;
+    let variable_columns = &columns.variable;
+    let tagged_columns = &columns.tagged;
 
     let (last_fixed_data_column, last_variable_data_column, end_fixed_values_offset) = {
-        let mut read = LittleEndianRead::new(Cursor::new(row_data));
+        let mut read = SliceRead::new(row_data);
 
         let lfdc: usize = read.read_u8()?.into();
         let lvdc: usize = read.read_u8()?.into();
@@ -528,36 +1188,77 @@ pub fn decode_row<R: Read + Seek>(
         (lfdc, lvdc, efvo)
     };
 
-    let variable_column_count = if last_variable_data_column >= 128 {
-        last_variable_data_column + 1 - 128
-    } else {
-        0
-    };
+    // variable column IDs start at 128; a record without variable columns stores 127 (or less)
+    let variable_column_count = last_variable_data_column.saturating_sub(127);
 
     // ceil(last_fixed_data_column / 8.0)
     let nullity_byte_count = (last_fixed_data_column + 7) / 8;
 
     let fixed_start = 4;
-    let nullity_start = end_fixed_values_offset - nullity_byte_count;
+    let nullity_start = end_fixed_values_offset.checked_sub(nullity_byte_count)
+        .ok_or_else(|| ReadError::malformed_record("end of fixed values before nullity bitmap"))?;
     let variable_start = end_fixed_values_offset;
 
-    let fixed_slice = &row_data[fixed_start..nullity_start];
-    let nullity_slice = &row_data[nullity_start..variable_start];
-    let variable_and_tagged_slice = &row_data[variable_start..];
+    let fixed_slice = row_data.get(fixed_start..nullity_start)
+        .ok_or_else(|| ReadError::malformed_record("fixed values out of bounds"))?;
+    let nullity_slice = row_data.get(nullity_start..variable_start)
+        .ok_or_else(|| ReadError::malformed_record("nullity bitmap out of bounds"))?;
+    let variable_and_tagged_slice = row_data.get(variable_start..)
+        .ok_or_else(|| ReadError::malformed_record("variable values out of bounds"))?;
 
     let mut ret = BTreeMap::new();
+    let mut errors = Vec::new();
 
-    let mut fixed_read = LittleEndianRead::new(Cursor::new(fixed_slice));
+    let mut fixed_read = SliceRead::new(fixed_slice);
+    let mut fixed_position_lost = false;
     for fixed_column in fixed_columns.iter().take(last_fixed_data_column) {
         let column_id = fixed_column.column_id;
 
+        // the record offset counts from the start of the record; offsets within the header are bogus
+        let fixed_offset = fixed_column.record_offset
+            .and_then(|ro| usize::try_from(ro).ok())
+            .and_then(|ro| ro.checked_sub(fixed_start));
+        match fixed_offset {
+            Some(offset) => {
+                // templates and derived tables can leave gaps between fixed values
+                fixed_read.set_position(offset);
+                fixed_position_lost = false;
+            },
+            None => {
+                if fixed_position_lost {
+                    // the value directly follows one of unknown size
+                    break;
+                }
+            },
+        }
+
         let value = match fixed_column.column_type {
+            DataType::Other(code) if fixed_column.length > 0 => {
+                // unknown type; pass on the raw bytes
+                let field_length = column_length(fixed_column)?;
+                ReadError::ensure_value_len(field_length, options.max_value_len)?;
+                let mut inner_value = vec![0u8; field_length];
+                fixed_read.read_bytes(&mut inner_value)?;
+                Data::Other(code, inner_value)
+            },
             DataType::Nil|DataType::LongBinary|DataType::LongText|DataType::SuperLongValue|DataType::Other(_) => {
-                return Err(ReadError::UnexpectedFixedColumnDataType {
+                let error = ReadError::UnexpectedFixedColumnDataType {
                     table_id: fixed_column.table_object_id,
                     column_id: fixed_column.column_id,
                     data_type: fixed_column.column_type,
+                };
+                if options.strict {
+                    return Err(error);
+                }
+
+                // the size of this value is unknown, so only fixed values with a record offset can
+                // be found after it
+                errors.push(ColumnDecodeError {
+                    column_id,
+                    error,
                 });
+                fixed_position_lost = true;
+                continue;
             },
             DataType::Bit => {
                 let inner_value = fixed_read.read_u8()?;
@@ -592,18 +1293,24 @@ pub fn decode_row<R: Read + Seek>(
                 Data::DateTime(inner_value)
             },
             DataType::Binary => {
-                let field_length: usize = fixed_column.length.try_into().unwrap();
+                let field_length = column_length(fixed_column)?;
+                ReadError::ensure_value_len(field_length, options.max_value_len)?;
                 let mut inner_value = vec![0u8; field_length];
-                fixed_read.read_exact(&mut inner_value)?;
+                fixed_read.read_bytes(&mut inner_value)?;
                 Data::Binary(inner_value)
             },
             DataType::Text => {
-                let field_length: usize = fixed_column.length.try_into().unwrap();
+                let field_length = column_length(fixed_column)?;
+                ReadError::ensure_value_len(field_length, options.max_value_len)?;
                 let mut bytes = vec![0u8; field_length];
-                fixed_read.read_exact(&mut bytes)?;
-
-                let string = decode_string(&bytes, fixed_column.codepage);
-                Data::Text(string)
+                fixed_read.read_bytes(&mut bytes)?;
+
+                let data_res = decode_text_data(&bytes, column_codepage(fixed_column, options.default_codepage), false)
+                    .map(|data| match data {
+                        Data::Text(string) => Data::Text(trim_fixed_text(string, options)),
+                        other => other,
+                    });
+                recover_column_data(data_res, fixed_column, &bytes, options, &mut errors)?
             },
             DataType::UnsignedLong => {
                 let inner_value = fixed_read.read_u32()?;
@@ -615,7 +1322,7 @@ pub fn decode_row<R: Read + Seek>(
             },
             DataType::Guid => {
                 let mut guid_bytes = [0u8; 16];
-                fixed_read.read_exact(&mut guid_bytes)?;
+                fixed_read.read_bytes(&mut guid_bytes)?;
                 let inner_value = Uuid::from_bytes_le(guid_bytes);
                 Data::Guid(inner_value)
             },
@@ -627,7 +1334,8 @@ pub fn decode_row<R: Read + Seek>(
 
         // check nullity
         let is_null = {
-            let column_index: usize = (column_id - 1).try_into().unwrap();
+            let column_index: usize = (column_id - 1).try_into()
+                .map_err(|_| ReadError::malformed_record(format!("invalid fixed column ID {}", column_id)))?;
             let byte_index = column_index / 8;
             let bit_index = column_index % 8;
 
@@ -645,30 +1353,62 @@ pub fn decode_row<R: Read + Seek>(
         ret.insert(fixed_column.column_id, Value::Simple(nullified_value));
     }
 
+    if variable_and_tagged_slice.len() < 2*variable_column_count {
+        return Err(ReadError::malformed_record("variable value offsets out of bounds"));
+    }
     let (variable_offsets_slice, variable_and_tagged_data_slice) = variable_and_tagged_slice.split_at(2*variable_column_count);
 
-    let mut variable_offsets_read = LittleEndianRead::new(Cursor::new(variable_offsets_slice));
+    let mut variable_offsets_read = SliceRead::new(variable_offsets_slice);
 
-    // read the variable offsets
-    let mut variable_offsets = vec![0; variable_column_count + 1];
-    for i in 0..variable_column_count {
+    // read the variable offsets; each is the end offset of its column's value
+    let mut variable_end_offsets = Vec::with_capacity(variable_column_count);
+    for _ in 0..variable_column_count {
         let column_offset = variable_offsets_read.read_u16()?;
-        variable_offsets[i + 1] = column_offset;
+        variable_end_offsets.push(column_offset);
     }
 
-    for (i, pair) in variable_offsets.windows(2).enumerate() {
+    let mut begin: usize = 0;
+    let mut last_offset_valid = true;
+    for (i, &end_raw) in variable_end_offsets.iter().enumerate() {
         let column_id: i32 = (128 + i).try_into().unwrap();
 
-        let (begin_raw, end_raw) = (pair[0], pair[1]);
+        // the offsets can never decrease or point past the data
+        let end: usize = (end_raw & 0b0111_1111_1111_1111).into();
+        let offset_error = if end > variable_and_tagged_data_slice.len() {
+            Some(ReadError::malformed_record(format!("variable column {} offset {} out of bounds", column_id, end)))
+        } else if end < begin {
+            Some(ReadError::malformed_record("non-monotonic variable offsets"))
+        } else {
+            None
+        };
+        last_offset_valid = offset_error.is_none();
+        if let Some(error) = offset_error {
+            if options.strict {
+                return Err(error);
+            }
+            // the value cannot be located; the next one starts where the last valid one ends
+            let error = match variable_columns.get(&column_id) {
+                Some(column_def) => error.in_table(column_def.table_object_id),
+                None => error,
+            };
+            errors.push(ColumnDecodeError {
+                column_id,
+                error,
+            });
+            continue;
+        }
+
+        // the next value starts where this one ends, even if this one has no value
+        let value_range = begin..end;
+        begin = end;
+
         if end_raw & (1 << 15) != 0 {
             // topmost bit set => no value for this column
             continue;
         }
 
-        let begin: usize = (begin_raw & 0b0111_1111_1111_1111).into();
-        let end: usize = (end_raw & 0b0111_1111_1111_1111).into();
-
-        let data_slice = &variable_and_tagged_data_slice[begin..end];
+        let data_slice = variable_and_tagged_data_slice.get(value_range)
+            .ok_or_else(|| ReadError::malformed_record(format!("variable column {} out of bounds", column_id)))?;
 
         let column_def = match variable_columns.get(&column_id) {
             Some(cd) => cd,
@@ -679,38 +1419,58 @@ pub fn decode_row<R: Read + Seek>(
             },
         };
 
-        let data = match column_def.column_type {
+        let data_res = match column_def.column_type {
             DataType::Nil|DataType::Bit|DataType::UnsignedByte|DataType::Short
                     |DataType::Long|DataType::Currency|DataType::IeeeSingle|DataType::IeeeDouble
                     |DataType::DateTime|DataType::LongBinary|DataType::LongText|DataType::SuperLongValue
-                    |DataType::UnsignedLong|DataType::LongLong|DataType::Guid|DataType::UnsignedShort
-                    |DataType::Other(_) => {
-                return Err(ReadError::UnexpectedFixedColumnDataType {
+                    |DataType::UnsignedLong|DataType::LongLong|DataType::Guid|DataType::UnsignedShort => {
+                Err(ReadError::UnexpectedVariableColumnDataType {
                     table_id: column_def.table_object_id,
                     column_id: column_def.column_id,
                     data_type: column_def.column_type,
-                });
+                })
             },
             DataType::Text => {
-                let string = decode_string(data_slice, column_def.codepage);
-                Data::Text(string)
+                decode_text_data(data_slice, column_codepage(column_def, options.default_codepage), false)
             },
             DataType::Binary => {
-                Data::Binary(data_slice.to_vec())
+                Ok(Data::Binary(data_slice.to_vec()))
+            },
+            DataType::Other(code) => {
+                Ok(Data::Other(code, data_slice.to_vec()))
             },
         };
+        let data = recover_column_data(data_res, column_def, data_slice, options, &mut errors)?;
         ret.insert(column_id, Value::Simple(data));
     }
 
-    let tagged_start = usize::from(*variable_offsets.last().unwrap());
-    let tagged_data_slice = &variable_and_tagged_data_slice[tagged_start..];
+    // the tagged values start where the last variable value ends (immediately after the variable
+    // offsets if there are no variable columns); the final offset may have the "no value" bit set too,
+    // and if it is invalid, the tagged values cannot be found
+    let tagged_data_slice = if last_offset_valid {
+        variable_and_tagged_data_slice.get(begin..)
+            .ok_or_else(|| ReadError::malformed_record("tagged values out of bounds"))?
+    } else {
+        &[]
+    };
     if tagged_data_slice.len() > 0 {
         let mut tags_offsets_flags = Vec::new();
 
         {
-            let mut min_tagged_data_offset = u64::MAX;
-            let mut tagged_read = LittleEndianRead::new(Cursor::new(tagged_data_slice));
-            while tagged_read.stream_position().unwrap() < min_tagged_data_offset {
+            // each tag takes up four bytes, so there cannot be more tags than that
+            let max_tag_count = tagged_data_slice.len() / 4;
+            let mut tag_count = 0;
+            let mut min_tagged_data_offset = usize::MAX;
+            let mut tagged_read = SliceRead::new(tagged_data_slice);
+            while tagged_read.position() < min_tagged_data_offset {
+                if tag_count >= max_tag_count {
+                    if options.strict {
+                        return Err(ReadError::malformed_record("too many tagged columns"));
+                    }
+                    // all the tags have been read; those with invalid offsets have been skipped
+                    break;
+                }
+                tag_count += 1;
                 let tag = tagged_read.read_u16()?;
                 let offset_and_flags = tagged_read.read_u16()?;
 
@@ -726,6 +1486,18 @@ pub fn decode_row<R: Read + Seek>(
                         SmallTagFlags::HAS_EXTENDED_FLAGS | SmallTagFlags::from_bits_retain(offset_and_flags & 0b1000_0000_0000_0000),
                     )
                 };
+
+                // the values follow the tags and cannot point back into them
+                let tags_end = tagged_read.position();
+                if usize::from(offset) < tags_end || usize::from(offset) > tagged_data_slice.len() {
+                    let error = ReadError::malformed_record(format!("tagged column {} offset {} out of bounds", tag, offset));
+                    if options.strict {
+                        return Err(error);
+                    }
+                    push_tagged_column_error(&mut errors, tagged_columns, tag, error);
+                    continue;
+                }
+
                 tags_offsets_flags.push((tag, offset, flags));
                 min_tagged_data_offset = min_tagged_data_offset.min(offset.into());
             }
@@ -733,11 +1505,19 @@ pub fn decode_row<R: Read + Seek>(
 
         for (i, &(tag, offset, small_flags)) in tags_offsets_flags.iter().enumerate() {
             let begin = usize::from(offset);
-            let mut item_slice = if i < tags_offsets_flags.len() - 1 {
+            let item_slice_opt = if i < tags_offsets_flags.len() - 1 {
                 let next_begin: usize = usize::from(tags_offsets_flags[i+1].1);
-                &tagged_data_slice[begin..next_begin]
+                tagged_data_slice.get(begin..next_begin)
             } else {
-                &tagged_data_slice[begin..]
+                tagged_data_slice.get(begin..)
+            };
+            let Some(mut item_slice) = item_slice_opt else {
+                let error = ReadError::malformed_record(format!("tagged column {} out of bounds", tag));
+                if options.strict {
+                    return Err(error);
+                }
+                push_tagged_column_error(&mut errors, tagged_columns, tag, error);
+                continue;
             };
 
             let column_number: i32 = tag.into();
@@ -745,7 +1525,10 @@ pub fn decode_row<R: Read + Seek>(
 
             let flags = if small_flags.contains(SmallTagFlags::HAS_EXTENDED_FLAGS) {
                 // beautiful
-                let mut big_flags = TagFlags::from_bits_retain(item_slice[0]);
+                let Some((&flags_byte, rest)) = item_slice.split_first() else {
+                    return Err(ReadError::malformed_record(format!("tagged column {} has no extended flags", tag)))
+                };
+                let mut big_flags = TagFlags::from_bits_retain(flags_byte);
 
                 // the small flags can only contain DERIVED
                 if small_flags.contains(SmallTagFlags::DERIVED) {
@@ -754,151 +1537,95 @@ pub fn decode_row<R: Read + Seek>(
                 }
 
                 // skip the first byte
-                item_slice = &item_slice[1..];
+                item_slice = rest;
                 big_flags
             } else {
                 TagFlags::from_small(small_flags)
             };
 
-            let mut multi_slices;
-            let slices = if flags.contains(TagFlags::TWO_VALUES) {
-                let first_value_length = usize::from(item_slice[0]);
-                let (first_slice, second_slice) = item_slice[1..].split_at(first_value_length);
-                &[first_slice, second_slice][..]
-            } else if flags.contains(TagFlags::MULTI_VALUES) {
-                let first_value_offset = u16::from_le_bytes(item_slice[0..2].try_into().unwrap());
-                let offsets_slice = &item_slice[..first_value_offset.into()];
-
-                let mut offsets = Vec::with_capacity(offsets_slice.len() / 2 + 1);
-                for chunk in offsets_slice.chunks(2) {
-                    let offset: usize = u16::from_le_bytes(chunk.try_into().unwrap()).into();
-                    offsets.push(offset);
-                }
+            // the long value of a separated multi-valued column contains the whole multi-value structure
+            let separated_item;
+            let mut value_flags = flags;
+            if flags.contains(TagFlags::SEPARATED) && flags.intersects(TagFlags::TWO_VALUES | TagFlags::MULTI_VALUES) {
+                let Some(sep_page_number) = large_value_page_number else {
+                    return Err(ReadError::SeparatedValueWithoutLongValueInfo)
+                };
+                separated_item = read_long_value_with_max(source, header, sep_page_number, item_slice, options.max_value_len)?;
+                item_slice = &separated_item;
 
-                // append the length of the slice as the final "offset"
-                offsets.push(item_slice.len());
+                // the values within are compressed individually; they are decompressed below
+                value_flags.remove(TagFlags::SEPARATED);
+            }
 
-                multi_slices = Vec::with_capacity(offsets.len() - 1);
-                for pair in offsets.windows(2) {
-                    let (begin, end) = (pair[0], pair[1]);
-                    multi_slices.push(&item_slice[begin..end]);
-                }
-                multi_slices.as_slice()
+            let slices = if flags.contains(TagFlags::TWO_VALUES) {
+                split_two_values(item_slice, tag)?.to_vec()
+            } else if flags.contains(TagFlags::MULTI_VALUES) {
+                split_multi_values(item_slice, tag)?
             } else {
-                &[item_slice][..]
+                vec![item_slice]
             };
 
-            let mut values = Vec::with_capacity(slices.len());
-            for &value_slice in slices {
-                match column.column_type {
-                    DataType::Long => {
-                        let inner_value = i32::from_le_bytes(value_slice.try_into().unwrap());
-                        values.push(Data::Long(inner_value));
-                    },
-                    DataType::Currency => {
-                        let inner_value = i64::from_le_bytes(value_slice.try_into().unwrap());
-                        values.push(Data::Currency(inner_value));
-                    },
-                    DataType::LongText => {
-                        if flags.contains(TagFlags::SEPARATED) {
-                            // the data is stored in a different page
-                            let Some(sep_page_number) = large_value_page_number else {
-                                return Err(ReadError::SeparatedValueWithoutLongValueInfo)
-                            };
-                            let value_number = reference_bytes_to_value_number(value_slice);
-                            let mut separated_values = Vec::new();
-                            let mut skip_index = 0;
-                            read_data_from_tree(
-                                reader,
-                                header,
-                                sep_page_number,
-                                value_number,
-                                1,
-                                &mut separated_values,
-                                &mut skip_index,
-                            )?;
-                            for separated_value in separated_values {
-                                let separated_string = decode_string(&separated_value, column.codepage);
-                                values.push(Data::LongText(separated_string));
-                            }
-                        } else {
-                            let inner_value = decode_string(value_slice, column.codepage);
-                            values.push(Data::LongText(inner_value));
-                        }
-                    },
-                    DataType::LongBinary => {
-                        if flags.contains(TagFlags::SEPARATED) {
-                            let Some(sep_page_number) = large_value_page_number else {
-                                return Err(ReadError::SeparatedValueWithoutLongValueInfo)
-                            };
-                            let value_number = reference_bytes_to_value_number(value_slice);
-                            let mut separated_values = Vec::new();
-                            let mut skip_index = 0;
-                            read_data_from_tree(
-                                reader,
-                                header,
-                                sep_page_number,
-                                value_number,
-                                1,
-                                &mut separated_values,
-                                &mut skip_index,
-                            )?;
-                            for separated_value in separated_values {
-                                values.push(Data::LongBinary(separated_value));
-                            }
-                        } else {
-                            let inner_value = value_slice.to_vec();
-                            values.push(Data::LongBinary(inner_value));
-                        }
-                    },
-                    other => {
-                        return Err(ReadError::UnexpectedTaggedColumnDataType {
-                            table_id: column.table_object_id,
-                            column_id: column.column_id,
-                            data_type: other,
-                        });
-                    },
-                }
+            let mut values = MultiValues::with_capacity(slices.len());
+            let mut compression = None;
+            for value_slice in slices {
+                let data_res = decode_tagged_value(
+                    source, header, value_slice, column, value_flags, large_value_page_number, options,
+                )
+                    .map(|(data, value_compression)| {
+                        compression = compression.or(value_compression);
+                        data
+                    });
+                values.push(recover_column_data(data_res, column, value_slice, options, &mut errors)?);
             }
 
-            if flags.contains(TagFlags::SEPARATED) && column.column_type != DataType::LongText && column.column_type != DataType::LongBinary {
-                panic!("unexpected data type for SEPARATED");
+            if flags.contains(TagFlags::SEPARATED)
+                    && column.column_type != DataType::LongText
+                    && column.column_type != DataType::LongBinary
+                    && column.column_type != DataType::SuperLongValue
+                    && !matches!(column.column_type, DataType::Other(_)) {
+                return Err(ReadError::malformed_record(format!("tagged column {} of type {:?} is separated", tag, column.column_type)));
             }
 
+            // two-valued columns always have two values and therefore become multiple values
             if values.len() == 1 {
                 ret.insert(column.column_id, Value::Complex {
-                    data: values.swap_remove(0),
+                    data: values.into_iter().next().unwrap(),
                     flags,
+                    compression,
                 });
             } else {
                 ret.insert(column.column_id, Value::Multiple {
                     values,
                     flags,
+                    compression,
                 });
             }
         }
     }
 
-    Ok(ret)
+    Ok(DecodedRow {
+        values: ret,
+        errors,
+    })
 }
 
 #[instrument]
-fn decode_string(bytes: &[u8], codepage: i32) -> String {
+pub(crate) fn decode_string(bytes: &[u8], codepage: i32) -> Result<String, ReadError> {
     if codepage == 1200 {
-        // UTF-16LE
-        let mut words = Vec::with_capacity(bytes.len() / 2);
-        for chunk in bytes.chunks_exact(2) {
-            let word = u16::from_le_bytes(chunk.try_into().unwrap());
-            words.push(word);
-        }
-        return String::from_utf16(&words).unwrap();
+        return decode_utf16le(bytes);
     }
 
     if codepage < 0 || codepage > 0xFFFF {
-        panic!("cannot handle codepage {} (0x{:X})", codepage, codepage as u32);
+        return Err(ReadError::malformed_record(format!("cannot handle codepage {} (0x{:X})", codepage, codepage as u32)));
     }
+    decode_codepage(bytes, codepage)
+}
+
+/// Decodes a string in a single-byte or multi-byte codepage using `encoding_rs`.
+#[cfg(feature = "encodings")]
+fn decode_codepage(bytes: &[u8], codepage: i32) -> Result<String, ReadError> {
     let Some(encoder) = codepage::to_encoding(codepage.try_into().unwrap()) else {
-        panic!("cannot find decoder for codepage {} (0x{:X})", codepage, codepage as u32);
+        return Err(ReadError::malformed_record(format!("cannot find decoder for codepage {} (0x{:X})", codepage, codepage as u32)));
     };
     let mut string = String::with_capacity(1024);
 
@@ -913,40 +1640,744 @@ fn decode_string(bytes: &[u8], codepage: i32) -> String {
                 string.reserve(512);
             },
             DecoderResult::Malformed(_, _) => {
-                panic!("effed up decoding of {0:?} in codepage {1} (0x{1:X})", bytes, codepage);
+                let (lossy, _had_errors) = encoder.decode_with_bom_removal(bytes);
+                return Err(ReadError::InvalidString {
+                    codepage,
+                    reason: Cow::Owned(format!("effed up decoding of {:?}", bytes)),
+                    lossy: lossy.into_owned(),
+                });
             },
         }
     }
-    string
+    Ok(string)
 }
 
-fn get_name_to_column(columns: &[Column]) -> BTreeMap<&str, &Column> {
-    columns
-        .iter()
-        .map(|col| (col.name.as_str(), col))
-        .collect()
+/// Decodes a string in ASCII or Latin-1 without the help of `encoding_rs`.
+///
+/// Windows-1252 is decoded as Latin-1, which only differs in the range 0x80 to 0x9F. Other
+/// codepages fail with [`ReadError::EncodingUnavailable`].
+#[cfg(not(feature = "encodings"))]
+fn decode_codepage(bytes: &[u8], codepage: i32) -> Result<String, ReadError> {
+    match codepage {
+        1252|28591 => Ok(bytes.iter().map(|b| char::from(*b)).collect()),
+        20127 => Ok(
+            bytes.iter()
+                .map(|b| if b.is_ascii() { char::from(*b) } else { char::REPLACEMENT_CHARACTER })
+                .collect()
+        ),
+        _ => Err(ReadError::EncodingUnavailable { codepage }),
+    }
 }
 
-#[instrument(skip(reader, header), fields(header.page_number, header.version, header.revision))]
-pub fn read_table_from_pages<R: Read + Seek>(
-    reader: &mut R,
-    header: &Header,
-    page_number: u64,
-    columns: &[Column],
-    large_value_page_number: Option<u64>,
+/// Decodes text, returning it as binary data if the codepage is not supported by this build.
+fn decode_text_data(bytes: &[u8], codepage: i32, long: bool) -> Result<Data, ReadError> {
+    match decode_string(bytes, codepage) {
+        Ok(string) => Ok(if long { Data::LongText(string) } else { Data::Text(string) }),
+        Err(ReadError::EncodingUnavailable { .. }) => {
+            let bytes = bytes.to_vec();
+            Ok(if long { Data::LongBinary(bytes) } else { Data::Binary(bytes) })
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Decodes a UTF-16LE string.
+///
+/// Unpaired surrogates and a trailing odd byte are errors; the error contains the string with each
+/// of them replaced by U+FFFD so that lenient decoding can still make use of it.
+fn decode_utf16le(bytes: &[u8]) -> Result<String, ReadError> {
+    let chunks = bytes.chunks_exact(2);
+    let odd_byte = !chunks.remainder().is_empty();
+    let words = chunks.map(|chunk| u16::from_le_bytes(chunk.try_into().unwrap()));
+
+    let mut string = String::with_capacity(bytes.len() / 2);
+    let mut first_error = None;
+    for (index, char_res) in char::decode_utf16(words).enumerate() {
+        match char_res {
+            Ok(c) => string.push(c),
+            Err(e) => {
+                if first_error.is_none() {
+                    first_error = Some(format!("unpaired surrogate 0x{:04X} at word {}", e.unpaired_surrogate(), index));
+                }
+                string.push(char::REPLACEMENT_CHARACTER);
+            },
+        }
+    }
+    if odd_byte {
+        if first_error.is_none() {
+            first_error = Some(format!("odd number of bytes ({})", bytes.len()));
+        }
+        string.push(char::REPLACEMENT_CHARACTER);
+    }
+
+    match first_error {
+        None => Ok(string),
+        Some(reason) => Err(ReadError::InvalidString {
+            codepage: 1200,
+            reason: Cow::Owned(reason),
+            lossy: string,
+        }),
+    }
+}
+
+/// Encodes a string in the given codepage; the inverse of [`decode_string`].
+///
+/// Characters that cannot be represented in the codepage cause an error instead of being replaced.
+#[instrument]
+pub fn encode_string(s: &str, codepage: i32) -> Result<Vec<u8>, WriteError> {
+    if codepage == 1200 || codepage == 1201 {
+        // UTF-16LE or UTF-16BE; encoding_rs only encodes into ASCII-compatible encodings
+        let mut bytes = Vec::with_capacity(2 * s.len());
+        for word in s.encode_utf16() {
+            if codepage == 1200 {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&word.to_be_bytes());
+            }
+        }
+        return Ok(bytes);
+    }
+
+    if !(0..=0xFFFF).contains(&codepage) {
+        return Err(WriteError::UnsupportedCodepage { codepage });
+    }
+    encode_codepage(s, codepage)
+}
+
+/// Encodes a string in a single-byte or multi-byte codepage using `encoding_rs`.
+#[cfg(feature = "encodings")]
+fn encode_codepage(s: &str, codepage: i32) -> Result<Vec<u8>, WriteError> {
+    let Some(encoding) = codepage::to_encoding(codepage.try_into().unwrap()) else {
+        return Err(WriteError::UnsupportedCodepage { codepage });
+    };
+    let mut bytes = Vec::with_capacity(s.len());
+
+    let mut encoder = encoding.new_encoder();
+    let mut offset = 0;
+    loop {
+        let (res, crunched) = encoder.encode_from_utf8_to_vec_without_replacement(&s[offset..], &mut bytes, true);
+        offset += crunched;
+        match res {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => {
+                bytes.reserve(512);
+            },
+            EncoderResult::Unmappable(_) => {
+                return Err(WriteError::StringNotRepresentable { codepage });
+            },
+        }
+    }
+    Ok(bytes)
+}
+
+/// Encodes a string in ASCII or Latin-1 without the help of `encoding_rs`.
+///
+/// Only characters that Windows-1252 shares with Latin-1 can be encoded in Windows-1252.
+#[cfg(not(feature = "encodings"))]
+fn encode_codepage(s: &str, codepage: i32) -> Result<Vec<u8>, WriteError> {
+    let max_char = match codepage {
+        20127 => '\u{7F}',
+        1252|28591 => '\u{FF}',
+        _ => return Err(WriteError::UnsupportedCodepage { codepage }),
+    };
+    s.chars()
+        .map(|c| {
+            if c > max_char || (codepage == 1252 && ('\u{80}'..='\u{9F}').contains(&c)) {
+                Err(WriteError::StringNotRepresentable { codepage })
+            } else {
+                Ok(u8::try_from(c).unwrap())
+            }
+        })
+        .collect()
+}
+
+/// Returns the length of a fixed-size column.
+fn column_length(column: &Column) -> Result<usize, ReadError> {
+    column.length.try_into()
+        .map_err(|_| ReadError::malformed_record(format!("column {} has invalid length {}", column.column_id, column.length)))
+}
+
+/// Converts the value of a fixed-size type stored in a tagged column into an array of the correct
+/// size.
+fn fixed_size_value<const N: usize>(value_slice: &[u8], column: &Column) -> Result<[u8; N], ReadError> {
+    value_slice.try_into()
+        .map_err(|_| ReadError::malformed_record(format!(
+            "value of tagged column {} has {} bytes, expected {}", column.column_id, value_slice.len(), N,
+        )))
+}
+
+/// Splits the data of a tagged column with [`TagFlags::TWO_VALUES`] into its two values.
+///
+/// The data consists of the length of the first value (one byte), the first value and the second
+/// value. Both values have the type of the column, so fixed-size types such as GUIDs are stored
+/// like any other.
+fn split_two_values(item_slice: &[u8], tag: u16) -> Result<[&[u8]; 2], ReadError> {
+    let Some((&first_value_length_u8, values_slice)) = item_slice.split_first() else {
+        return Err(ReadError::malformed_record(format!("two-valued tagged column {} is empty", tag)))
+    };
+    let first_value_length = usize::from(first_value_length_u8);
+    if values_slice.len() < first_value_length {
+        return Err(ReadError::malformed_record(format!("first value of tagged column {} out of bounds", tag)));
+    }
+    let (first_slice, second_slice) = values_slice.split_at(first_value_length);
+    Ok([first_slice, second_slice])
+}
+
+/// Splits the data of a tagged column with [`TagFlags::MULTI_VALUES`] into its values.
+///
+/// The data consists of the offsets of the values (little-endian `u16`s, relative to the start of
+/// the data) followed by the values themselves.
+fn split_multi_values(item_slice: &[u8], tag: u16) -> Result<Vec<&[u8]>, ReadError> {
+    let first_value_offset_bytes = item_slice.get(0..2)
+        .ok_or_else(|| ReadError::malformed_record(format!("multi-valued tagged column {} has no offsets", tag)))?;
+    let first_value_offset = u16::from_le_bytes(first_value_offset_bytes.try_into().unwrap());
+    let offsets_slice = item_slice.get(..first_value_offset.into())
+        .ok_or_else(|| ReadError::malformed_record(format!("offsets of tagged column {} out of bounds", tag)))?;
+    if offsets_slice.len() % 2 != 0 {
+        return Err(ReadError::malformed_record(format!("offsets of tagged column {} have odd length", tag)));
+    }
+
+    let mut offsets = Vec::with_capacity(offsets_slice.len() / 2 + 1);
+    for chunk in offsets_slice.chunks_exact(2) {
+        let offset: usize = u16::from_le_bytes(chunk.try_into().unwrap()).into();
+        offsets.push(offset);
+    }
+
+    // append the length of the slice as the final "offset"
+    offsets.push(item_slice.len());
+
+    let mut slices = Vec::with_capacity(offsets.len() - 1);
+    for pair in offsets.windows(2) {
+        let (begin, end) = (pair[0], pair[1]);
+        let value_slice = item_slice.get(begin..end)
+            .ok_or_else(|| ReadError::malformed_record(format!("value of tagged column {} out of bounds", tag)))?;
+        slices.push(value_slice);
+    }
+    Ok(slices)
+}
+
+/// Decodes a single value of a tagged column, decompressing it or reading it from the long value
+/// tree if necessary. Also returns the compression scheme of a compressed value.
+fn decode_tagged_value<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    value_slice: &[u8],
+    column: &Column,
+    flags: TagFlags,
+    large_value_page_number: Option<u64>,
+    options: &DecodeOptions,
+) -> Result<(Data, Option<CompressionScheme>), ReadError> {
+    let is_long_type = matches!(
+        column.column_type,
+        DataType::LongText|DataType::LongBinary|DataType::SuperLongValue|DataType::Other(_)
+    );
+    let separated = flags.contains(TagFlags::SEPARATED) && is_long_type;
+    let stored_bytes = if separated {
+        // the data is stored in a different page
+        let Some(sep_page_number) = large_value_page_number else {
+            return Err(ReadError::SeparatedValueWithoutLongValueInfo)
+        };
+        Cow::Owned(read_long_value_with_max(source, header, sep_page_number, value_slice, options.max_value_len)?)
+    } else {
+        Cow::Borrowed(value_slice)
+    };
+
+    let stored_bytes = if flags.contains(TagFlags::ENCRYPTED) || column.flags.contains(ColumnFlags::ENCRYPTED) {
+        let Some(decrypt_provider) = &options.decrypt_provider else {
+            // without the key material, the best we can do is pass on the ciphertext
+            return Ok((Data::Binary(stored_bytes.into_owned()), None));
+        };
+        let plaintext = decrypt_provider.decrypt(column.column_id, &stored_bytes)
+            .map_err(|error| ReadError::DecryptionFailed { column_id: column.column_id, error })?;
+        Cow::Owned(plaintext)
+    } else {
+        stored_bytes
+    };
+
+    if flags.contains(TagFlags::COMPRESSED) {
+        // separated values are compressed as a whole; the reassembled value is decompressed like
+        // one stored in place
+        let compression = stored_bytes.first()
+            .map(|header_byte| CompressionScheme::from_header_byte(*header_byte));
+        let decompressed_value = decompress(&stored_bytes)?;
+        Ok((decode_value_bytes(&decompressed_value, column, options.default_codepage)?, compression))
+    } else {
+        Ok((decode_value_bytes(&stored_bytes, column, options.default_codepage)?, None))
+    }
+}
+
+/// Removes the padding from the value of a fixed-length text column.
+fn trim_fixed_text(mut string: String, options: &DecodeOptions) -> String {
+    let trimmed_len = if options.trim_fixed_text_spaces {
+        string.trim_end_matches(['\0', ' ']).len()
+    } else {
+        string.trim_end_matches('\0').len()
+    };
+    string.truncate(trimmed_len);
+    string
+}
+
+/// Returns the codepage of a text column, falling back to the given default if the column has none.
+pub(crate) fn column_codepage(column: &Column, default_codepage: i32) -> i32 {
+    if column.codepage == 0 {
+        default_codepage
+    } else {
+        column.codepage
+    }
+}
+
+/// Interprets the bytes of a single value according to the type of the given column.
+fn decode_value_bytes(value_slice: &[u8], column: &Column, default_codepage: i32) -> Result<Data, ReadError> {
+    let data = match column.column_type {
+        DataType::Bit => {
+            let inner_value = u8::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::Bit(Bit(inner_value))
+        },
+        DataType::UnsignedByte => {
+            let inner_value = u8::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::UnsignedByte(inner_value)
+        },
+        DataType::Short => {
+            let inner_value = i16::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::Short(inner_value)
+        },
+        DataType::Long => {
+            let inner_value = i32::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::Long(inner_value)
+        },
+        DataType::Currency => {
+            let inner_value = i64::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::Currency(inner_value)
+        },
+        DataType::IeeeSingle => {
+            let inner_value = f32::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::IeeeSingle(inner_value)
+        },
+        DataType::IeeeDouble => {
+            let inner_value = f64::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::IeeeDouble(inner_value)
+        },
+        DataType::DateTime => {
+            let inner_value = i64::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::DateTime(inner_value)
+        },
+        DataType::Binary => {
+            Data::Binary(value_slice.to_vec())
+        },
+        DataType::Text => {
+            decode_text_data(value_slice, column_codepage(column, default_codepage), false)?
+        },
+        DataType::UnsignedLong => {
+            let inner_value = u32::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::UnsignedLong(inner_value)
+        },
+        DataType::LongLong => {
+            let inner_value = i64::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::LongLong(inner_value)
+        },
+        DataType::Guid => {
+            let inner_value = Uuid::from_bytes_le(fixed_size_value(value_slice, column)?);
+            Data::Guid(inner_value)
+        },
+        DataType::UnsignedShort => {
+            let inner_value = u16::from_le_bytes(fixed_size_value(value_slice, column)?);
+            Data::UnsignedShort(inner_value)
+        },
+        DataType::LongText => {
+            decode_text_data(value_slice, column_codepage(column, default_codepage), true)?
+        },
+        DataType::LongBinary => {
+            Data::LongBinary(value_slice.to_vec())
+        },
+        DataType::SuperLongValue => {
+            Data::SuperLongValue(value_slice.to_vec())
+        },
+        DataType::Other(code) => {
+            // unknown type; pass on the raw bytes
+            Data::Other(code, value_slice.to_vec())
+        },
+        other => {
+            return Err(ReadError::UnexpectedTaggedColumnDataType {
+                table_id: column.table_object_id,
+                column_id: column.column_id,
+                data_type: other,
+            });
+        },
+    };
+    Ok(data)
+}
+
+/// Passes on the result of decoding the value of a column; in lenient mode, records an error for
+/// the column and turns the raw bytes of the value into [`Data::Other`]. Invalid strings are kept as text
+/// with replacement characters instead.
+fn recover_column_data(
+    data_res: Result<Data, ReadError>,
+    column: &Column,
+    raw_value: &[u8],
+    options: &DecodeOptions,
+    errors: &mut Vec<ColumnDecodeError>,
+) -> Result<Data, ReadError> {
+    match data_res {
+        Ok(data) => Ok(data),
+        Err(e) => {
+            if options.strict {
+                return Err(e);
+            }
+            // keep undecodable text as text with replacement characters
+            let data = match (&e, column.column_type) {
+                (ReadError::InvalidString { lossy, .. }, DataType::Text) => Data::Text(lossy.clone()),
+                (ReadError::InvalidString { lossy, .. }, DataType::LongText) => Data::LongText(lossy.clone()),
+                _ => Data::Other(column.column_type.into(), raw_value.to_vec()),
+            };
+            errors.push(ColumnDecodeError {
+                column_id: column.column_id,
+                error: e.in_table(column.table_object_id),
+            });
+            Ok(data)
+        },
+    }
+}
+
+/// Collects a problem with locating the value of a tagged column while decoding in lenient mode.
+fn push_tagged_column_error(
+    errors: &mut Vec<ColumnDecodeError>,
+    tagged_columns: &BTreeMap<i32, &Column>,
+    tag: u16,
+    error: ReadError,
+) {
+    let column_id: i32 = tag.into();
+    let error = match tagged_columns.get(&column_id) {
+        Some(column) => error.in_table(column.table_object_id),
+        None => error,
+    };
+    errors.push(ColumnDecodeError {
+        column_id,
+        error,
+    });
+}
+
+fn get_name_to_column(columns: &[Column]) -> BTreeMap<&str, &Column> {
+    columns
+        .iter()
+        .map(|col| (col.name.as_str(), col))
+        .collect()
+}
+
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_table_from_pages<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+) -> Result<Vec<BTreeMap<i32, Value>>, ReadError> {
+    let decoded = read_table_from_pages_with_options(
+        source, header, page_number, columns, large_value_page_number, &DecodeOptions::default(),
+    )?;
+    Ok(decoded.rows)
+}
+
+/// Reads the rows of the table whose tree starts at the given page, decoding them according to
+/// the given options.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_table_from_pages_with_options<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+    options: &DecodeOptions,
+) -> Result<DecodedRows, ReadError> {
+    let mut rows = Vec::new();
+    let warnings = visit_table_rows(
+        source, header, page_number, columns, large_value_page_number, options,
+        |_key, _raw, row| {
+            rows.push(row);
+            ControlFlow::Continue(())
+        },
+    )?;
+    Ok(DecodedRows {
+        rows,
+        warnings,
+    })
+}
+
+/// Reads the rows of the table whose tree starts at the given page like [`read_table_from_pages`],
+/// additionally returning the primary key and the bytes of the record from which each row was
+/// decoded.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_raw_rows_from_pages<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+) -> Result<Vec<RawRow>, ReadError> {
+    let mut rows = Vec::new();
+    visit_table_rows(
+        source, header, page_number, columns, large_value_page_number, &DecodeOptions::default(),
+        |key, raw, values| {
+            rows.push(RawRow {
+                key,
+                raw,
+                values,
+            });
+            ControlFlow::Continue(())
+        },
+    )?;
+    Ok(rows)
+}
+
+/// Whether records on a page with the given flags are stored in the legacy record format.
+///
+/// Databases with format 0x620 up to revision 2 (Exchange 2000 and Windows 2000/XP era, as well as
+/// databases upgraded from them) store records in a legacy format that differs in the layout of
+/// tagged columns. The database engine converts these records lazily and marks converted pages
+/// with [`PageFlags::NEW_RECORD_FORMAT`]. Decoding legacy records is not supported.
+pub fn is_legacy_record_format(header: &Header, page_flags: PageFlags) -> bool {
+    header.version_and_revision() <= 0x0000_0620_0000_0002
+        && !page_flags.contains(PageFlags::NEW_RECORD_FORMAT)
+}
+
+/// Reads the rows of the table whose tree starts at the given page like [`read_table_from_pages`],
+/// but decodes them in parallel.
+///
+/// The tree is walked sequentially, collecting the raw data of all rows, which are then decoded on
+/// the rayon thread pool. Since positional reads do not share a cursor, all workers read separately
+/// stored long values from the same source. The rows are returned in the same order as by
+/// [`read_table_from_pages`].
+///
+/// Whether this pays off depends on the table:
+///
+/// * Only decoding is spread across threads. Walking the tree takes up most of the time for tables
+///   with small rows, which therefore gain little; rows with many columns, compressed values or
+///   separated long values gain the most.
+/// * The raw data of all rows is kept in memory in addition to the decoded rows, roughly doubling
+///   the peak memory use.
+/// * Even on a single core, it is not slower than [`read_table_from_pages`]: decoding the 640000 rows
+///   of the synthetic table of the `mmap` benchmark from memory took 0.96 s instead of 1.35 s.
+/// * If several rows fail to decode, the error returned is not necessarily that of the first one.
+#[cfg(feature = "parallel")]
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_table_from_pages_parallel<S: DbSource + Sync + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
 ) -> Result<Vec<BTreeMap<i32, Value>>, ReadError> {
+    use rayon::prelude::*;
+
+    // remember the page of each row so that decoding errors can point to it
     let mut raw_rows = Vec::new();
-    let mut skip_index = 0;
-    read_data_from_tree(reader, header, page_number, 0, usize::MAX, &mut raw_rows, &mut skip_index)?;
+    read_keyed_data_from_tree_with_source(source, header, page_number, &mut |_source, leaf| {
+        if is_legacy_record_format(header, leaf.page_flags) {
+            return Err(ReadError::UnsupportedRecordFormat.at_page(leaf.page_number));
+        }
+        raw_rows.push((leaf.page_number, leaf.data));
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    let decoded_columns = DecodedColumns::new(columns);
+    raw_rows.into_par_iter()
+        .map(|(leaf_page_number, raw_row)| {
+            decode_row_with(source, header, &raw_row, &decoded_columns, header.page_size, large_value_page_number)
+                .map_err(|e| e.at_page(leaf_page_number))
+        })
+        .collect()
+}
+
+/// Decodes the rows of the table whose tree starts at the given page and passes each of them to
+/// `f`, in key order. The walk stops early if `f` returns [`ControlFlow::Break`].
+///
+/// Unlike [`read_table_from_pages`], at most one decoded row is kept in memory at a time.
+#[instrument(skip(source, header, f), fields(header.page_number, header.version, header.revision))]
+pub fn for_each_row<S: DbSource + ?Sized, F: FnMut(&BTreeMap<i32, Value>) -> ControlFlow<()>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+    mut f: F,
+) -> Result<(), ReadError> {
+    visit_table_rows(
+        source, header, page_number, columns, large_value_page_number, &DecodeOptions::default(),
+        |_key, _raw, row| f(&row),
+    )?;
+    Ok(())
+}
+
+fn visit_table_rows<S: DbSource + ?Sized, F: FnMut(Vec<u8>, Vec<u8>, BTreeMap<i32, Value>) -> ControlFlow<()>>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    columns: &[Column],
+    large_value_page_number: Option<u64>,
+    options: &DecodeOptions,
+    mut f: F,
+) -> Result<Vec<ReadError>, ReadError> {
+    let decoded_columns = DecodedColumns::new(columns);
+    let mut warnings = Vec::new();
+    let walk = if options.verify_page_ownership {
+        read_keyed_data_from_tree_verifying_owner
+    } else {
+        read_keyed_data_from_tree_with_source
+    };
+    walk(source, header, page_number, &mut |source, leaf| {
+        if is_legacy_record_format(header, leaf.page_flags) {
+            return Err(ReadError::UnsupportedRecordFormat.at_page(leaf.page_number));
+        }
+
+        // point decoding errors and warnings to the page of the row
+        let row = decode_row_with_options(
+            source, header, &leaf.data, &decoded_columns, header.page_size, large_value_page_number, options,
+        )
+            .map_err(|e| e.at_page(leaf.page_number))?;
+        warnings.extend(row.errors.into_iter().map(|e| e.error.at_page(leaf.page_number)));
+        trace!(row = ?row.values);
+        Ok(f(leaf.key, leaf.data, row.values))
+    })?;
+    Ok(warnings)
+}
+
+/// Salvages the rows of the given table that are stored on leaf pages which are no longer linked
+/// into its tree.
+///
+/// Every page of the file is examined; data leaf pages that belong to the table (according to
+/// their father data page object ID) but are not reached by walking the table's tree are decoded.
+/// Since such pages are usually found in damaged databases, problems with individual pages and
+/// rows are collected as warnings instead of ending the scan.
+#[instrument(skip(source, header, table), fields(header.page_number, header.version, header.revision))]
+pub fn recover_orphan_records<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    table: &Table,
+    options: &DecodeOptions,
+) -> Result<DecodedRows, ReadError> {
+    // without the object ID, the pages of the table cannot be told apart from those of others
+    let fdp_object_id: u32 = ReadError::catalog_value("table FDP object ID", table.header.fdp_object_id)
+        .map_err(|e| e.in_table(table.header.table_object_id))?;
+
+    let mut warnings = Vec::new();
+    let long_value_page_number = table.long_value_page_number()
+        .unwrap_or_else(|e| {
+            warnings.push(e);
+            None
+        });
 
-    let mut rows = Vec::with_capacity(raw_rows.len());
-    for raw_row in raw_rows {
-        let row = decode_row(reader, header, &raw_row, columns, header.page_size, large_value_page_number)?;
-        trace!(?row);
-        rows.push(row);
+    // the tree may be damaged; whatever part of it can be walked is not orphaned
+    let mut linked_pages = BTreeSet::new();
+    match table.fdp_page_number() {
+        Ok(fdp_page_number) => {
+            let _ = read_keyed_data_from_tree(source, header, fdp_page_number, &mut |leaf| {
+                linked_pages.insert(leaf.page_number);
+                ControlFlow::Continue(())
+            });
+        },
+        Err(e) => warnings.push(e),
     }
 
-    Ok(rows)
+    let decoded_columns = DecodedColumns::new(&table.columns);
+    let non_data_flags = PageFlags::SPACE_TREE_PAGE | PageFlags::INDEX_PAGE | PageFlags::LONG_VALUE_PAGE;
+    let mut rows = Vec::new();
+    let mut scratch = Vec::new();
+    let mut scan = PageScan::new(source, header)?;
+    while let Some((page_number, page_header_res)) = scan.next() {
+        let page_header = match page_header_res {
+            Ok(ph) => ph,
+            Err(e) => {
+                warnings.push(e);
+                continue;
+            },
+        };
+        if !page_header.is_usable()
+                || !page_header.flags.contains(PageFlags::LEAF_PAGE)
+                || page_header.flags.intersects(non_data_flags)
+                || page_header.father_data_page_oid != fdp_object_id
+                || linked_pages.contains(&page_number) {
+            continue;
+        }
+        if is_legacy_record_format(header, page_header.flags) {
+            warnings.push(ReadError::UnsupportedRecordFormat.at_page(page_number));
+            continue;
+        }
+
+        let source = scan.source();
+        let page_tags = match read_page_tags(source, header.page_size, &page_header) {
+            Ok(pt) => pt,
+            Err(e) => {
+                warnings.push(e.at_page(page_number));
+                continue;
+            },
+        };
+        for page_tag in page_tags.iter().skip(1) {
+            let leaf = match read_page_entry_with_buffer(source, header.page_size, &page_header, page_tag, &mut scratch) {
+                Ok(PageEntry::Leaf(leaf)) => leaf,
+                Ok(_) => continue,
+                Err(e) => {
+                    warnings.push(e.at_page(page_number));
+                    continue;
+                },
+            };
+            let decoded = decode_row_with_options(
+                source, header, &leaf.entry_data, &decoded_columns, header.page_size, long_value_page_number,
+                options,
+            );
+            match decoded {
+                Ok(row) => {
+                    rows.push(row.values);
+                    warnings.extend(row.errors.into_iter().map(|e| e.error.at_page(page_number)));
+                },
+                Err(e) => warnings.push(e.at_page(page_number)),
+            }
+        }
+    }
+
+    Ok(DecodedRows {
+        rows,
+        warnings,
+    })
+}
+
+/// Counts the rows of the table whose tree starts at the given page without decoding them.
+///
+/// If `exclude_deleted` is set, entries flagged as deleted are not counted.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn count_table_rows<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    page_number: u64,
+    exclude_deleted: bool,
+) -> Result<u64, ReadError> {
+    let mut count = 0;
+    read_keyed_data_from_tree(source, header, page_number, &mut |leaf| {
+        if !(exclude_deleted && leaf.tag_flags.contains(PageTagFlags::DELETED)) {
+            count += 1;
+        }
+        ControlFlow::Continue(())
+    })?;
+    Ok(count)
+}
+
+/// Walks the tree of the given index and returns the keys of the records it references, in index
+/// order, skipping entries flagged as deleted.
+///
+/// The keys are normalized (the form in which ESE compares them) and cannot generally be decoded
+/// back into column values.
+#[instrument(skip(source, header), fields(header.page_number, header.version, header.revision))]
+pub fn read_index_entries<S: DbSource + ?Sized>(
+    source: &S,
+    header: &Header,
+    index: &Index,
+) -> Result<Vec<Vec<u8>>, ReadError> {
+    let mut keys = Vec::new();
+    read_keyed_data_from_tree(source, header, index.fdp_page_number()?, &mut |leaf| {
+        if !leaf.tag_flags.contains(PageTagFlags::DELETED) {
+            keys.push(leaf.key);
+        }
+        ControlFlow::Continue(())
+    })?;
+    Ok(keys)
 }
 
 #[instrument]
@@ -957,6 +2388,7 @@ pub fn collect_tables(rows: &[BTreeMap<i32, Value>], metadata_columns: &[Column]
     let mut table_number_to_columns: BTreeMap<i32, Vec<Column>> = BTreeMap::new();
     let mut table_number_to_indexes: BTreeMap<i32, Vec<Index>> = BTreeMap::new();
     let mut table_number_to_long_value: BTreeMap<i32, LongValueInfo> = BTreeMap::new();
+    let mut table_number_to_callbacks: BTreeMap<i32, Vec<Callback>> = BTreeMap::new();
 
     for row in rows {
         let type_value_i16 = *get_value!(@required, name_to_column, row, "Type", Short);
@@ -984,6 +2416,13 @@ pub fn collect_tables(rows: &[BTreeMap<i32, Value>], metadata_columns: &[Column]
                 let long_value = LongValueInfo::try_from_metadata(metadata_columns, row)?;
                 table_number_to_long_value.insert(long_value.table_object_id, long_value);
             },
+            ObjectType::Callback => {
+                let callback = Callback::try_from_metadata(metadata_columns, row)?;
+                table_number_to_callbacks
+                    .entry(callback.table_object_id)
+                    .or_default()
+                    .push(callback);
+            },
             _ => {
                 // currently unhandled...
             },
@@ -1001,16 +2440,874 @@ pub fn collect_tables(rows: &[BTreeMap<i32, Value>], metadata_columns: &[Column]
     for (_, header) in table_number_to_header {
         let columns = table_number_to_columns.remove(&header.table_object_id)
             .unwrap_or_else(|| Vec::with_capacity(0));
-        let indexes = table_number_to_indexes.remove(&header.table_object_id)
+        let mut indexes = table_number_to_indexes.remove(&header.table_object_id)
             .unwrap_or_else(|| Vec::with_capacity(0));
+        for index in &mut indexes {
+            if let Some(key_field_ids) = &index.key_field_ids {
+                index.key_segments = IndexSegment::parse_key_field_ids(key_field_ids, &columns);
+            }
+        }
         let long_value = table_number_to_long_value.remove(&header.table_object_id);
+        let callbacks = table_number_to_callbacks.remove(&header.table_object_id)
+            .unwrap_or_else(|| Vec::with_capacity(0));
         tables.push(Table {
             header,
             columns,
             indexes,
             long_value,
+            callbacks,
         });
     }
 
+    inherit_template_columns(&mut tables);
+
     Ok(tables)
 }
+
+/// Adds the columns of template tables to the tables derived from them.
+///
+/// The catalog only lists the columns a derived table adds to its template, but the records of the
+/// derived table also contain the values of the template's columns. The column IDs of the added
+/// columns follow those of the template's columns, so both sets can be merged as they are; should
+/// the IDs collide anyway, the column of the derived table takes precedence.
+///
+/// If the template has [`ObjectFlags::NO_FIXED_VAR_COLUMNS_IN_DERIVED_TABLES`], the derived table
+/// only adds tagged columns and all fixed and variable columns belong to the template, which may
+/// have gained them after the derived table was created. Fixed and variable columns of the template
+/// then take precedence over colliding ones of the derived table.
+fn inherit_template_columns(tables: &mut [Table]) {
+    let template_name_to_flags_columns: BTreeMap<String, (ObjectFlags, Vec<Column>)> = tables.iter()
+        .filter(|t| t.header.flags.contains(ObjectFlags::TABLE_TEMPLATE))
+        .map(|t| (t.header.name.clone(), (t.header.flags, t.columns.clone())))
+        .collect();
+
+    for table in tables {
+        if !table.header.flags.contains(ObjectFlags::TABLE_DERIVED) {
+            continue;
+        }
+        let Some(template_name) = &table.header.template_name else { continue };
+        let Some((template_flags, template_columns)) = template_name_to_flags_columns.get(template_name) else {
+            debug!(table = ?table.header.name, template = ?template_name, "template table not found");
+            continue;
+        };
+        let template_owns_fixed_var = template_flags.contains(ObjectFlags::NO_FIXED_VAR_COLUMNS_IN_DERIVED_TABLES);
+
+        for template_column in template_columns {
+            let existing_index = table.columns.iter()
+                .position(|c| c.column_id == template_column.column_id);
+            match existing_index {
+                None => table.columns.push(template_column.clone()),
+                Some(index) if template_owns_fixed_var && template_column.column_id < 256 => {
+                    debug!(
+                        table = ?table.header.name, column_id = template_column.column_id,
+                        "derived table has a fixed or variable column of its own despite its template forbidding it",
+                    );
+                    table.columns[index] = template_column.clone();
+                },
+                Some(_) => {
+                    debug!(
+                        table = ?table.header.name, column_id = template_column.column_id,
+                        "column ID of derived table collides with its template",
+                    );
+                },
+            }
+        }
+        table.columns.sort_unstable_by_key(|c| c.column_id);
+    }
+}
+
+/// Collects the tables like [`collect_tables`], but skips the internal tables of the database
+/// engine (see [`Table::is_system`]).
+#[instrument]
+pub fn collect_user_tables(rows: &[BTreeMap<i32, Value>], metadata_columns: &[Column]) -> Result<Vec<Table>, ReadError> {
+    let mut tables = collect_tables(rows, metadata_columns)?;
+    tables.retain(|t| !t.is_system());
+    Ok(tables)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{branch_entry, leaf_entry, RecordingSource, TestDb};
+
+    /// The data of the root of a long value: reference count and total size.
+    fn long_value_root(size: u32) -> Vec<u8> {
+        let mut data = 1u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    /// The key of a chunk of a long value: the big-endian long value ID and byte offset.
+    fn long_value_chunk_key(id: u32, offset: u32) -> Vec<u8> {
+        let mut key = id.to_be_bytes().to_vec();
+        key.extend_from_slice(&offset.to_be_bytes());
+        key
+    }
+
+    /// Builds a long value tree rooted at page 10 with the long values 1 and 2 on leaf pages 11 and 12.
+    fn long_value_tree_db(first_value: &[u8], second_value: &[u8]) -> TestDb {
+        let mut db = TestDb::new();
+        let lv_flags = PageFlags::LONG_VALUE_PAGE;
+        db.add_page(10, PageFlags::ROOT_PAGE | lv_flags, &[
+            vec![0u8; 16],
+            branch_entry(&long_value_chunk_key(1, 0), 11),
+            branch_entry(&[], 12),
+        ]);
+        db.add_page(11, PageFlags::LEAF_PAGE | lv_flags, &[
+            Vec::new(),
+            leaf_entry(&1u32.to_be_bytes(), &long_value_root(first_value.len().try_into().unwrap())),
+            leaf_entry(&long_value_chunk_key(1, 0), first_value),
+        ]);
+        db.add_page(12, PageFlags::LEAF_PAGE | lv_flags, &[
+            Vec::new(),
+            leaf_entry(&2u32.to_be_bytes(), &long_value_root(second_value.len().try_into().unwrap())),
+            leaf_entry(&long_value_chunk_key(2, 0), second_value),
+        ]);
+        db
+    }
+
+    fn test_column(column_id: i32, column_type: DataType, length: i32) -> Column {
+        Column {
+            table_object_id: 100,
+            column_id,
+            column_type,
+            length,
+            flags: ColumnFlags::empty(),
+            codepage: DEFAULT_CODEPAGE,
+            root_flag: None,
+            record_offset: None,
+            name: format!("column{}", column_id),
+            default_value: None,
+        }
+    }
+
+    #[test]
+    fn record_without_variable_columns() {
+        let columns = [
+            Column { record_offset: Some(4), ..test_column(1, DataType::Long, 4) },
+            test_column(256, DataType::LongText, 0),
+        ];
+        let record = [
+            1, 127, 9, 0, // header: last fixed column 1, no variable columns, end of fixed values
+            5, 0, 0, 0, // column 1
+            0b0, // nullity bitmap
+            0x00, 0x01, 0x04, 0x00, // tag of column 256, value at offset 4 of the tagged area
+            b'h', b'i',
+        ];
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let decoded = decode_row_with(source, &header, &record, &DecodedColumns::new(&columns), 8 * 1024, None).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[&1], Value::Simple(Data::Long(5)));
+        assert_eq!(decoded[&256].single(), Some(&Data::LongText("hi".to_owned())));
+
+        let encoded = crate::encode::encode_row(&decoded, &columns, 8 * 1024).unwrap();
+        assert_eq!(encoded, record);
+    }
+
+    /// A record with the variable columns 128 and 129 whose values end at the given offsets.
+    fn variable_record(first_end: u16, second_end: u16, data: &[u8]) -> Vec<u8> {
+        let mut record = vec![0, 129, 4, 0];
+        record.extend_from_slice(&first_end.to_le_bytes());
+        record.extend_from_slice(&second_end.to_le_bytes());
+        record.extend_from_slice(data);
+        record
+    }
+
+    fn decode_variable_record(record: &[u8], strict: bool) -> Result<DecodedRow, ReadError> {
+        let columns = [
+            test_column(128, DataType::Binary, 0),
+            test_column(129, DataType::Binary, 0),
+        ];
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let options = DecodeOptions {
+            strict,
+            ..DecodeOptions::default()
+        };
+        decode_row_with_options(source, &header, record, &DecodedColumns::new(&columns), 8 * 1024, None, &options)
+    }
+
+    #[test]
+    fn reversed_variable_offsets() {
+        let record = variable_record(4, 2, b"abcd");
+
+        let error = decode_variable_record(&record, true).unwrap_err();
+        assert!(matches!(error, ReadError::MalformedRecord { reason } if reason == "non-monotonic variable offsets"));
+
+        // only the value whose end precedes its start is lost
+        let row = decode_variable_record(&record, false).unwrap();
+        assert_eq!(row.values.len(), 1);
+        assert_eq!(row.values[&128], Value::Simple(Data::Binary(b"abcd".to_vec())));
+        assert_eq!(row.errors.len(), 1);
+        assert_eq!(row.errors[0].column_id, 129);
+    }
+
+    #[test]
+    fn variable_offset_out_of_bounds() {
+        let record = variable_record(2, 100, b"abcd");
+
+        let error = decode_variable_record(&record, true).unwrap_err();
+        assert!(matches!(error, ReadError::MalformedRecord { reason } if reason == "variable column 129 offset 100 out of bounds"));
+
+        let row = decode_variable_record(&record, false).unwrap();
+        assert_eq!(row.values.len(), 1);
+        assert_eq!(row.values[&128], Value::Simple(Data::Binary(b"ab".to_vec())));
+        assert_eq!(row.errors.len(), 1);
+        assert_eq!(row.errors[0].column_id, 129);
+    }
+
+    #[test]
+    fn tagged_offset_into_tags() {
+        let columns = [
+            test_column(256, DataType::LongText, 0),
+            test_column(257, DataType::LongText, 0),
+        ];
+        let record = [
+            0, 127, 4, 0, // header: no fixed and no variable columns
+            0x00, 0x01, 0x08, 0x00, // tag of column 256, value at offset 8 of the tagged area
+            0x01, 0x01, 0x02, 0x00, // tag of column 257, value at offset 2 (within the tags)
+            b'h', b'i',
+        ];
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let decode = |strict| {
+            let options = DecodeOptions {
+                strict,
+                ..DecodeOptions::default()
+            };
+            decode_row_with_options(source, &header, &record, &DecodedColumns::new(&columns), 8 * 1024, None, &options)
+        };
+
+        let error = decode(true).unwrap_err();
+        assert!(matches!(error, ReadError::MalformedRecord { reason } if reason == "tagged column 257 offset 2 out of bounds"));
+
+        let row = decode(false).unwrap();
+        assert_eq!(row.values.len(), 1);
+        assert_eq!(row.values[&256].single(), Some(&Data::LongText("hi".to_owned())));
+        assert_eq!(row.errors.len(), 1);
+        assert_eq!(row.errors[0].column_id, 257);
+    }
+
+    #[test]
+    fn fixed_columns_with_gaps_round_trip() {
+        // the template of a derived table can leave a gap between fixed values (bytes 8 to 11)
+        let mut tables = [
+            test_table("Template", ObjectFlags::TABLE_TEMPLATE, None, vec![
+                Column { record_offset: Some(4), ..test_column(1, DataType::Long, 4) },
+                Column { record_offset: Some(12), ..test_column(2, DataType::Short, 2) },
+            ]),
+            test_table("Derived", ObjectFlags::TABLE_DERIVED, Some("Template"), vec![
+                Column { record_offset: Some(14), ..test_column(3, DataType::Long, 4) },
+            ]),
+        ];
+        inherit_template_columns(&mut tables);
+        let columns = &tables[1].columns;
+        let mut values = BTreeMap::new();
+        values.insert(1, Value::Simple(Data::Long(42)));
+        values.insert(2, Value::Simple(Data::Short(7)));
+        values.insert(3, Value::Simple(Data::Long(9)));
+
+        let record = crate::encode::encode_row(&values, columns, 8 * 1024).unwrap();
+        assert_eq!(record, [
+            3, 127, 19, 0, // header: last fixed column, no variable columns, end of fixed values
+            42, 0, 0, 0, // column 1 at offset 4
+            0, 0, 0, 0, // gap
+            7, 0, // column 2 at offset 12
+            9, 0, 0, 0, // column 3 at offset 14
+            0b000, // nullity bitmap
+        ]);
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let decoded = decode_row_with(source, &header, &record, &DecodedColumns::new(columns), 8 * 1024, None).unwrap();
+        assert_eq!(decoded, values);
+    }
+
+    fn test_table(name: &str, flags: ObjectFlags, template_name: Option<&str>, columns: Vec<Column>) -> Table {
+        Table {
+            header: TableHeader {
+                table_object_id: columns.first().map(|c| c.table_object_id).unwrap_or(100),
+                fdp_object_id: 0,
+                fdp_page_number: 0,
+                density: 0,
+                flags,
+                page_count: 0,
+                root_flag: None,
+                name: name.to_owned(),
+                template_name: template_name.map(|n| n.to_owned()),
+            },
+            columns,
+            indexes: Vec::new(),
+            long_value: None,
+            callbacks: Vec::new(),
+        }
+    }
+
+    fn named_column(column_id: i32, column_type: DataType, name: &str) -> Column {
+        Column {
+            name: name.to_owned(),
+            ..test_column(column_id, column_type, 0)
+        }
+    }
+
+    fn column_names(table: &Table) -> Vec<(i32, &str)> {
+        table.columns.iter()
+            .map(|c| (c.column_id, c.name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn derived_table_inherits_template_columns() {
+        let mut tables = [
+            test_table("Template", ObjectFlags::TABLE_TEMPLATE, None, vec![
+                named_column(1, DataType::Long, "TemplateFixed"),
+                named_column(256, DataType::LongText, "TemplateTagged"),
+            ]),
+            test_table("Derived", ObjectFlags::TABLE_DERIVED, Some("Template"), vec![
+                named_column(2, DataType::Long, "DerivedFixed"),
+                named_column(257, DataType::LongText, "DerivedTagged"),
+            ]),
+        ];
+        inherit_template_columns(&mut tables);
+
+        assert_eq!(column_names(&tables[0]), [(1, "TemplateFixed"), (256, "TemplateTagged")]);
+        assert_eq!(
+            column_names(&tables[1]),
+            [(1, "TemplateFixed"), (2, "DerivedFixed"), (256, "TemplateTagged"), (257, "DerivedTagged")],
+        );
+    }
+
+    #[test]
+    fn template_without_fixed_var_columns_in_derived_tables_owns_them() {
+        let mut tables = [
+            test_table(
+                "Template",
+                ObjectFlags::TABLE_TEMPLATE | ObjectFlags::NO_FIXED_VAR_COLUMNS_IN_DERIVED_TABLES,
+                None,
+                vec![
+                    named_column(1, DataType::Long, "TemplateFixed"),
+                    named_column(128, DataType::Text, "TemplateVariable"),
+                    named_column(256, DataType::LongText, "TemplateTagged"),
+                ],
+            ),
+            test_table("Derived", ObjectFlags::TABLE_DERIVED, Some("Template"), vec![
+                named_column(1, DataType::Long, "DerivedFixed"),
+                named_column(128, DataType::Text, "DerivedVariable"),
+                named_column(256, DataType::LongText, "DerivedTagged"),
+                named_column(257, DataType::LongText, "DerivedTagged2"),
+            ]),
+        ];
+        inherit_template_columns(&mut tables);
+
+        // fixed and variable columns come from the template, tagged columns from the derived table
+        assert_eq!(
+            column_names(&tables[1]),
+            [(1, "TemplateFixed"), (128, "TemplateVariable"), (256, "DerivedTagged"), (257, "DerivedTagged2")],
+        );
+    }
+
+    /// A catalog row describing a column of the given type, with the given default value.
+    fn catalog_column_row(column_type: DataType, default_value: &[u8]) -> BTreeMap<i32, Value> {
+        let mut row = BTreeMap::new();
+        row.insert(1, Value::Simple(Data::Long(100)));
+        row.insert(2, Value::Simple(Data::Short(ObjectType::Column.to_base_type())));
+        row.insert(3, Value::Simple(Data::Long(1)));
+        row.insert(4, Value::Simple(Data::Long(column_type.to_base_type())));
+        row.insert(5, Value::Simple(Data::Long(4)));
+        row.insert(6, Value::Simple(Data::Long(0)));
+        row.insert(7, Value::Simple(Data::Long(DEFAULT_CODEPAGE)));
+        row.insert(128, Value::Simple(Data::Text("Answer".to_owned())));
+        row.insert(256, Value::Simple(Data::LongBinary(default_value.to_vec())));
+        row
+    }
+
+    /// The definitions of the catalog columns, including the tagged default value column.
+    fn catalog_column_defs() -> Vec<Column> {
+        let mut column_defs = METADATA_COLUMN_DEFS.to_vec();
+        column_defs.push(Column {
+            name: "DefaultValue".to_owned(),
+            ..test_column(256, DataType::LongBinary, 0)
+        });
+        column_defs
+    }
+
+    #[test]
+    fn column_default_value_is_decoded() {
+        let row = catalog_column_row(DataType::Long, &42i32.to_le_bytes());
+        let column = Column::try_from_metadata(&catalog_column_defs(), &row).unwrap();
+        assert_eq!(column.name, "Answer");
+        assert_eq!(column.default_value, Some(Data::Long(42)));
+    }
+
+    #[test]
+    fn malformed_column_default_value_is_ignored() {
+        // three bytes cannot be a Long
+        let row = catalog_column_row(DataType::Long, &[1, 2, 3]);
+        let column = Column::try_from_metadata(&catalog_column_defs(), &row).unwrap();
+        assert_eq!(column.column_type, DataType::Long);
+        assert_eq!(column.default_value, None);
+    }
+
+    /// A catalog row describing an index whose key columns are stored in the given values of the
+    /// tagged `KeyFldIDs` column.
+    fn catalog_index_row(key_field_ids: &[&[u8]]) -> BTreeMap<i32, Value> {
+        let mut row = BTreeMap::new();
+        row.insert(1, Value::Simple(Data::Long(100)));
+        row.insert(2, Value::Simple(Data::Short(ObjectType::Index.to_base_type())));
+        row.insert(3, Value::Simple(Data::Long(1)));
+        row.insert(4, Value::Simple(Data::Long(12)));
+        row.insert(5, Value::Simple(Data::Long(1)));
+        row.insert(6, Value::Simple(Data::Long(0)));
+        row.insert(7, Value::Simple(Data::Long(1033)));
+        row.insert(128, Value::Simple(Data::Text("ByKey".to_owned())));
+        let values: Vec<Data> = key_field_ids.iter()
+            .map(|ids| Data::LongBinary(ids.to_vec()))
+            .collect();
+        row.insert(256, Value::Multiple {
+            values: values.into(),
+            flags: TagFlags::MULTI_VALUES,
+            compression: None,
+        });
+        row
+    }
+
+    fn catalog_index_defs() -> Vec<Column> {
+        let mut column_defs = METADATA_COLUMN_DEFS.to_vec();
+        column_defs.push(Column {
+            name: "KeyFldIDs".to_owned(),
+            ..test_column(256, DataType::LongBinary, 0)
+        });
+        column_defs
+    }
+
+    #[test]
+    fn index_key_segments_are_decoded() {
+        let columns = [
+            test_column(1, DataType::Long, 4),
+            test_column(256, DataType::LongText, 0),
+        ];
+        // column 1 ascending, then column 256 descending; split across two values
+        let row = catalog_index_row(&[&[0x00, 0x00, 0x01, 0x00], &[0x01, 0x00, 0x00, 0x01]]);
+        let index = Index::try_from_metadata(&catalog_index_defs(), &row).unwrap();
+        assert_eq!(index.name, "ByKey");
+        assert_eq!(index.key_field_ids.as_deref(), Some(&[0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x01][..]));
+
+        let segments = IndexSegment::parse_key_field_ids(index.key_field_ids.as_ref().unwrap(), &columns);
+        assert_eq!(segments, [
+            IndexSegment { column_id: 1, descending: false },
+            IndexSegment { column_id: 256, descending: true },
+        ]);
+    }
+
+    #[test]
+    fn legacy_index_key_segments_are_decoded() {
+        let columns = [
+            test_column(1, DataType::Long, 4),
+            test_column(2, DataType::Long, 4),
+            test_column(256, DataType::LongText, 0),
+        ];
+        // column 1 ascending and column 256 descending as signed 16-bit IDs; read as 4-byte
+        // segments, they would refer to the unknown column 0xFF00
+        let key_field_ids = [0x01, 0x00, 0x00, 0xFF];
+        let segments = IndexSegment::parse_key_field_ids(&key_field_ids, &columns);
+        assert_eq!(segments, [
+            IndexSegment { column_id: 1, descending: false },
+            IndexSegment { column_id: 256, descending: true },
+        ]);
+    }
+
+    fn catalog_table_row(table_object_id: i32, name: &str, flags: ObjectFlags) -> BTreeMap<i32, Value> {
+        let mut row = BTreeMap::new();
+        row.insert(1, Value::Simple(Data::Long(table_object_id)));
+        row.insert(2, Value::Simple(Data::Short(ObjectType::Table.to_base_type())));
+        row.insert(3, Value::Simple(Data::Long(table_object_id)));
+        row.insert(4, Value::Simple(Data::Long(4 + table_object_id)));
+        row.insert(5, Value::Simple(Data::Long(80)));
+        row.insert(6, Value::Simple(Data::Long(flags.bits())));
+        row.insert(7, Value::Simple(Data::Long(1)));
+        row.insert(128, Value::Simple(Data::Text(name.to_owned())));
+        row
+    }
+
+    #[test]
+    fn user_tables_skip_system_tables_but_keep_templates() {
+        let rows = [
+            catalog_table_row(2, "MSysObjects", ObjectFlags::empty()),
+            catalog_table_row(3, "MSysLocales", ObjectFlags::SYSTEM),
+            catalog_table_row(4, "Internal", ObjectFlags::SYSTEM),
+            catalog_table_row(5, "Mailboxes", ObjectFlags::empty()),
+            catalog_table_row(6, "FolderTemplate", ObjectFlags::TABLE_TEMPLATE),
+            catalog_table_row(7, "Folder", ObjectFlags::TABLE_DERIVED),
+        ];
+        let tables = collect_user_tables(&rows, &*METADATA_COLUMN_DEFS).unwrap();
+        let names: Vec<&str> = tables.iter()
+            .map(|t| t.header.name.as_str())
+            .collect();
+        assert_eq!(names, ["Mailboxes", "FolderTemplate", "Folder"]);
+    }
+
+    #[test]
+    fn written_values_decode_to_same_data() {
+        let values = [
+            Data::Bit(Bit(0xFF)),
+            Data::UnsignedByte(0xAB),
+            Data::Short(-2),
+            Data::Long(-70000),
+            Data::Currency(123_4567),
+            Data::IeeeSingle(1.5),
+            Data::IeeeDouble(-0.25),
+            Data::DateTime(0x4000_0000_0000_0000),
+            Data::Text("Gr\u{FC}\u{DF}e".to_owned()),
+            Data::LongText("long text".to_owned()),
+            Data::UnsignedLong(0xDEAD_BEEF),
+            Data::LongLong(-1),
+            Data::Guid(Uuid::from_u128(0x00112233_4455_6677_8899_AABBCCDDEEFF)),
+            Data::UnsignedShort(0xFFFE),
+            Data::Binary(vec![0x00, 0x01, 0x02]),
+            Data::LongBinary(vec![0xFF; 300]),
+        ];
+        for data in values {
+            let column = test_column(256, data.data_type(), 0);
+            let mut bytes = Vec::new();
+            data.write_value_to_bytes(&mut bytes, DEFAULT_CODEPAGE).unwrap();
+            let decoded = decode_value_bytes(&bytes, &column, DEFAULT_CODEPAGE).unwrap();
+            assert_eq!(decoded, data);
+        }
+    }
+
+    #[test]
+    fn written_guid_uses_windows_layout() {
+        let data = Data::Guid(Uuid::from_u128(0x00112233_4455_6677_8899_AABBCCDDEEFF));
+        let mut bytes = Vec::new();
+        data.write_value_to_bytes(&mut bytes, DEFAULT_CODEPAGE).unwrap();
+        assert_eq!(bytes, [
+            0x33, 0x22, 0x11, 0x00, 0x55, 0x44, 0x77, 0x66,
+            0x88, 0x99, 0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF,
+        ]);
+    }
+
+    #[test]
+    fn encoded_strings_decode_to_same_string() {
+        let cases = [
+            ("plain ASCII", 1252),
+            ("Gr\u{FC}\u{DF}e", 1252),
+            ("Gr\u{FC}\u{DF}e", 28591),
+            ("\u{65E5}\u{672C}\u{1F600}", 1200),
+        ];
+        for (string, codepage) in cases {
+            let bytes = encode_string(string, codepage).unwrap();
+            assert_eq!(decode_string(&bytes, codepage).unwrap(), string);
+        }
+    }
+
+    #[test]
+    fn utf16_strings_are_encoded_with_surrogates() {
+        // U+1F600 is encoded as the surrogate pair D83D DE00
+        assert_eq!(
+            encode_string("a\u{20AC}\u{1F600}", 1200).unwrap(),
+            [0x61, 0x00, 0xAC, 0x20, 0x3D, 0xD8, 0x00, 0xDE],
+        );
+        assert_eq!(
+            encode_string("a\u{20AC}", 1201).unwrap(),
+            [0x00, 0x61, 0x20, 0xAC],
+        );
+    }
+
+    #[test]
+    fn unrepresentable_strings_are_rejected() {
+        assert!(matches!(
+            encode_string("\u{65E5}\u{672C}", 1252),
+            Err(WriteError::StringNotRepresentable { codepage: 1252 }),
+        ));
+        assert!(matches!(
+            encode_string("text", -1),
+            Err(WriteError::UnsupportedCodepage { codepage: -1 }),
+        ));
+    }
+
+    #[test]
+    fn negative_page_numbers_are_errors() {
+        let mut table = test_table("Broken", ObjectFlags::empty(), None, Vec::new());
+        table.header.fdp_page_number = -1;
+        table.long_value = Some(LongValueInfo {
+            table_object_id: 100,
+            long_value_id: 101,
+            fdp_page_number: -2,
+            used_pages: 0,
+            flags: ObjectFlags::empty(),
+            page_count: 0,
+            name: "LV".to_owned(),
+        });
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let error = table.read_root_page_header(source, &header).unwrap_err();
+        assert!(matches!(
+            error,
+            ReadError::WithContext { table: Some(100), source, .. }
+                if matches!(*source, ReadError::InvalidCatalogValue { value: -1, .. })
+        ));
+        assert!(table.long_value_page_number().is_err());
+
+        let index = Index {
+            table_object_id: 100,
+            index_id: 102,
+            fdp_page_number: -3,
+            used_pages: 0,
+            flags: ObjectFlags::empty(),
+            locale: 0,
+            root_flag: None,
+            record_offset: None,
+            name: "Broken_Index".to_owned(),
+            key_field_ids: None,
+            key_segments: Vec::new(),
+        };
+        assert!(matches!(
+            read_index_entries(source, &header, &index),
+            Err(ReadError::WithContext { table: Some(100), .. })
+        ));
+    }
+
+    #[test]
+    fn orphans_are_recovered_despite_negative_root_page_number() {
+        // test pages belong to object 2
+        let mut table = test_table("Broken", ObjectFlags::empty(), None, vec![
+            Column { record_offset: Some(4), ..test_column(1, DataType::Long, 4) },
+        ]);
+        table.header.fdp_object_id = 2;
+        table.header.fdp_page_number = -1;
+        let mut db = TestDb::new();
+        db.add_page(3, PageFlags::LEAF_PAGE | PageFlags::NEW_RECORD_FORMAT, &[
+            Vec::new(),
+            leaf_entry(&[0x01], &[1, 127, 9, 0, 42, 0, 0, 0, 0b0]),
+        ]);
+
+        let recovered = recover_orphan_records(db.bytes.as_slice(), &db.header, &table, &DecodeOptions::default())
+            .unwrap();
+        assert_eq!(recovered.rows.len(), 1);
+        assert_eq!(recovered.rows[0][&1], Value::Simple(Data::Long(42)));
+        assert!(recovered.warnings.iter().any(|w| matches!(
+            w,
+            ReadError::WithContext { source, .. } if matches!(**source, ReadError::InvalidCatalogValue { value: -1, .. })
+        )));
+
+        table.header.fdp_object_id = -2;
+        assert!(recover_orphan_records(db.bytes.as_slice(), &db.header, &table, &DecodeOptions::default()).is_err());
+    }
+
+    /// A record with only the given tagged column, whose item starts with the given extended flags.
+    fn tagged_record(column_id: u16, flags: TagFlags, item: &[u8]) -> Vec<u8> {
+        // no fixed columns, no variable columns, fixed values end right after the header
+        let mut record = vec![0, 127, 4, 0];
+        record.extend_from_slice(&column_id.to_le_bytes());
+        record.extend_from_slice(&(4 | SmallTagFlags::HAS_EXTENDED_FLAGS.bits()).to_le_bytes());
+        record.push(flags.bits());
+        record.extend_from_slice(item);
+        record
+    }
+
+    #[test]
+    fn separated_compressed_value_is_decompressed() {
+        // "ab" compressed with 7-bit ASCII
+        let db = long_value_tree_db(b"unrelated", &[0x0D, 0x61, 0x31]);
+        let columns = [test_column(256, DataType::LongText, 0)];
+        let record = tagged_record(256, TagFlags::SEPARATED | TagFlags::COMPRESSED, &2u32.to_le_bytes());
+
+        let row = decode_row_with(
+            db.bytes.as_slice(), &db.header, &record, &DecodedColumns::new(&columns), db.header.page_size, Some(10),
+        ).unwrap();
+        assert_eq!(row[&256].single(), Some(&Data::LongText("ab".to_owned())));
+        assert!(matches!(row[&256].compression(), Some(CompressionScheme::SevenBitAscii)));
+    }
+
+    #[test]
+    fn separated_multi_valued_compressed_values_are_decompressed() {
+        // two values, "ab" and "ba", each compressed with 7-bit ASCII
+        let multi_value = [0x04, 0x00, 0x07, 0x00, 0x0D, 0x61, 0x31, 0x0D, 0xE2, 0x30];
+        let db = long_value_tree_db(b"unrelated", &multi_value);
+        let columns = [test_column(256, DataType::LongText, 0)];
+        let flags = TagFlags::SEPARATED | TagFlags::COMPRESSED | TagFlags::MULTI_VALUES;
+        let record = tagged_record(256, flags, &2u32.to_le_bytes());
+
+        let row = decode_row_with(
+            db.bytes.as_slice(), &db.header, &record, &DecodedColumns::new(&columns), db.header.page_size, Some(10),
+        ).unwrap();
+        assert_eq!(
+            row[&256].to_data_vec(),
+            vec![&Data::LongText("ab".to_owned()), &Data::LongText("ba".to_owned())],
+        );
+        assert!(matches!(row[&256].compression(), Some(CompressionScheme::SevenBitAscii)));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_decoding_matches_sequential_decoding() {
+        let columns = [
+            Column { record_offset: Some(4), ..test_column(1, DataType::Long, 4) },
+            test_column(256, DataType::LongText, 0),
+        ];
+        // a data tree rooted at page 1 with three leaves; the first row of each leaf refers to a
+        // long value
+        let mut db = long_value_tree_db(b"first value", b"second value");
+        let mut root_entries = vec![vec![0u8; 16]];
+        for leaf_index in 0..3u8 {
+            let mut leaf_entries = vec![Vec::new()];
+            for row_index in 0..4u8 {
+                let record = if row_index == 0 {
+                    let long_value_id = u32::from(leaf_index % 2 + 1);
+                    tagged_record(256, TagFlags::SEPARATED, &long_value_id.to_le_bytes())
+                } else {
+                    let mut values = BTreeMap::new();
+                    values.insert(1, Value::Simple(Data::Long((10 * leaf_index + row_index).into())));
+                    values.insert(256, Value::Simple(Data::LongText(format!("row {}", row_index))));
+                    crate::encode::encode_row(&values, &columns, db.header.page_size).unwrap()
+                };
+                leaf_entries.push(leaf_entry(&[leaf_index, row_index], &record));
+            }
+            let leaf_page_number = u32::from(leaf_index) + 2;
+            db.add_page(leaf_page_number, PageFlags::LEAF_PAGE | PageFlags::NEW_RECORD_FORMAT, &leaf_entries);
+            let last_key = if leaf_index < 2 { vec![leaf_index, 3] } else { Vec::new() };
+            root_entries.push(branch_entry(&last_key, leaf_page_number));
+        }
+        db.add_page(1, PageFlags::ROOT_PAGE | PageFlags::BRANCH_PAGE, &root_entries);
+
+        let source = db.bytes.as_slice();
+        let sequential = read_table_from_pages(source, &db.header, 1, &columns, Some(10)).unwrap();
+        let parallel = read_table_from_pages_parallel(source, &db.header, 1, &columns, Some(10)).unwrap();
+        assert_eq!(sequential.len(), 12);
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel[4][&256].single(), Some(&Data::LongText("second value".to_owned())));
+        assert_eq!(parallel[11][&1], Value::Simple(Data::Long(23)));
+    }
+
+    #[test]
+    fn long_value_lookup_skips_preceding_subtrees() {
+        let db = long_value_tree_db(b"first value", b"second value");
+
+        let source = RecordingSource::new(&db);
+        let value = read_long_value(&source, &db.header, 10, &2u32.to_le_bytes()).unwrap();
+        assert_eq!(value, b"second value");
+        assert_eq!(source.pages_read(), BTreeSet::from([10, 12]));
+
+        let source = RecordingSource::new(&db);
+        let value = read_long_value(&source, &db.header, 10, &1u32.to_le_bytes()).unwrap();
+        assert_eq!(value, b"first value");
+        // the walk only stops on reaching the first key past the long value, which is on page 12
+        assert_eq!(source.pages_read(), BTreeSet::from([10, 11, 12]));
+    }
+
+    #[test]
+    fn separated_super_long_value() {
+        let db = long_value_tree_db(b"unrelated", b"a super long value");
+        let columns = [test_column(256, DataType::SuperLongValue, 0)];
+        let record = tagged_record(256, TagFlags::SEPARATED, &2u32.to_le_bytes());
+
+        let row = decode_row_with(
+            db.bytes.as_slice(), &db.header, &record, &DecodedColumns::new(&columns), db.header.page_size, Some(10),
+        ).unwrap();
+        assert_eq!(row[&256].single(), Some(&Data::SuperLongValue(b"a super long value".to_vec())));
+    }
+
+    #[test]
+    fn record_decodes_from_its_bytes_alone() {
+        let columns = [test_column(256, DataType::SuperLongValue, 0)];
+        let header = crate::testing::test_header(8 * 1024);
+        let decoded_columns = DecodedColumns::new(&columns);
+
+        let record = tagged_record(256, TagFlags::empty(), b"short enough");
+        let row = decode_record(&header, &record, &decoded_columns, 8 * 1024, &DecodeOptions::default()).unwrap();
+        assert!(row.errors.is_empty());
+        assert_eq!(row.values[&256].single(), Some(&Data::SuperLongValue(b"short enough".to_vec())));
+
+        let separated = tagged_record(256, TagFlags::SEPARATED, &2u32.to_le_bytes());
+        assert!(matches!(
+            decode_record(&header, &separated, &decoded_columns, 8 * 1024, &DecodeOptions::default()),
+            Err(ReadError::SeparatedValueWithoutLongValueInfo),
+        ));
+    }
+
+    #[test]
+    fn intrinsic_super_long_value() {
+        let columns = [test_column(256, DataType::SuperLongValue, 0)];
+        let record = tagged_record(256, TagFlags::empty(), b"short enough");
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let row = decode_row_with(source, &header, &record, &DecodedColumns::new(&columns), 8 * 1024, None).unwrap();
+        assert_eq!(row[&256].single(), Some(&Data::SuperLongValue(b"short enough".to_vec())));
+    }
+
+    #[test]
+    fn two_valued_guid_column_yields_both_guids() {
+        let columns = [test_column(256, DataType::Guid, 16)];
+        let first = Uuid::from_u128(0x00112233_4455_6677_8899_AABBCCDDEEFF);
+        let second = Uuid::from_u128(0xFFEEDDCC_BBAA_9988_7766_554433221100);
+        let mut item = vec![16];
+        item.extend_from_slice(&first.to_bytes_le());
+        item.extend_from_slice(&second.to_bytes_le());
+        let record = tagged_record(256, TagFlags::TWO_VALUES, &item);
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let row = decode_row(source, &header, &record, &columns, 8 * 1024, None).unwrap();
+        assert!(matches!(row[&256], Value::Multiple { .. }));
+        assert_eq!(row[&256].to_data_vec(), vec![&Data::Guid(first), &Data::Guid(second)]);
+    }
+
+    #[test]
+    fn two_valued_column_with_short_first_value_fails() {
+        let columns = [test_column(256, DataType::Guid, 16)];
+        // the first value claims 16 bytes but only 4 follow
+        let record = tagged_record(256, TagFlags::TWO_VALUES, &[16, 1, 2, 3, 4]);
+
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let result = decode_row(source, &header, &record, &columns, 8 * 1024, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn lenient_decoding_keeps_columns_around_bad_one() {
+        let columns = [
+            Column { record_offset: Some(4), ..test_column(1, DataType::Long, 4) },
+            test_column(128, DataType::Binary, 0),
+            test_column(256, DataType::Long, 4),
+            test_column(257, DataType::LongText, 0),
+        ];
+        let record = [
+            1, 128, 9, 0, // header: last fixed column 1, last variable column 128, end of fixed values
+            7, 0, 0, 0, // column 1
+            0b0, // nullity bitmap
+            2, 0, // end of column 128
+            b'a', b'b', // column 128
+            0x00, 0x01, 0x08, 0x00, // tag of column 256, value at offset 8 of the tagged area
+            0x01, 0x01, 0x0B, 0x00, // tag of column 257, value at offset 11
+            1, 2, 3, // column 256: three bytes cannot be a Long
+            b'h', b'i', // column 257
+        ];
+        let header = crate::testing::test_header(8 * 1024);
+        let source: &[u8] = &[];
+        let decode = |strict| {
+            let options = DecodeOptions {
+                strict,
+                ..DecodeOptions::default()
+            };
+            decode_row_with_options(source, &header, &record, &DecodedColumns::new(&columns), 8 * 1024, None, &options)
+        };
+
+        assert!(decode(true).is_err());
+
+        let row = decode(false).unwrap();
+        assert_eq!(row.values.len(), 4);
+        assert_eq!(row.values[&1], Value::Simple(Data::Long(7)));
+        // the bad value is passed on undecoded
+        assert_eq!(row.values[&256].single(), Some(&Data::Other(DataType::Long.into(), vec![1, 2, 3])));
+        assert_eq!(row.values[&128], Value::Simple(Data::Binary(b"ab".to_vec())));
+        assert_eq!(row.values[&257].single(), Some(&Data::LongText("hi".to_owned())));
+        assert_eq!(row.errors.len(), 1);
+        assert_eq!(row.errors[0].column_id, 256);
+    }
+}
@@ -0,0 +1,149 @@
+//! Building small databases in memory for tests and benchmarks.
+//!
+//! Only available to other crates with the `testing` feature; this is not part of the public API.
+
+
+use std::collections::BTreeSet;
+use std::io::Cursor;
+use std::sync::Mutex;
+
+use crate::common::DbTime;
+use crate::error::IoError;
+use crate::header::{Header, HEADER_SIGNATURE, parse_header};
+use crate::page::{ChecksumAndPageNumber, PageFlags, PageHeader, PageTag, PageTagFlags, write_page};
+use crate::source::DbSource;
+
+
+/// Builds a header with the given page size that passes the checks of `parse_header`.
+pub fn test_header(page_size: u32) -> Header {
+    let mut header_bytes = vec![0u8; page_size.try_into().unwrap()];
+    header_bytes[4..8].copy_from_slice(&HEADER_SIGNATURE.to_le_bytes());
+    header_bytes[236..240].copy_from_slice(&page_size.to_le_bytes());
+    let mut checksum = 0;
+    for chunk in header_bytes[8..].chunks_exact(4) {
+        checksum ^= u32::from_le_bytes(chunk.try_into().unwrap());
+    }
+    header_bytes[0..4].copy_from_slice(&checksum.to_le_bytes());
+    parse_header(&header_bytes).unwrap()
+}
+
+pub fn test_page_header(page_number: u32, tag_count: u16, flags: PageFlags) -> PageHeader {
+    PageHeader {
+        checksum_and_page_number: ChecksumAndPageNumber::V1 { xor_checksum: 0, page_number },
+        last_modification_time: DbTime { hour: 0, minute: 0, second: 0, padding: 0 },
+        prev_page_num: 0,
+        next_page_num: 0,
+        father_data_page_oid: 2,
+        available_data_size: 0,
+        available_uncommitted_data_size: 0,
+        first_available_data_offset: 0,
+        first_available_page_tag: tag_count,
+        flags,
+    }
+}
+
+/// The data of a leaf entry without a common key prefix.
+pub fn leaf_entry(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(2 + key.len() + data.len());
+    entry.extend_from_slice(&u16::try_from(key.len()).unwrap().to_le_bytes());
+    entry.extend_from_slice(key);
+    entry.extend_from_slice(data);
+    entry
+}
+
+/// The data of a leaf entry that shares the first `common_size` bytes of its key with the key
+/// prefix of its page; its tag must be flagged [`PageTagFlags::COMPRESSED`].
+pub fn compressed_leaf_entry(common_size: u16, local_key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut entry = common_size.to_le_bytes().to_vec();
+    entry.extend_from_slice(&leaf_entry(local_key, data));
+    entry
+}
+
+/// The data of a branch entry without a common key prefix.
+pub fn branch_entry(key: &[u8], child_page_number: u32) -> Vec<u8> {
+    leaf_entry(key, &child_page_number.to_le_bytes())
+}
+
+
+/// A database file with 8 KiB pages assembled in memory one page at a time.
+pub struct TestDb {
+    pub header: Header,
+    pub bytes: Vec<u8>,
+}
+impl Default for TestDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl TestDb {
+    pub fn new() -> Self {
+        Self {
+            header: test_header(8 * 1024),
+            bytes: Vec::new(),
+        }
+    }
+
+    /// Writes a page whose tags hold the given entries, in order. The first entry is the key prefix
+    /// (or, on root pages, the root page header).
+    pub fn add_page(&mut self, page_number: u32, flags: PageFlags, entries: &[Vec<u8>]) {
+        let flagged_entries: Vec<(PageTagFlags, Vec<u8>)> = entries.iter()
+            .map(|entry| (PageTagFlags::empty(), entry.clone()))
+            .collect();
+        self.add_linked_page(page_number, flags, 0, &flagged_entries);
+    }
+
+    /// Writes a page like [`add_page`](Self::add_page), but with the given tag flags and a link to
+    /// the next sibling page (0 if there is none).
+    pub fn add_linked_page(&mut self, page_number: u32, flags: PageFlags, next_page_number: u32, entries: &[(PageTagFlags, Vec<u8>)]) {
+        let mut tags = Vec::with_capacity(entries.len());
+        let mut data = Vec::new();
+        for (tag_flags, entry) in entries {
+            tags.push(PageTag {
+                value_offset: data.len().try_into().unwrap(),
+                value_size: entry.len().try_into().unwrap(),
+                flags: *tag_flags,
+                flags_in_data: false,
+            });
+            data.extend_from_slice(entry);
+        }
+        let page_header = PageHeader {
+            next_page_num: next_page_number,
+            ..test_page_header(page_number, tags.len().try_into().unwrap(), flags)
+        };
+        write_page(&mut Cursor::new(&mut self.bytes), &self.header, &page_header, &tags, &data).unwrap();
+    }
+}
+
+
+/// A source that remembers which pages have been read from it.
+pub struct RecordingSource<'a> {
+    bytes: &'a [u8],
+    page_size: u64,
+    pages_read: Mutex<BTreeSet<u64>>,
+}
+impl<'a> RecordingSource<'a> {
+    pub fn new(db: &'a TestDb) -> Self {
+        Self {
+            bytes: &db.bytes,
+            page_size: db.header.page_size.into(),
+            pages_read: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub fn pages_read(&self) -> BTreeSet<u64> {
+        self.pages_read.lock().unwrap().clone()
+    }
+}
+impl DbSource for RecordingSource<'_> {
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<(), IoError> {
+        // the header and shadow header occupy the first two pages; page number p has index p + 1
+        if let Some(page_number) = (offset / self.page_size).checked_sub(1) {
+            self.pages_read.lock().unwrap().insert(page_number);
+        }
+        self.bytes.read_at(offset, buf)
+    }
+
+    fn byte_len(&self) -> Result<u64, IoError> {
+        self.bytes.byte_len()
+    }
+}
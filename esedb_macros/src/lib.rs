@@ -24,7 +24,7 @@ pub fn derive_read_write_binary(input_tokens: TokenStream) -> TokenStream {
 
             let output = quote! {
                 impl crate::byte_io::ReadFromBytes for #name {
-                    type Error = ::std::io::Error;
+                    type Error = crate::error::IoError;
 
                     fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
                         #(
@@ -39,7 +39,7 @@ pub fn derive_read_write_binary(input_tokens: TokenStream) -> TokenStream {
                 }
 
                 impl crate::byte_io::WriteToBytes for #name {
-                    type Error = ::std::io::Error;
+                    type Error = crate::error::IoError;
 
                     fn write_to_bytes<W: crate::byte_io::ByteWrite>(&self, writer: &mut W) -> Result<(), Self::Error> {
                         #(
@@ -102,20 +102,20 @@ pub fn derive_read_write_binary(input_tokens: TokenStream) -> TokenStream {
                         type Error = crate::error::IoOrInvalidValueError;
 
                         fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
-                            let value = reader. #read_base_type_string ()?;
+                            let value = reader. #read_base_type_ident ()?;
                             match Self::try_from_repr(value) {
                                 Some(v) => Ok(v),
-                                None => Err(Self::Error::InvalidValue),
+                                None => Err(Self::Error::InvalidValue { type_name: stringify!(#name) }),
                             }
                         }
                     }
 
                     impl crate::byte_io::WriteToBytes for #name {
-                        type Error = ::std::io::Error;
+                        type Error = crate::error::IoError;
 
                         fn write_to_bytes<W: crate::byte_io::ByteWrite>(&self, writer: &mut W) -> Result<(), Self::Error> {
                             let value = self.into_repr();
-                            writer. #write_base_type_string (value)
+                            writer. #write_base_type_ident (value)
                         }
                     }
                 }
@@ -123,7 +123,7 @@ pub fn derive_read_write_binary(input_tokens: TokenStream) -> TokenStream {
                 // from_to_other
                 quote! {
                     impl crate::byte_io::ReadFromBytes for #name {
-                        type Error = ::std::io::Error;
+                        type Error = crate::error::IoError;
 
                         fn read_from_bytes<R: crate::byte_io::ByteRead>(reader: &mut R) -> Result<Self, Self::Error> {
                             let value = reader. #read_base_type_ident ()?;
@@ -132,7 +132,7 @@ pub fn derive_read_write_binary(input_tokens: TokenStream) -> TokenStream {
                     }
 
                     impl crate::byte_io::WriteToBytes for #name {
-                        type Error = ::std::io::Error;
+                        type Error = crate::error::IoError;
 
                         fn write_to_bytes<W: crate::byte_io::ByteWrite>(&self, writer: &mut W) -> Result<(), Self::Error> {
                             let value = self.to_base_type();
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use esedb::table::{Column, Value};
+
+
+/// Quotes a field if it contains a delimiter, a quote or a line break, doubling any quotes.
+pub fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Writes a record of fields, terminated by CRLF as specified by RFC 4180.
+pub fn write_csv_record<W: Write, S: AsRef<str>>(writer: &mut W, fields: &[S]) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "{}", escape_csv_field(field.as_ref()))?;
+    }
+    write!(writer, "\r\n")
+}
+
+/// Writes the names of the given columns as a header record.
+pub fn write_csv_header<W: Write>(writer: &mut W, columns: &[&Column]) -> io::Result<()> {
+    let names: Vec<&str> = columns.iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    write_csv_record(writer, &names)
+}
+
+/// Writes the values of the given columns of a row as a record; missing values are empty.
+pub fn write_csv_row<W: Write>(writer: &mut W, row: &BTreeMap<i32, Value>, columns: &[&Column]) -> io::Result<()> {
+    let fields: Vec<String> = columns.iter()
+        .map(|c| row.get(&c.column_id)
+            .map(|v| v.to_string())
+            .unwrap_or_default())
+        .collect();
+    write_csv_record(writer, &fields)
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use esedb::json::value_to_json;
+use esedb::table::{Column, RawRow, Value};
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
+
+
+/// A difference between the rows of two versions of a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RowDiff {
+    /// The primary key of the row.
+    pub key: Vec<u8>,
+    pub kind: RowDiffKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RowDiffKind {
+    /// The row only exists in the second version; contains its values keyed by column name.
+    Added(BTreeMap<String, Value>),
+
+    /// The row only exists in the first version; contains its values keyed by column name.
+    Removed(BTreeMap<String, Value>),
+
+    /// The values of some columns differ; contains the old and new values keyed by column name.
+    Changed(BTreeMap<String, (Option<Value>, Option<Value>)>),
+}
+
+
+/// Compares two versions of a table, matching rows by their primary key and columns by their name.
+///
+/// The differences are returned in key order.
+pub fn diff_rows(old_rows: &[RawRow], old_columns: &[Column], new_rows: &[RawRow], new_columns: &[Column]) -> Vec<RowDiff> {
+    let old_key_to_row: BTreeMap<&[u8], BTreeMap<String, Value>> = old_rows.iter()
+        .map(|r| (r.key.as_slice(), name_values(&r.values, old_columns)))
+        .collect();
+    let new_key_to_row: BTreeMap<&[u8], BTreeMap<String, Value>> = new_rows.iter()
+        .map(|r| (r.key.as_slice(), name_values(&r.values, new_columns)))
+        .collect();
+
+    let all_keys: BTreeSet<&[u8]> = old_key_to_row.keys()
+        .chain(new_key_to_row.keys())
+        .copied()
+        .collect();
+    let mut diffs = Vec::new();
+    for key in all_keys {
+        let kind = match (old_key_to_row.get(key), new_key_to_row.get(key)) {
+            (None, None) => unreachable!(),
+            (None, Some(new_row)) => RowDiffKind::Added(new_row.clone()),
+            (Some(old_row), None) => RowDiffKind::Removed(old_row.clone()),
+            (Some(old_row), Some(new_row)) => {
+                let column_names: BTreeSet<&String> = old_row.keys()
+                    .chain(new_row.keys())
+                    .collect();
+                let mut changes = BTreeMap::new();
+                for column_name in column_names {
+                    let old_value = old_row.get(column_name);
+                    let new_value = new_row.get(column_name);
+                    if old_value != new_value {
+                        changes.insert(column_name.clone(), (old_value.cloned(), new_value.cloned()));
+                    }
+                }
+                if changes.is_empty() {
+                    continue;
+                }
+                RowDiffKind::Changed(changes)
+            },
+        };
+        diffs.push(RowDiff {
+            key: key.to_vec(),
+            kind,
+        });
+    }
+    diffs
+}
+
+/// Keys the values of a row by column name; columns without a definition are keyed by their ID.
+fn name_values(row: &BTreeMap<i32, Value>, columns: &[Column]) -> BTreeMap<String, Value> {
+    row.iter()
+        .map(|(column_id, value)| {
+            let name = columns.iter()
+                .find(|c| c.column_id == *column_id)
+                .map(|c| c.name.clone())
+                .unwrap_or_else(|| column_id.to_string());
+            (name, value.clone())
+        })
+        .collect()
+}
+
+fn format_key(key: &[u8]) -> String {
+    key.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Prints a difference in a human-readable form.
+pub fn print_diff(diff: &RowDiff) {
+    let key = format_key(&diff.key);
+    match &diff.kind {
+        RowDiffKind::Added(row) => {
+            println!("added {}", key);
+            for (column_name, value) in row {
+                println!("  {}={}", column_name, value);
+            }
+        },
+        RowDiffKind::Removed(row) => {
+            println!("removed {}", key);
+            for (column_name, value) in row {
+                println!("  {}={}", column_name, value);
+            }
+        },
+        RowDiffKind::Changed(changes) => {
+            println!("changed {}", key);
+            for (column_name, (old_value, new_value)) in changes {
+                let old_str = old_value.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "<none>".to_owned());
+                let new_str = new_value.as_ref().map(|v| v.to_string()).unwrap_or_else(|| "<none>".to_owned());
+                println!("  {}: {} -> {}", column_name, old_str, new_str);
+            }
+        },
+    }
+}
+
+/// Converts a difference into a JSON object.
+pub fn diff_to_json(diff: &RowDiff) -> JsonValue {
+    let key = format_key(&diff.key);
+    match &diff.kind {
+        RowDiffKind::Added(row) => json!({
+            "change": "added",
+            "key": key,
+            "row": row_to_json_by_name(row),
+        }),
+        RowDiffKind::Removed(row) => json!({
+            "change": "removed",
+            "key": key,
+            "row": row_to_json_by_name(row),
+        }),
+        RowDiffKind::Changed(changes) => {
+            let mut columns = JsonMap::new();
+            for (column_name, (old_value, new_value)) in changes {
+                columns.insert(column_name.clone(), json!({
+                    "old": old_value.as_ref().map(value_to_json),
+                    "new": new_value.as_ref().map(value_to_json),
+                }));
+            }
+            json!({
+                "change": "changed",
+                "key": key,
+                "columns": columns,
+            })
+        },
+    }
+}
+
+fn row_to_json_by_name(row: &BTreeMap<String, Value>) -> JsonValue {
+    let object: JsonMap<String, JsonValue> = row.iter()
+        .map(|(column_name, value)| (column_name.clone(), value_to_json(value)))
+        .collect();
+    JsonValue::Object(object)
+}
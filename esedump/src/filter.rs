@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+use esedb::data::{Bit, Data, DataType};
+use esedb::table::{Column, Value};
+use uuid::Uuid;
+
+
+/// A condition that a row must fulfill to be output: the given column must contain the given value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnFilter {
+    pub column_id: i32,
+    pub expected: Data,
+}
+impl ColumnFilter {
+    /// Parses a `COLUMN=VALUE` condition, interpreting the value according to the column's type.
+    pub fn parse(condition: &str, columns: &[Column]) -> Result<Self, String> {
+        let Some((column_name, value_str)) = condition.split_once('=') else {
+            return Err(format!("condition {:?} is not of the form COLUMN=VALUE", condition))
+        };
+        let column = columns.iter()
+            .find(|c| c.name == column_name)
+            .ok_or_else(|| format!("column {:?} not found", column_name))?;
+        let expected = parse_data(column.column_type, value_str)?;
+        Ok(Self {
+            column_id: column.column_id,
+            expected,
+        })
+    }
+
+    /// Whether the row matches this condition. Multi-valued columns match if any of their values
+    /// match.
+    pub fn matches(&self, row: &BTreeMap<i32, Value>) -> bool {
+        let Some(value) = row.get(&self.column_id) else { return false };
+        value.to_data_vec().into_iter()
+            .any(|d| d == &self.expected)
+    }
+}
+
+
+/// Parses a string into data of the given type.
+pub fn parse_data(data_type: DataType, s: &str) -> Result<Data, String> {
+    fn parse_num<T: std::str::FromStr>(s: &str) -> Result<T, String>
+            where T::Err: std::fmt::Display {
+        s.parse().map_err(|e| format!("failed to parse {:?}: {}", s, e))
+    }
+
+    let data = match data_type {
+        DataType::Nil => {
+            if !s.is_empty() {
+                return Err(format!("Nil columns cannot contain {:?}", s));
+            }
+            Data::Nil
+        },
+        DataType::Bit => match s {
+            "1"|"true" => Data::Bit(Bit::from(true)),
+            "0"|"false" => Data::Bit(Bit::from(false)),
+            other => return Err(format!("failed to parse {:?} as a bit", other)),
+        },
+        DataType::UnsignedByte => Data::UnsignedByte(parse_num(s)?),
+        DataType::Short => Data::Short(parse_num(s)?),
+        DataType::Long => Data::Long(parse_num(s)?),
+        DataType::Currency => Data::Currency(parse_num(s)?),
+        DataType::IeeeSingle => Data::IeeeSingle(parse_num(s)?),
+        DataType::IeeeDouble => Data::IeeeDouble(parse_num(s)?),
+        DataType::DateTime => Data::DateTime(parse_num(s)?),
+        DataType::Binary => Data::Binary(parse_hex(s)?),
+        DataType::Text => Data::Text(s.to_owned()),
+        DataType::LongBinary => Data::LongBinary(parse_hex(s)?),
+        DataType::LongText => Data::LongText(s.to_owned()),
+        DataType::SuperLongValue => Data::SuperLongValue(parse_hex(s)?),
+        DataType::UnsignedLong => Data::UnsignedLong(parse_num(s)?),
+        DataType::LongLong => Data::LongLong(parse_num(s)?),
+        DataType::Guid => Data::Guid(Uuid::parse_str(s).map_err(|e| format!("failed to parse {:?}: {}", s, e))?),
+        DataType::UnsignedShort => Data::UnsignedShort(parse_num(s)?),
+        DataType::Other(code) => Data::Other(code, parse_hex(s)?),
+    };
+    Ok(data)
+}
+
+/// Parses a string of hexadecimal digit pairs into bytes.
+fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    if !s.len().is_multiple_of(2) {
+        return Err(format!("hex string {:?} has an odd number of digits", s));
+    }
+    let mut bytes = Vec::with_capacity(s.len() / 2);
+    for i in (0..s.len()).step_by(2) {
+        let byte_str = s.get(i..i+2)
+            .ok_or_else(|| format!("hex string {:?} contains non-ASCII characters", s))?;
+        let byte = u8::from_str_radix(byte_str, 16)
+            .map_err(|e| format!("failed to parse {:?} as hex: {}", byte_str, e))?;
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
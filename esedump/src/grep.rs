@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use esedb::data::Data;
+use esedb::table::{Table, Value};
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+
+/// What to search for in the values of the columns.
+#[derive(Clone, Debug)]
+pub enum Matcher {
+    Substring(String),
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+impl Matcher {
+    pub fn is_match(&self, haystack: &str) -> bool {
+        match self {
+            Self::Substring(needle) => haystack.contains(needle.as_str()),
+            #[cfg(feature = "regex")]
+            Self::Regex(regex) => regex.is_match(haystack),
+        }
+    }
+}
+
+
+/// A value matching the pattern.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GrepMatch {
+    pub table_name: String,
+
+    /// The index of the row in the table, in key order.
+    pub row_index: usize,
+
+    pub column_name: String,
+}
+
+
+/// Returns whether a piece of data matches.
+///
+/// Text is always searched. Binary data is only searched if `search_binary` is set, in which case
+/// the pattern is matched against its lowercase hexadecimal representation.
+pub fn data_matches(data: &Data, matcher: &Matcher, search_binary: bool) -> bool {
+    match data {
+        Data::Text(text)|Data::LongText(text) => matcher.is_match(text),
+        Data::Binary(bytes)|Data::LongBinary(bytes)|Data::SuperLongValue(bytes) => {
+            if !search_binary {
+                return false;
+            }
+            let hex: String = bytes.iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+            matcher.is_match(&hex)
+        },
+        _ => false,
+    }
+}
+
+/// Returns the matching values of a row.
+pub fn grep_row(
+    table: &Table,
+    row_index: usize,
+    row: &BTreeMap<i32, Value>,
+    matcher: &Matcher,
+    search_binary: bool,
+) -> Vec<GrepMatch> {
+    let mut matches = Vec::new();
+    for (column_id, value) in row {
+        let is_match = value.to_data_vec()
+            .into_iter()
+            .any(|d| data_matches(d, matcher, search_binary));
+        if !is_match {
+            continue;
+        }
+        let column_name = table.columns.iter()
+            .find(|c| c.column_id == *column_id)
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| column_id.to_string());
+        matches.push(GrepMatch {
+            table_name: table.header.name.clone(),
+            row_index,
+            column_name,
+        });
+    }
+    matches
+}
@@ -1,22 +1,67 @@
+mod csv;
+mod diff;
+mod filter;
+mod grep;
+mod page_dump;
+mod verify;
+
+
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
 use std::path::{Path, PathBuf};
 
-use clap::Parser;
-use esedb::header::read_header;
-use esedb::page::CATALOG_PAGE_NUMBER;
-use esedb::table::{collect_tables, read_table_from_pages};
+use clap::{Parser, ValueEnum};
+use esedb::data::Data;
+use esedb::header::{Header, LogTime, read_header};
+use esedb::json::row_to_json;
+use esedb::page::{btree_stats, CATALOG_PAGE_NUMBER};
+use esedb::table::{
+    collect_tables, Column, DecodeOptions, DEFAULT_MAX_VALUE_LEN, for_each_row, read_long_value_chunks,
+    read_raw_rows_from_pages, read_table_from_pages, read_table_from_pages_with_options, Table, Value,
+};
+
+use crate::csv::{write_csv_header, write_csv_row};
+use crate::diff::{diff_rows, diff_to_json, print_diff};
+use crate::filter::ColumnFilter;
+use crate::grep::{grep_row, Matcher};
+use crate::page_dump::{dump_page, hex_dump};
+use crate::verify::{print_summary, verify_pages, verify_tables, VerifySummary};
 
 
 #[derive(Parser)]
 enum Opts {
     Tables(TablesOpts),
     DumpTable(DumpTableOpts),
+    DumpPage(DumpPageOpts),
+    Indexes(IndexesOpts),
+    Header(HeaderOpts),
+    Stats(StatsOpts),
+    Diff(DiffOpts),
+    Verify(VerifyOpts),
+    LongValue(LongValueOpts),
+    Grep(GrepOpts),
+    ExportAll(ExportAllOpts),
+    #[cfg(feature = "parquet")]
+    ExportParquet(ExportParquetOpts),
 }
 impl Opts {
     pub fn db_path(&self) -> &Path {
         match self {
             Self::Tables(to) => to.db_path.as_path(),
             Self::DumpTable(dto) => dto.db_path.as_path(),
+            Self::DumpPage(dpo) => dpo.db_path.as_path(),
+            Self::Indexes(io) => io.db_path.as_path(),
+            Self::Header(ho) => ho.db_path.as_path(),
+            Self::Stats(so) => so.db_path.as_path(),
+            Self::Diff(dio) => dio.db_path.as_path(),
+            Self::Verify(vo) => vo.db_path.as_path(),
+            Self::LongValue(lvo) => lvo.db_path.as_path(),
+            Self::Grep(go) => go.db_path.as_path(),
+            Self::ExportAll(eao) => eao.db_path.as_path(),
+            #[cfg(feature = "parquet")]
+            Self::ExportParquet(epo) => epo.db_path.as_path(),
         }
     }
 }
@@ -24,12 +69,156 @@ impl Opts {
 #[derive(Parser)]
 struct TablesOpts {
     pub db_path: PathBuf,
+
+    /// Skip the internal tables of the database engine (MSys*).
+    #[arg(long)]
+    pub exclude_system: bool,
 }
 
 #[derive(Parser)]
 struct DumpTableOpts {
     pub db_path: PathBuf,
     pub table: String,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+
+    /// Only output rows where the given column has the given value. If specified multiple times,
+    /// all conditions must match.
+    #[arg(long = "where", value_name = "COLUMN=VALUE")]
+    pub where_conditions: Vec<String>,
+
+    /// Only output the given columns, in the given order.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMN,...")]
+    pub columns: Vec<String>,
+
+    /// Output 16-byte binary values in the given columns as GUIDs.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMN,...")]
+    pub guid_columns: Vec<String>,
+
+    /// Output undecodable column values as raw bytes and report them as warnings instead of
+    /// failing.
+    #[arg(long)]
+    pub lenient: bool,
+
+    /// Fail if a page of the table's tree belongs to a different object.
+    #[arg(long)]
+    pub verify_ownership: bool,
+}
+
+#[derive(Parser)]
+struct HeaderOpts {
+    pub db_path: PathBuf,
+}
+
+#[derive(Parser)]
+struct DumpPageOpts {
+    pub db_path: PathBuf,
+    pub page_number: u64,
+}
+
+#[derive(Parser)]
+struct IndexesOpts {
+    pub db_path: PathBuf,
+    pub table: String,
+}
+
+#[derive(Parser)]
+struct StatsOpts {
+    pub db_path: PathBuf,
+    pub table: String,
+}
+
+#[derive(Parser)]
+struct DiffOpts {
+    /// The older version of the database.
+    pub db_path: PathBuf,
+
+    /// The newer version of the database.
+    pub other_db_path: PathBuf,
+
+    pub table: String,
+
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(Parser)]
+struct VerifyOpts {
+    pub db_path: PathBuf,
+}
+
+#[derive(Parser)]
+struct LongValueOpts {
+    pub db_path: PathBuf,
+    pub table: String,
+
+    /// The ID of the long value, as referenced by the records of the table.
+    pub value_id: u32,
+}
+
+#[derive(Parser)]
+struct GrepOpts {
+    pub db_path: PathBuf,
+    pub pattern: String,
+
+    /// Interpret the pattern as a regular expression instead of a substring.
+    #[cfg(feature = "regex")]
+    #[arg(long)]
+    pub regex: bool,
+
+    /// Also search binary columns, matching the pattern against their lowercase hexadecimal
+    /// representation.
+    #[arg(long)]
+    pub binary: bool,
+
+    /// Also search the internal tables of the database engine (MSys*).
+    #[arg(long)]
+    pub include_system: bool,
+}
+impl GrepOpts {
+    pub fn matcher(&self) -> Matcher {
+        #[cfg(feature = "regex")]
+        if self.regex {
+            let regex = regex::Regex::new(&self.pattern)
+                .expect("failed to parse regular expression");
+            return Matcher::Regex(regex);
+        }
+        Matcher::Substring(self.pattern.clone())
+    }
+}
+
+#[derive(Parser)]
+struct ExportAllOpts {
+    pub db_path: PathBuf,
+
+    /// The directory into which to write one CSV file per table.
+    pub out_dir: PathBuf,
+
+    /// Also export the internal tables of the database engine (MSys*).
+    #[arg(long)]
+    pub include_system: bool,
+}
+
+#[cfg(feature = "parquet")]
+#[derive(Parser)]
+struct ExportParquetOpts {
+    pub db_path: PathBuf,
+    pub table: String,
+    pub out_path: PathBuf,
+
+    /// The number of rows to convert and write at once.
+    #[arg(long, default_value_t = 65536)]
+    pub batch_rows: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, ValueEnum)]
+enum OutputFormat {
+    /// One `column=value` line per column, rows separated by `---`.
+    Text,
+
+    /// One JSON object per row and line.
+    Json,
 }
 
 
@@ -42,66 +231,427 @@ fn main() {
         .init();
 
     let opts = Opts::parse();
-    let mut file = File::open(opts.db_path())
-        .expect("failed to open database file");
-    let header = read_header(&mut file)
-        .expect("failed to read database header");
-    let shadow_header = read_header(&mut file)
-        .expect("failed to read database shadow header");
-    if header != shadow_header {
-        println!("warning: shadow header mismatch");
-    }
+    let (file, header) = open_database(opts.db_path());
 
-    // read the catalog of objects
-    let naive_rows = read_table_from_pages(&mut file, &header, CATALOG_PAGE_NUMBER, &*esedb::table::METADATA_COLUMN_DEFS, None)
-        .expect("failed to read metadata table from pages");
-    let naive_tables = collect_tables(&naive_rows, &*esedb::table::METADATA_COLUMN_DEFS)
-        .expect("failed to collect tables");
-
-    // find the MSysObjects table
-    let mso = naive_tables.iter()
-        .find(|t| t.header.name == "MSysObjects")
-        .expect("MSysObjects table not found");
+    if let Opts::Header(_) = &opts {
+        // don't depend on the catalog being readable
+        print_header(&header);
+        return;
+    }
+    if let Opts::DumpPage(dump_page_opts) = &opts {
+        // don't depend on the catalog being readable
+        dump_page(&file, &header, dump_page_opts.page_number)
+            .expect("failed to dump page");
+        return;
+    }
+    if let Opts::Verify(_) = &opts {
+        // don't depend on the catalog being readable
+        let mut summary = VerifySummary::default();
+        verify_pages(&file, &header, &mut summary);
+        match try_read_catalog(&file, &header) {
+            Ok(tables) => verify_tables(&file, &header, &tables, &mut summary),
+            Err(e) => {
+                println!("failed to read catalog: {}", e);
+                summary.tree_errors += 1;
+            },
+        }
+        print_summary(&summary);
+        return;
+    }
 
-    // re-read the metadata given this definition
-    let meta_rows = read_table_from_pages(&mut file, &header, mso.header.fdp_page_number.try_into().unwrap(), &mso.columns, mso.long_value_page_number())
-        .expect("failed to read metadata table from pages");
-    let tables = collect_tables(&meta_rows, &mso.columns)
-        .expect("failed to collect tables");
+    let tables = read_catalog(&file, &header);
 
     match opts {
-        Opts::Tables(_tables_opts) => {
+        Opts::Tables(tables_opts) => {
             for table in &tables {
+                if tables_opts.exclude_system && table.is_system() {
+                    continue;
+                }
                 println!("table {:?} ({})", table.header.name, table.header.table_object_id);
                 println!("  flags {:?}", table.header.flags);
+                match table.read_root_page_header(&file, &header) {
+                    Ok(root_page_header) => {
+                        println!("  initial page count {}", root_page_header.initial_page_count());
+                        println!("  extent space {:?}", root_page_header.extent_space());
+                        println!("  space tree page {}", root_page_header.space_tree_page_number());
+                    },
+                    Err(e) => println!("  warning: failed to read root page header: {}", e),
+                }
                 for column in &table.columns {
                     println!("  column {:?} ({})", column.name, column.column_id);
                     println!("    flags {:?}", column.flags);
-                    println!("    type {:?}", column.column_type);
+                    println!("    type {}", column.column_type);
                     println!("    length {}", column.length);
                     println!("    codepage {}", column.codepage);
+                    if let Some(default_value) = &column.default_value {
+                        println!("    default {}", default_value);
+                    }
                 }
                 for index in &table.indexes {
                     println!("  index {:?} ({})", index.name, index.index_id);
                     println!("    flags {:?}", index.flags);
                 }
+                for callback in &table.callbacks {
+                    println!("  callback {:?} ({})", callback.name, callback.callback_id);
+                    println!("    type 0x{:08X}", callback.callback_type);
+                    if let Some(function) = &callback.function {
+                        println!("    function {:?}", function);
+                    }
+                }
+            }
+        },
+        Opts::Indexes(indexes_opts) => {
+            let table = tables.iter()
+                .find(|t| t.header.name == indexes_opts.table)
+                .expect("requested table not found");
+            for index in &table.indexes {
+                println!("index {:?} ({})", index.name, index.index_id);
+                println!("  flags {:?}", index.flags);
+                for segment in &index.key_segments {
+                    let column_name = segment.column(&table.columns)
+                        .map(|c| format!("{:?}", c.name))
+                        .unwrap_or_else(|| "<unknown>".to_owned());
+                    let direction = if segment.descending { "descending" } else { "ascending" };
+                    println!("  key {} ({}) {}", column_name, segment.column_id, direction);
+                }
             }
         },
+        Opts::Stats(stats_opts) => {
+            let table = tables.iter()
+                .find(|t| t.header.name == stats_opts.table)
+                .expect("requested table not found");
+            let print_stats = |name: &str, page_number: u64| {
+                let stats = btree_stats(&file, &header, page_number)
+                    .expect("failed to collect tree statistics");
+                println!("{} (root page {})", name, page_number);
+                println!("  depth {}", stats.depth);
+                println!("  branch pages {}", stats.branch_pages);
+                println!("  leaf pages {}", stats.leaf_pages);
+                println!("  entries {}", stats.entries);
+                println!("  average fill {:.1}%", stats.avg_fill * 100.0);
+            };
+            print_stats("data", table.fdp_page_number().expect("invalid FDP page number"));
+            let long_value_page_number_opt = table.long_value_page_number()
+                .expect("invalid long value page number");
+            if let Some(long_value_page_number) = long_value_page_number_opt {
+                print_stats("long values", long_value_page_number);
+            }
+            for index in &table.indexes {
+                print_stats(&format!("index {:?}", index.name), index.fdp_page_number().expect("invalid index FDP page number"));
+            }
+        },
+        Opts::LongValue(long_value_opts) => {
+            let table = tables.iter()
+                .find(|t| t.header.name == long_value_opts.table)
+                .expect("requested table not found");
+            let long_value_page_number = table.long_value_page_number()
+                .expect("invalid long value page number")
+                .expect("requested table has no long value tree");
+            let reference = long_value_opts.value_id.to_le_bytes();
+            let chunks = read_long_value_chunks(&file, &header, long_value_page_number, &reference, DEFAULT_MAX_VALUE_LEN)
+                .expect("failed to read long value");
+
+            println!("long value {} of table {:?}", long_value_opts.value_id, table.header.name);
+            match chunks.total_size {
+                Some(total_size) => println!("  announced length {}", total_size),
+                None => println!("  warning: long value root not found"),
+            }
+            println!("  chunks {}", chunks.chunks.len());
+            for (offset, chunk) in &chunks.chunks {
+                println!("    offset {} length {}", offset, chunk.len());
+            }
+            let value = chunks.reassemble();
+            println!("  total length {}", value.len());
+            print!("{}", hex_dump(&value, "  "));
+        },
+        Opts::Grep(grep_opts) => {
+            let matcher = grep_opts.matcher();
+            for table in &tables {
+                if !grep_opts.include_system && table.is_system() {
+                    continue;
+                }
+                let mut row_index = 0;
+                let res = for_each_row(
+                    &file, &header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns,
+                    table.long_value_page_number().expect("invalid long value page number"),
+                    |row| {
+                        for found in grep_row(table, row_index, row, &matcher, grep_opts.binary) {
+                            println!("{:?} row {} column {:?}", found.table_name, found.row_index, found.column_name);
+                        }
+                        row_index += 1;
+                        ControlFlow::Continue(())
+                    },
+                );
+                if let Err(e) = res {
+                    println!("warning: failed to read rows of table {:?}: {}", table.header.name, e);
+                }
+            }
+        },
+        Opts::ExportAll(export_all_opts) => {
+            std::fs::create_dir_all(&export_all_opts.out_dir)
+                .expect("failed to create output directory");
+            let mut file_names = BTreeSet::new();
+            for table in &tables {
+                if !export_all_opts.include_system && table.is_system() {
+                    continue;
+                }
+
+                // sanitization may map different table names to the same file name
+                let base_name = sanitize_file_name(&table.header.name);
+                let mut file_name = format!("{}.csv", base_name);
+                let mut counter = 2;
+                while !file_names.insert(file_name.clone()) {
+                    file_name = format!("{}_{}.csv", base_name, counter);
+                    counter += 1;
+                }
+                let path = export_all_opts.out_dir.join(&file_name);
+                eprintln!("exporting table {:?} to {}", table.header.name, path.display());
+
+                let csv_file = File::create(&path)
+                    .expect("failed to create CSV file");
+                let mut writer = BufWriter::new(csv_file);
+                let columns: Vec<&Column> = table.columns.iter().collect();
+                write_csv_header(&mut writer, &columns)
+                    .expect("failed to write CSV file");
+                let mut row_count: u64 = 0;
+                let res = for_each_row(
+                    &file, &header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns,
+                    table.long_value_page_number().expect("invalid long value page number"),
+                    |row| {
+                        write_csv_row(&mut writer, row, &columns)
+                            .expect("failed to write CSV file");
+                        row_count += 1;
+                        ControlFlow::Continue(())
+                    },
+                );
+                writer.flush()
+                    .expect("failed to write CSV file");
+                match res {
+                    Ok(()) => eprintln!("  {} rows", row_count),
+                    Err(e) => eprintln!("  warning: export incomplete after {} rows: {}", row_count, e),
+                }
+            }
+        },
+        #[cfg(feature = "parquet")]
+        Opts::ExportParquet(export_parquet_opts) => {
+            let table = tables.iter()
+                .find(|t| t.header.name == export_parquet_opts.table)
+                .expect("requested table not found");
+
+            let parquet_file = File::create(&export_parquet_opts.out_path)
+                .expect("failed to create Parquet file");
+            let schema = std::sync::Arc::new(esedb::arrow::arrow_schema(table));
+            let mut writer = parquet::arrow::ArrowWriter::try_new(parquet_file, schema, None)
+                .expect("failed to create Parquet writer");
+
+            // convert the rows in batches to keep memory usage bounded
+            let batch_rows = export_parquet_opts.batch_rows.max(1);
+            let mut rows = Vec::with_capacity(batch_rows);
+            let mut write_batch = |rows: &mut Vec<BTreeMap<i32, Value>>| {
+                let batch = esedb::arrow::to_record_batch(table, rows)
+                    .expect("failed to convert rows to Arrow");
+                writer.write(&batch)
+                    .expect("failed to write Parquet file");
+                rows.clear();
+            };
+            for_each_row(
+                &file, &header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns,
+                table.long_value_page_number().expect("invalid long value page number"),
+                |row| {
+                    rows.push(row.clone());
+                    if rows.len() >= batch_rows {
+                        write_batch(&mut rows);
+                    }
+                    ControlFlow::Continue(())
+                },
+            )
+                .expect("failed to read data rows");
+            if !rows.is_empty() {
+                write_batch(&mut rows);
+            }
+            writer.close()
+                .expect("failed to finish Parquet file");
+        },
         Opts::DumpTable(dump_table_opts) => {
             // find table
             let table = tables.iter()
                 .find(|t| t.header.name == dump_table_opts.table)
                 .expect("requested table not found");
 
-            let rows = read_table_from_pages(&mut file, &header, table.header.fdp_page_number.try_into().unwrap(), &table.columns, table.long_value_page_number())
+            let filters: Vec<ColumnFilter> = dump_table_opts.where_conditions.iter()
+                .map(|condition| ColumnFilter::parse(condition, &table.columns)
+                    .unwrap_or_else(|e| panic!("invalid condition {:?}: {}", condition, e)))
+                .collect();
+            let output_columns: Vec<&Column> = if dump_table_opts.columns.is_empty() {
+                table.columns.iter().collect()
+            } else {
+                dump_table_opts.columns.iter()
+                    .map(|name| table.columns.iter()
+                        .find(|c| &c.name == name)
+                        .unwrap_or_else(|| panic!("column {:?} not found in table {:?}", name, table.header.name)))
+                    .collect()
+            };
+
+            let guid_column_ids: Vec<i32> = dump_table_opts.guid_columns.iter()
+                .map(|name| table.columns.iter()
+                    .find(|c| &c.name == name)
+                    .map(|c| c.column_id)
+                    .unwrap_or_else(|| panic!("column {:?} not found in table {:?}", name, table.header.name)))
+                .collect();
+
+            let decode_options = DecodeOptions {
+                strict: !dump_table_opts.lenient,
+                verify_page_ownership: dump_table_opts.verify_ownership,
+                ..DecodeOptions::default()
+            };
+            let decoded = read_table_from_pages_with_options(&file, &header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns, table.long_value_page_number().expect("invalid long value page number"), &decode_options)
+                .map_err(|e| e.in_table(table.header.table_object_id))
                 .expect("failed to read data rows");
-            for row in &rows {
-                println!("---");
-                for column in &table.columns {
-                    let Some(value) = row.get(&column.column_id) else { continue };
-                    println!("{}={:?}", column.name, value);
+            for warning in &decoded.warnings {
+                println!("warning: {}", warning);
+            }
+            for mut row in decoded.rows {
+                if !filters.iter().all(|f| f.matches(&row)) {
+                    continue;
+                }
+                for column_id in &guid_column_ids {
+                    if let Some(value) = row.get_mut(column_id) {
+                        *value = value.map_data(|d| d.to_guid_lenient()
+                            .map(Data::Guid)
+                            .unwrap_or_else(|| d.clone()));
+                    }
+                }
+                let row = &row;
+
+                match dump_table_opts.format {
+                    OutputFormat::Text => {
+                        println!("---");
+                        for column in &output_columns {
+                            let Some(value) = row.get(&column.column_id) else { continue };
+                            println!("{}={}", column.name, value);
+                        }
+                    },
+                    OutputFormat::Json => {
+                        if dump_table_opts.columns.is_empty() {
+                            println!("{}", row_to_json(row, &table.columns));
+                        } else {
+                            let projected_row: BTreeMap<i32, Value> = output_columns.iter()
+                                .filter_map(|c| row.get(&c.column_id).map(|v| (c.column_id, v.clone())))
+                                .collect();
+                            println!("{}", row_to_json(&projected_row, &table.columns));
+                        }
+                    },
+                }
+            }
+        },
+        Opts::Diff(diff_opts) => {
+            let (other_file, other_header) = open_database(&diff_opts.other_db_path);
+            let other_tables = read_catalog(&other_file, &other_header);
+
+            let table = tables.iter()
+                .find(|t| t.header.name == diff_opts.table)
+                .expect("requested table not found");
+            let other_table = other_tables.iter()
+                .find(|t| t.header.name == diff_opts.table)
+                .expect("requested table not found in other database");
+
+            let rows = read_raw_rows_from_pages(&file, &header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns, table.long_value_page_number().expect("invalid long value page number"))
+                .expect("failed to read data rows");
+            let other_rows = read_raw_rows_from_pages(&other_file, &other_header, other_table.fdp_page_number().expect("invalid FDP page number"), &other_table.columns, other_table.long_value_page_number().expect("invalid long value page number"))
+                .expect("failed to read data rows of other database");
+
+            for diff in diff_rows(&rows, &table.columns, &other_rows, &other_table.columns) {
+                match diff_opts.format {
+                    OutputFormat::Text => print_diff(&diff),
+                    OutputFormat::Json => println!("{}", diff_to_json(&diff)),
                 }
             }
         },
+        Opts::DumpPage(_)|Opts::Header(_)|Opts::Verify(_) => unreachable!(),
+    }
+}
+
+
+/// Opens a database file and reads its header, warning about inconsistencies.
+fn open_database(path: &Path) -> (File, Header) {
+    let mut file = File::open(path)
+        .expect("failed to open database file");
+    let header = read_header(&mut file)
+        .expect("failed to read database header");
+    let shadow_header = read_header(&mut file)
+        .expect("failed to read database shadow header");
+    if header != shadow_header {
+        println!("warning: shadow header mismatch");
+    }
+    if header.needs_recovery() {
+        println!("warning: database was not shut down cleanly and may be inconsistent");
+    }
+    (file, header)
+}
+
+/// Reads the definitions of the tables in the database.
+fn read_catalog(file: &File, header: &Header) -> Vec<Table> {
+    try_read_catalog(file, header)
+        .expect("failed to read catalog")
+}
+
+/// Reads the definitions of the tables in the database, returning a description of the problem on
+/// failure.
+fn try_read_catalog(file: &File, header: &Header) -> Result<Vec<Table>, String> {
+    // read the catalog of objects
+    let naive_rows = read_table_from_pages(file, header, CATALOG_PAGE_NUMBER, &*esedb::table::METADATA_COLUMN_DEFS, None)
+        .map_err(|e| format!("failed to read metadata table from pages: {}", e))?;
+    let naive_tables = collect_tables(&naive_rows, &*esedb::table::METADATA_COLUMN_DEFS)
+        .map_err(|e| format!("failed to collect tables: {}", e))?;
+
+    // find the MSysObjects table
+    let mso = naive_tables.iter()
+        .find(|t| t.header.name == "MSysObjects")
+        .ok_or_else(|| "MSysObjects table not found".to_owned())?;
+
+    // re-read the metadata given this definition
+    let meta_rows = read_table_from_pages(file, header, mso.fdp_page_number().expect("invalid FDP page number"), &mso.columns, mso.long_value_page_number().expect("invalid long value page number"))
+        .map_err(|e| format!("failed to read metadata table from pages: {}", e))?;
+    collect_tables(&meta_rows, &mso.columns)
+        .map_err(|e| format!("failed to collect tables: {}", e))
+}
+
+/// Replaces the characters of a table name that are unsafe in file names with underscores.
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() {
+        "_".to_owned()
+    } else {
+        sanitized
+    }
+}
+
+
+fn print_header(header: &Header) {
+    println!("version 0x{:X} revision 0x{:X} ({})", header.version, header.format_revision, header.format_version_name());
+    println!("page size {}", header.page_size);
+    println!("state {:?}", header.db_state());
+    println!("database time {}", header.database_time.as_u64_logical());
+    if let Some((lowest, highest)) = header.required_log_generations() {
+        println!("required logs {} to {}", lowest, highest);
+    }
+    println!("created on computer {:?}", header.db_signature.computer_name_string());
+    println!("created at {}", format_log_time(&header.db_signature.creation_time()));
+    println!("consistent at {}", format_log_time(&header.consistent_timestamp));
+    println!("attached at {}", format_log_time(&header.attach_timestamp));
+    println!("detached at {}", format_log_time(&header.detach_timestamp));
+    println!("last full backup at {}", format_log_time(&header.previous_full_backup.timestamp));
+    println!("last incremental backup at {}", format_log_time(&header.previous_incremental_backup.timestamp));
+}
+
+fn format_log_time(log_time: &LogTime) -> String {
+    if log_time.is_never() {
+        return "never".to_owned();
+    }
+    match log_time.to_naive_datetime() {
+        Some(dt) => dt.to_string(),
+        None => format!("invalid ({:?})", log_time),
     }
 }
@@ -0,0 +1,116 @@
+
+use esedb::error::ReadError;
+use esedb::header::Header;
+use esedb::page::{
+    PageFlags, PageHeader, read_data_for_tag, read_page_entry, read_page_header, read_page_tags,
+    read_root_page_header,
+};
+use esedb::source::DbSource;
+
+
+/// Describes the role of a page in its tree according to its flags.
+pub fn describe_page_kind(page_header: &PageHeader) -> String {
+    let flags = page_header.flags;
+    let mut pieces = Vec::new();
+    if flags.contains(PageFlags::EMPTY_PAGE) {
+        pieces.push("empty");
+    }
+    if flags.contains(PageFlags::ROOT_PAGE) {
+        pieces.push("root");
+    }
+    if flags.contains(PageFlags::BRANCH_PAGE) {
+        pieces.push("branch");
+    }
+    if flags.contains(PageFlags::LEAF_PAGE) {
+        pieces.push("leaf");
+    }
+    if flags.contains(PageFlags::SPACE_TREE_PAGE) {
+        pieces.push("space tree");
+    } else if flags.contains(PageFlags::INDEX_PAGE) {
+        pieces.push("index");
+    } else if flags.contains(PageFlags::LONG_VALUE_PAGE) {
+        pieces.push("long value");
+    } else {
+        pieces.push("data");
+    }
+    pieces.join(" ")
+}
+
+/// Formats bytes as a classic hex dump with offsets and an ASCII column, indented by the given
+/// prefix.
+pub fn hex_dump(bytes: &[u8], indent: &str) -> String {
+    let mut ret = String::new();
+    for (line_index, chunk) in bytes.chunks(16).enumerate() {
+        ret.push_str(indent);
+        ret.push_str(&format!("{:08x} ", line_index * 16));
+        for i in 0..16 {
+            match chunk.get(i) {
+                Some(b) => ret.push_str(&format!(" {:02x}", b)),
+                None => ret.push_str("   "),
+            }
+        }
+        ret.push_str("  |");
+        for b in chunk {
+            if b.is_ascii_graphic() || *b == b' ' {
+                ret.push(char::from(*b));
+            } else {
+                ret.push('.');
+            }
+        }
+        ret.push_str("|\n");
+    }
+    ret
+}
+
+/// Prints the header, the tags and the entries of a page.
+pub fn dump_page<S: DbSource + ?Sized>(source: &S, header: &Header, page_number: u64) -> Result<(), ReadError> {
+    let page_header = read_page_header(source, header, page_number)?;
+    println!("page {} ({})", page_number, describe_page_kind(&page_header));
+    println!("  checksum and page number {:?}", page_header.checksum_and_page_number);
+    println!("  last modification time {}", page_header.last_modification_time.as_u64_logical());
+    println!("  previous page {}", page_header.prev_page_num);
+    println!("  next page {}", page_header.next_page_num);
+    println!("  father data page object ID {}", page_header.father_data_page_oid);
+    println!("  available data size {}", page_header.available_data_size);
+    println!("  available uncommitted data size {}", page_header.available_uncommitted_data_size);
+    println!("  free space {} ({:.1}% full)", page_header.free_space(), page_header.fill_percent(header.page_size));
+    println!("  first available data offset {}", page_header.first_available_data_offset);
+    println!("  first available page tag {}", page_header.first_available_page_tag);
+    println!("  flags {:?}", page_header.flags);
+
+    let page_tags = read_page_tags(source, header.page_size, &page_header)?;
+    for (tag_index, page_tag) in page_tags.iter().enumerate() {
+        println!(
+            "  tag {}: offset {} size {} flags {:?}",
+            tag_index, page_tag.value_offset, page_tag.value_size, page_tag.flags,
+        );
+
+        if tag_index == 0 {
+            // the first tag contains the root page header or the page key prefix
+            let data = read_data_for_tag(source, header.page_size, &page_header, page_tag)?;
+            if page_header.flags.contains(PageFlags::ROOT_PAGE) {
+                match read_root_page_header(&data) {
+                    Ok(root_header) => println!("    root page header {:?}", root_header),
+                    Err(e) => {
+                        println!("    undecodable root page header: {}", e);
+                        print!("{}", hex_dump(&data, "    "));
+                    },
+                }
+            } else {
+                println!("    page key prefix");
+                print!("{}", hex_dump(&data, "    "));
+            }
+            continue;
+        }
+
+        match read_page_entry(source, header.page_size, &page_header, page_tag) {
+            Ok(entry) => println!("    {:?}", entry),
+            Err(e) => {
+                println!("    undecodable entry: {}", e);
+                let data = read_data_for_tag(source, header.page_size, &page_header, page_tag)?;
+                print!("{}", hex_dump(&data, "    "));
+            },
+        }
+    }
+    Ok(())
+}
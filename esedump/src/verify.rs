@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use esedb::header::Header;
+use esedb::page::{
+    ChecksumAndPageNumber, ExtendedPageHeaderOrPageNumber, PageChecksumStatus, read_page_bytes, read_page_header,
+    verify_page_checksum,
+};
+use esedb::source::DbSource;
+use esedb::table::{DecodeOptions, read_index_entries, read_table_from_pages_with_options, Table};
+
+
+/// The results of verifying a database.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct VerifySummary {
+    pub pages_scanned: u64,
+    pub uninitialized_pages: u64,
+    pub unreadable_pages: u64,
+    pub checksum_errors: u64,
+    pub page_number_errors: u64,
+
+    /// Problems encountered while walking the trees of the tables and decoding their rows.
+    pub tree_errors: u64,
+
+    /// The object IDs of the trees containing broken pages.
+    pub broken_objects: BTreeSet<u32>,
+
+    /// The names of the tables affected by any of the problems.
+    pub affected_tables: BTreeSet<String>,
+}
+impl VerifySummary {
+    pub fn error_count(&self) -> u64 {
+        self.unreadable_pages + self.checksum_errors + self.page_number_errors + self.tree_errors
+    }
+}
+
+
+/// Verifies the checksum and the page number of every page of the database, printing each problem.
+pub fn verify_pages<S: DbSource + ?Sized>(source: &S, header: &Header, summary: &mut VerifySummary) {
+    let file_len = match source.byte_len() {
+        Ok(fl) => fl,
+        Err(e) => {
+            println!("failed to obtain file length: {}", e);
+            summary.unreadable_pages += 1;
+            return;
+        },
+    };
+    // the header and shadow header take up the first two pages
+    let last_page_number = (file_len / u64::from(header.page_size)).saturating_sub(2);
+
+    for page_number in 1..=last_page_number {
+        summary.pages_scanned += 1;
+        let page_bytes = match read_page_bytes(source, header, page_number) {
+            Ok(pb) => pb,
+            Err(e) => {
+                println!("page {}: unreadable: {}", page_number, e);
+                summary.unreadable_pages += 1;
+                continue;
+            },
+        };
+
+        let mut broken = false;
+        match verify_page_checksum(&page_bytes, page_number) {
+            Ok(PageChecksumStatus::Valid) => {},
+            Ok(PageChecksumStatus::Uninitialized) => {
+                summary.uninitialized_pages += 1;
+                continue;
+            },
+            Err(e) => {
+                println!("page {}: unreadable: {}", page_number, e);
+                summary.unreadable_pages += 1;
+                continue;
+            },
+            Ok(PageChecksumStatus::Mismatch { stored, computed }) => {
+                println!("page {}: checksum mismatch (stored 0x{:08X}, computed 0x{:08X})", page_number, stored, computed);
+                summary.checksum_errors += 1;
+                broken = true;
+            },
+        }
+
+        let page_header = match read_page_header(source, header, page_number) {
+            Ok(ph) => ph,
+            Err(e) => {
+                println!("page {}: unreadable header: {}", page_number, e);
+                summary.unreadable_pages += 1;
+                continue;
+            },
+        };
+        // small pages in the new checksum format do not store their page number
+        let stored_page_number = match page_header.checksum_and_page_number {
+            ChecksumAndPageNumber::V1 { page_number: pn, .. } => Some(u64::from(pn)),
+            ChecksumAndPageNumber::V2 { extended_header: ExtendedPageHeaderOrPageNumber::ExtendedPageHeader(eh), .. }
+                |ChecksumAndPageNumber::V3 { extended_header: ExtendedPageHeaderOrPageNumber::ExtendedPageHeader(eh), .. }
+                => Some(eh.page_number),
+            _ => None,
+        };
+        if let Some(stored_page_number) = stored_page_number {
+            if stored_page_number != page_number {
+                println!("page {}: page claims to be page {}", page_number, stored_page_number);
+                summary.page_number_errors += 1;
+                broken = true;
+            }
+        }
+
+        if broken {
+            summary.broken_objects.insert(page_header.father_data_page_oid);
+        }
+    }
+}
+
+/// Walks the trees of all tables, their long values and their indexes, printing each problem.
+///
+/// Rows are decoded leniently, so that every undecodable value is reported.
+pub fn verify_tables<S: DbSource + ?Sized>(source: &S, header: &Header, tables: &[Table], summary: &mut VerifySummary) {
+    let decode_options = DecodeOptions {
+        strict: false,
+        ..DecodeOptions::default()
+    };
+
+    let mut object_to_table: BTreeMap<u32, &str> = BTreeMap::new();
+    for table in tables {
+        let mut object_ids = vec![table.header.fdp_object_id];
+        object_ids.extend(table.indexes.iter().map(|i| i.index_id));
+        object_ids.extend(table.long_value.iter().map(|lv| lv.long_value_id));
+        for object_id in object_ids {
+            if let Ok(object_id_u32) = u32::try_from(object_id) {
+                object_to_table.insert(object_id_u32, &table.header.name);
+            }
+        }
+    }
+    for broken_object in &summary.broken_objects {
+        if let Some(table_name) = object_to_table.get(broken_object) {
+            summary.affected_tables.insert((*table_name).to_owned());
+        }
+    }
+
+    for table in tables {
+        let mut table_errors = 0;
+        let rows_res = table.long_value_page_number()
+            .and_then(|long_value_page_number| read_table_from_pages_with_options(
+                source, header, table.fdp_page_number().expect("invalid FDP page number"), &table.columns,
+                long_value_page_number, &decode_options,
+            ));
+        match rows_res {
+            Ok(decoded) => {
+                for warning in &decoded.warnings {
+                    println!("table {:?}: {}", table.header.name, warning);
+                }
+                table_errors += decoded.warnings.len();
+            },
+            Err(e) => {
+                println!("table {:?}: failed to read rows: {}", table.header.name, e);
+                table_errors += 1;
+            },
+        }
+
+        for index in &table.indexes {
+            if let Err(e) = read_index_entries(source, header, index) {
+                println!("table {:?}: failed to read index {:?}: {}", table.header.name, index.name, e);
+                table_errors += 1;
+            }
+        }
+
+        if table_errors > 0 {
+            summary.tree_errors += u64::try_from(table_errors).unwrap();
+            summary.affected_tables.insert(table.header.name.clone());
+        }
+    }
+}
+
+/// Prints the summary of a verification.
+pub fn print_summary(summary: &VerifySummary) {
+    println!("pages scanned: {}", summary.pages_scanned);
+    println!("  uninitialized: {}", summary.uninitialized_pages);
+    println!("  unreadable: {}", summary.unreadable_pages);
+    println!("  checksum errors: {}", summary.checksum_errors);
+    println!("  page number errors: {}", summary.page_number_errors);
+    println!("tree and decoding errors: {}", summary.tree_errors);
+    println!("errors found: {}", summary.error_count());
+    if summary.affected_tables.is_empty() {
+        println!("tables affected: none");
+    } else {
+        let table_names: Vec<String> = summary.affected_tables.iter()
+            .map(|n| format!("{:?}", n))
+            .collect();
+        println!("tables affected: {}", table_names.join(", "));
+    }
+}